@@ -11,6 +11,9 @@ impl<'a> ArgHelper<'a> {
     pub fn get_matches(&self) -> &ArgMatches {
         &self.args
     }
+    pub fn has_subcommand(&self) -> bool {
+        self.args.subcommand().is_some()
+    }
     pub fn get_argument_value<T: Clone + Send + Sync + 'static>(&self, id: &str) -> Option<T> {
         Some(self.args.get_one::<T>(id)?.clone())
     }
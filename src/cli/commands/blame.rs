@@ -0,0 +1,288 @@
+use crate::cli::*;
+use crate::git::interface::GitInterface;
+use crate::model::{BlameLine, DerivationRecord, NodePathType, QualifiedPath};
+use clap::{Arg, ArgAction, Command};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::error::Error;
+
+/// A line of a product file and the feature it comes from.
+#[derive(Clone, Debug, Serialize)]
+struct Provenance {
+    line: usize,
+    commit: String,
+    feature: String,
+    content: String,
+}
+
+/// Shown instead of a feature for commits that may come from a feature of which nothing is
+/// left, not even a backup.
+const UNKNOWN_FEATURE: &str = "unknown";
+
+/// Maps commits of a product to the features that introduced them.
+struct FeatureResolver<'a> {
+    git: &'a GitInterface,
+    product: QualifiedPath,
+    features: Vec<QualifiedPath>,
+    /// Whether some features of the derivation record cannot be resolved anymore.
+    has_unknown_features: bool,
+    cache: HashMap<String, Option<QualifiedPath>>,
+}
+
+impl<'a> FeatureResolver<'a> {
+    /// Deleted and archived features of the record are resolved through their backups and
+    /// archives.
+    fn new(
+        git: &'a GitInterface,
+        product: QualifiedPath,
+        record: DerivationRecord,
+    ) -> Result<Self, Box<dyn Error>> {
+        let mut features = Vec::new();
+        let mut has_unknown_features = false;
+        for feature in record.features() {
+            if git.get_revision(feature)?.is_some() {
+                features.push(feature.clone());
+            } else {
+                has_unknown_features = true;
+            }
+        }
+        Ok(Self {
+            git,
+            product,
+            features,
+            has_unknown_features,
+            cache: HashMap::new(),
+        })
+    }
+    /// Returns the feature that introduced `commit`: the first feature of the derivation record
+    /// whose history contains it, or the ancestor of that feature the commit originates from.
+    /// Commits that are in none of the features were made on the product itself, unless some
+    /// features cannot be resolved anymore. Then the origin is unknown and `None` is returned.
+    fn resolve(&mut self, commit: &str) -> Result<Option<QualifiedPath>, Box<dyn Error>> {
+        if let Some(feature) = self.cache.get(commit) {
+            return Ok(feature.clone());
+        }
+        let mut owner = Some(self.product.clone()).filter(|_| !self.has_unknown_features);
+        for feature in self.features.iter() {
+            if !self.git.is_ancestor(commit, feature)? {
+                continue;
+            }
+            let mut feature = feature.clone();
+            while let Some(parent) = self.git.get_model().get_parent_with_branch(&feature) {
+                if !self.git.is_ancestor(commit, &parent)? {
+                    break;
+                }
+                feature = parent;
+            }
+            owner = Some(feature);
+            break;
+        }
+        self.cache.insert(commit.to_string(), owner.clone());
+        Ok(owner)
+    }
+}
+
+fn get_provenance(
+    git: &GitInterface,
+    product: &QualifiedPath,
+    file: &str,
+) -> Result<Vec<Provenance>, Box<dyn Error>> {
+    let record = DerivationRecord::latest(&git.get_commit_history(product)?).ok_or_else(|| {
+        Box::new(CommandError::new(
+            format!("{} has no derivation record", product).as_str(),
+        )) as Box<dyn Error>
+    })?;
+    let mut resolver = FeatureResolver::new(git, product.clone(), record)?;
+    git.blame(product, file)?
+        .iter()
+        .map(|line: &BlameLine| {
+            Ok(Provenance {
+                line: line.number(),
+                commit: line.commit().clone(),
+                feature: match resolver.resolve(line.commit())? {
+                    Some(feature) => feature.to_string(),
+                    None => UNKNOWN_FEATURE.to_string(),
+                },
+                content: line.content().clone(),
+            })
+        })
+        .collect()
+}
+
+fn format_provenance(lines: &[Provenance]) -> String {
+    let feature_width = lines.iter().map(|l| l.feature.len()).max().unwrap_or(0);
+    let line_width = lines.len().to_string().len();
+    lines
+        .iter()
+        .map(|l| {
+            format!(
+                "{:<feature_width$} {} {:>line_width$}) {}",
+                l.feature,
+                &l.commit[..l.commit.len().min(7)],
+                l.line,
+                l.content
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[derive(Clone, Debug)]
+pub struct BlameCommand;
+impl CommandDefinition for BlameCommand {
+    fn build_command(&self) -> Command {
+        Command::new("blame")
+            .about("Show the feature each line of a file on the current product comes from")
+            .disable_help_subcommand(true)
+            .arg(
+                Arg::new("file")
+                    .required(true)
+                    .help("The file to annotate, relative to the root of the repository"),
+            )
+            .arg(
+                Arg::new("json")
+                    .long("json")
+                    .action(ArgAction::SetTrue)
+                    .help("Print the annotations as JSON"),
+            )
+    }
+}
+impl CommandInterface for BlameCommand {
+    fn run_command(&self, context: &mut CommandContext) -> Result<(), Box<dyn Error>> {
+        let file = context
+            .arg_helper
+            .get_argument_value::<String>("file")
+            .unwrap();
+        let json = context
+            .arg_helper
+            .get_argument_value::<bool>("json")
+            .unwrap();
+        let product = match context.git.get_current_node_path()?.concretize() {
+            NodePathType::Product(path) => path.get_qualified_path(),
+            _ => {
+                return Err(Box::new(CommandError::new(
+                    "Cannot blame: Current branch is not a product branch",
+                )));
+            }
+        };
+        let lines = get_provenance(context.git, &product, &file)?;
+        if json {
+            context.log_to_stdout(serde_json::to_string_pretty(&lines)?);
+        } else {
+            context.log_to_stdout(format_provenance(&lines));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::commands::{DeriveCommand, FeatureCommand};
+    use crate::git::interface::GitPath;
+    use crate::git::interface::test_utils::{
+        commit_file_to_branch, populate_with_features, prepare_empty_git_repo,
+    };
+    use std::path::PathBuf;
+    use tempfile::TempDir;
+
+    #[test]
+    fn blame_maps_lines_to_features() {
+        let path = TempDir::new().unwrap();
+        let repo_path = PathBuf::from(path.path());
+        prepare_empty_git_repo(repo_path.clone()).unwrap();
+        commit_file_to_branch(repo_path.clone(), "main", "base", "base\n").unwrap();
+        populate_with_features(repo_path.clone()).unwrap();
+        commit_file_to_branch(repo_path.clone(), "_main/_feature/root", "root", "root\n").unwrap();
+        CommandRepository::new(
+            Box::new(FeatureCommand),
+            GitPath::CustomDirectory(repo_path.clone()),
+        )
+        .execute(ArgSource::SUPPLIED(vec!["feature", "sync", "root/foo"]))
+        .unwrap();
+        commit_file_to_branch(
+            repo_path.clone(),
+            "_main/_feature/_root/foo",
+            "file",
+            "a\nb\n",
+        )
+        .unwrap();
+        commit_file_to_branch(
+            repo_path.clone(),
+            "_main/_feature/_root/bar",
+            "bar",
+            "bar\n",
+        )
+        .unwrap();
+        CommandRepository::new(
+            Box::new(DeriveCommand),
+            GitPath::CustomDirectory(repo_path.clone()),
+        )
+        .execute(ArgSource::SUPPLIED(vec![
+            "derive", "-p", "myprod", "root/foo", "root/bar",
+        ]))
+        .unwrap();
+        commit_file_to_branch(repo_path.clone(), "_main/_product/myprod", "file", "a\nc\n")
+            .unwrap();
+
+        let interface = GitInterface::in_directory(repo_path.clone());
+        let product = QualifiedPath::from("main/product/myprod");
+        let features = |file: &str| {
+            get_provenance(&interface, &product, file)
+                .unwrap()
+                .into_iter()
+                .map(|l| l.feature)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(features("base"), vec!["main"]);
+        assert_eq!(features("root"), vec!["main/feature/root"]);
+        assert_eq!(features("bar"), vec!["main/feature/root/bar"]);
+        assert_eq!(
+            features("file"),
+            vec!["main/feature/root/foo", "main/product/myprod"]
+        );
+
+        interface.checkout(&product).unwrap();
+        let repo = CommandRepository::new(
+            Box::new(BlameCommand),
+            GitPath::CustomDirectory(repo_path.clone()),
+        );
+        repo.execute(ArgSource::SUPPLIED(vec!["blame", "file", "--json"]))
+            .unwrap();
+        assert!(
+            repo.execute(ArgSource::SUPPLIED(vec!["blame", "missing"]))
+                .is_err()
+        );
+
+        CommandRepository::new(
+            Box::new(FeatureCommand),
+            GitPath::CustomDirectory(repo_path.clone()),
+        )
+        .execute(ArgSource::SUPPLIED(vec![
+            "feature", "-D", "root/bar", "--force",
+        ]))
+        .unwrap();
+        let interface = GitInterface::in_directory(repo_path.clone());
+        let features = |file: &str| {
+            get_provenance(&interface, &product, file)
+                .unwrap()
+                .into_iter()
+                .map(|l| l.feature)
+                .collect::<Vec<_>>()
+        };
+        // resolved through the backup
+        assert_eq!(features("bar"), vec!["main/feature/root/bar"]);
+        let status = std::process::Command::new("git")
+            .args([
+                "update-ref",
+                "-d",
+                "refs/tangl/backup/heads/_main/_feature/_root/bar",
+            ])
+            .current_dir(&repo_path)
+            .status()
+            .unwrap();
+        assert!(status.success());
+        assert_eq!(features("bar"), vec![UNKNOWN_FEATURE]);
+        assert_eq!(features("root"), vec!["main/feature/root"]);
+    }
+}
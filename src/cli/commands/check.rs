@@ -1,39 +1,444 @@
+use crate::cli::completion::*;
 use crate::cli::*;
-use crate::git::conflict::ConflictChecker;
-use crate::model::QualifiedPath;
-use clap::Command;
+use crate::git::conflict::{ConflictChecker, ConflictExplanation, ConflictStatistic};
+use crate::model::{
+    AnyNodeType, ConflictRecord, ConflictStatus, HasBranchFilteringNodePathTransformer, NodePath,
+    NodePathTransformer, NodePathType, QualifiedPath,
+};
+use clap::{Arg, ArgAction, Command};
 use std::error::Error;
 
 fn no_features() -> String {
     "Nothing to check: no features in tree".to_string()
 }
 
+fn format_explanation(explanation: &ConflictExplanation) -> String {
+    let left = explanation.left.strip_n_left(2);
+    let right = explanation.right.strip_n_left(2);
+    let mut lines = Vec::new();
+    for file in explanation.files.iter() {
+        let hunks = file
+            .hunks
+            .iter()
+            .map(|(start, end)| format!("{}-{}", start, end))
+            .collect::<Vec<_>>()
+            .join(", ");
+        lines.push(format!("  {}: lines {}", file.path, hunks));
+        for (feature, commits) in [(&left, &file.left_commits), (&right, &file.right_commits)] {
+            for commit in commits.iter() {
+                let hash = &commit.hash()[..commit.hash().len().min(7)];
+                lines.push(format!("    {} {} {}", feature, hash, commit.message()));
+            }
+        }
+    }
+    lines.push(match &explanation.common_ancestor {
+        Some(ancestor) if *ancestor == explanation.left || *ancestor == explanation.right => {
+            let (ancestor, child) = if *ancestor == explanation.left {
+                (&left, &right)
+            } else {
+                (&right, &left)
+            };
+            format!(
+                "  Suggestion: resolve the conflict in {} by merging {} into it",
+                child, ancestor
+            )
+        }
+        Some(ancestor) => format!(
+            "  Suggestion: hoist the shared change into the common ancestor {}",
+            ancestor.strip_n_left(2)
+        ),
+        None => "  Suggestion: move the shared change into a new feature both features depend on"
+            .to_string(),
+    });
+    lines.join("\n")
+}
+
+fn get_checked_pair(statistic: &ConflictStatistic) -> Option<(&QualifiedPath, &QualifiedPath)> {
+    match statistic {
+        ConflictStatistic::OK((l, r))
+        | ConflictStatistic::CONFLICT((l, r))
+        | ConflictStatistic::SEMANTIC_CONFLICT((l, r)) => Some((l, r)),
+        ConflictStatistic::ERROR(_, _) => None,
+    }
+}
+
+/// Describes a status change of a pair and the commit ranges of the features that changed.
+fn format_change(previous: &ConflictRecord, current: &ConflictRecord) -> String {
+    let mut lines = vec![format!(
+        "{} and {}: {} -> {}",
+        current.get_left_path().strip_n_left(2),
+        current.get_right_path().strip_n_left(2),
+        previous.status,
+        current.status
+    )];
+    for (feature, tip) in [
+        (current.get_left_path(), &current.left_tip),
+        (current.get_right_path(), &current.right_tip),
+    ] {
+        if let Some(old_tip) = previous.get_tip(&feature)
+            && old_tip != tip
+        {
+            lines.push(format!(
+                "  {} changed in {}..{}",
+                feature.strip_n_left(2),
+                &old_tip[..old_tip.len().min(7)],
+                &tip[..tip.len().min(7)]
+            ));
+        }
+    }
+    lines.join("\n")
+}
+
 #[derive(Clone, Debug)]
 pub struct CheckCommand;
 
 impl CommandDefinition for CheckCommand {
     fn build_command(&self) -> Command {
         Command::new("check")
-            .about("Check all features for merge conflicts")
+            .about("Check features for merge conflicts")
             .disable_help_subcommand(true)
+            .arg(
+                Arg::new("features")
+                    .action(ArgAction::Append)
+                    .help("The features to check. Checks all features if omitted."),
+            )
+            .arg(
+                Arg::new("against")
+                    .long("against")
+                    .help("Only checks this feature against each of the others"),
+            )
+            .arg(
+                Arg::new("subtree")
+                    .long("subtree")
+                    .conflicts_with("features")
+                    .help("Only checks the given feature and its descendants"),
+            )
+            .arg(make_verify())
+            .arg(
+                Arg::new("explain")
+                    .long("explain")
+                    .action(ArgAction::SetTrue)
+                    .help("Shows the conflicting files and commits of every conflicting pair"),
+            )
+            .arg(
+                Arg::new("diff")
+                    .long("diff")
+                    .action(ArgAction::SetTrue)
+                    .help("Only shows the pairs whose status changed since the last check"),
+            )
+            .arg(
+                Arg::new("since")
+                    .long("since")
+                    .requires("diff")
+                    .help("Compares with the status at the given date instead of the last check"),
+            )
+    }
+}
+
+/// Returns the features with a branch in the subtree of `node_path`, including itself.
+pub fn collect_branch_features(node_path: NodePath<AnyNodeType>) -> Vec<QualifiedPath> {
+    let mut features = HasBranchFilteringNodePathTransformer::new(true)
+        .transform(std::iter::once(node_path.clone()).chain(node_path.iter_children_req()))
+        .filter(|path| matches!(path.clone().concretize(), NodePathType::Feature(_)))
+        .map(|path| path.get_qualified_path())
+        .collect::<Vec<_>>();
+    features.sort();
+    features
+}
+
+fn resolve_node(
+    feature: String,
+    context: &CommandContext,
+) -> Result<NodePath<AnyNodeType>, Box<dyn Error>> {
+    let path = context.git.get_current_area()?.get_path_to_feature_root()
+        + QualifiedPath::from(feature.clone());
+    match context.git.get_model().get_node_path(&path) {
+        Some(node_path) if matches!(node_path.clone().concretize(), NodePathType::Feature(_)) => {
+            Ok(node_path)
+        }
+        _ => Err(Box::new(CommandError::new(
+            format!("Feature {} does not exist", feature).as_str(),
+        ))),
+    }
+}
+
+fn resolve_branch_feature(
+    feature: String,
+    context: &CommandContext,
+) -> Result<QualifiedPath, Box<dyn Error>> {
+    let path = resolve_node(feature.clone(), context)?.get_qualified_path();
+    if !context.git.get_model().has_branch(&path) {
+        return Err(Box::new(CommandError::new(
+            format!("Feature {} has no branch", feature).as_str(),
+        )));
+    }
+    Ok(path)
+}
+
+fn select_features(context: &CommandContext) -> Result<Vec<QualifiedPath>, Box<dyn Error>> {
+    if let Some(features) = context.arg_helper.get_argument_values::<String>("features") {
+        return features
+            .into_iter()
+            .map(|feature| resolve_branch_feature(feature, context))
+            .collect();
+    }
+    if let Some(subtree) = context.arg_helper.get_argument_value::<String>("subtree") {
+        return Ok(collect_branch_features(resolve_node(subtree, context)?));
     }
+    Ok(match context.git.get_current_area()?.to_feature_root() {
+        Some(feature_root) => collect_branch_features(feature_root.to_any_type()),
+        None => vec![],
+    })
 }
 
 impl CommandInterface for CheckCommand {
     fn run_command(&self, context: &mut CommandContext) -> Result<(), Box<dyn Error>> {
-        let maybe_feature_root = context.git.get_current_area()?.to_feature_root();
-        if maybe_feature_root.is_some() {
-            let feature_root = maybe_feature_root.unwrap();
-            let all_features: Vec<QualifiedPath> = feature_root
-                .iter_children_req()
-                .map(|child| child.get_qualified_path())
-                .collect();
-            for statistic in ConflictChecker::new(&context.git).check(&all_features)? {
-                context.log_to_stdout(statistic);
-            }
-        } else {
+        let features = select_features(context)?;
+        let against = match context.arg_helper.get_argument_value::<String>("against") {
+            Some(feature) => Some(resolve_branch_feature(feature, context)?),
+            None => None,
+        };
+        let n_others = features
+            .iter()
+            .filter(|f| Some(*f) != against.as_ref())
+            .count();
+        if n_others == 0 || (against.is_none() && features.len() < 2) {
             context.log_to_stdout(no_features());
+            return Ok(());
+        }
+        let verify_command = context.arg_helper.get_argument_value::<String>("verify");
+        let explain = context
+            .arg_helper
+            .get_argument_value::<bool>("explain")
+            .unwrap();
+        let diff = context
+            .arg_helper
+            .get_argument_value::<bool>("diff")
+            .unwrap();
+        let since = match context.arg_helper.get_argument_value::<String>("since") {
+            Some(date) => Some(context.git.parse_date(&date)?),
+            None => None,
+        };
+        let baseline = if diff {
+            Some(context.git.read_conflict_cache()?)
+        } else {
+            None
+        };
+        let checker = ConflictChecker::new(context.git).with_verify_command(verify_command);
+        let statistics: Box<dyn Iterator<Item = ConflictStatistic>> = match &against {
+            Some(feature) => Box::new(checker.check_against(feature, &features)?),
+            None => Box::new(checker.check(&features)?),
+        };
+        let mut results = Vec::new();
+        for statistic in statistics {
+            if !diff {
+                context.log_to_stdout(statistic.to_string());
+            }
+            if let ConflictStatistic::CONFLICT((l, r)) = &statistic
+                && explain
+                && !diff
+            {
+                context.log_to_stdout(format_explanation(&checker.explain(l, r)?));
+            }
+            results.push(statistic);
+        }
+        checker.record(&results)?;
+        if let Some(baseline) = baseline {
+            let current = context.git.read_conflict_cache()?;
+            let mut n_changes = 0;
+            for (l, r) in results.iter().filter_map(get_checked_pair) {
+                let previous = match since {
+                    Some(timestamp) => baseline.get_at(l, r, timestamp),
+                    None => baseline.get(l, r),
+                };
+                if let (Some(previous), Some(record)) = (previous, current.get(l, r))
+                    && previous.status != record.status
+                {
+                    n_changes += 1;
+                    context.log_to_stdout(format_change(previous, record));
+                    if record.status == ConflictStatus::Conflict && explain {
+                        context.log_to_stdout(format_explanation(&checker.explain(l, r)?));
+                    }
+                }
+            }
+            if n_changes == 0 {
+                context.log_to_stdout("No pair of features changed its status");
+            }
         }
         Ok(())
     }
+    fn shell_complete(
+        &self,
+        completion_helper: CompletionHelper,
+        context: &mut CommandContext,
+    ) -> Result<Vec<String>, Box<dyn Error>> {
+        let maybe_feature_root = context.git.get_current_area()?.to_feature_root();
+        if maybe_feature_root.is_none() {
+            return Ok(vec![]);
+        }
+        let feature_root = maybe_feature_root.unwrap();
+        let result = match completion_helper.currently_editing() {
+            Some(value) => match value.get_id().as_str() {
+                "features" | "against" => completion_helper.complete_qualified_paths(
+                    feature_root.get_qualified_path(),
+                    HasBranchFilteringNodePathTransformer::new(true)
+                        .transform(feature_root.iter_children_req())
+                        .map(|path| path.get_qualified_path()),
+                    true,
+                ),
+                "subtree" => completion_helper.complete_qualified_paths(
+                    feature_root.get_qualified_path(),
+                    feature_root
+                        .iter_children_req()
+                        .map(|path| path.get_qualified_path()),
+                    true,
+                ),
+                _ => vec![],
+            },
+            None => vec![],
+        };
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::interface::test_utils::{
+        commit_file_to_branch, populate_with_features, prepare_empty_git_repo,
+    };
+    use crate::git::interface::{GitInterface, GitPath};
+    use crate::model::ConflictCache;
+    use std::path::PathBuf;
+    use std::process::Command as Process;
+    use tempfile::TempDir;
+
+    #[test]
+    fn check_selected_features() {
+        let path = TempDir::new().unwrap();
+        let repo_path = PathBuf::from(path.path());
+        prepare_empty_git_repo(repo_path.clone()).unwrap();
+        populate_with_features(repo_path.clone()).unwrap();
+        Process::new("git")
+            .args(["branch", "_main/_feature/_root/_inner/leaf"])
+            .current_dir(&repo_path)
+            .output()
+            .unwrap();
+        commit_file_to_branch(repo_path.clone(), "_main/_feature/_root/foo", "a", "foo").unwrap();
+        commit_file_to_branch(repo_path.clone(), "_main/_feature/_root/bar", "a", "bar").unwrap();
+
+        let interface = GitInterface::in_directory(repo_path.clone());
+        let root = interface
+            .get_model()
+            .get_node_path(&QualifiedPath::from("main/feature/root"))
+            .unwrap();
+        let features = collect_branch_features(root);
+        assert_eq!(features.len(), 5);
+        assert!(!features.contains(&QualifiedPath::from("main/feature/root/inner")));
+        assert!(features.contains(&QualifiedPath::from("main/feature/root/inner/leaf")));
+
+        let repo = CommandRepository::new(
+            Box::new(CheckCommand),
+            GitPath::CustomDirectory(repo_path.clone()),
+        );
+        // the checked pairs are the ones recorded in the emptied conflict cache
+        let checked_pairs = |args: Vec<&str>| {
+            interface
+                .write_conflict_cache(&ConflictCache::default())
+                .unwrap();
+            repo.execute(ArgSource::SUPPLIED(args)).unwrap();
+            let mut pairs = GitInterface::in_directory(repo_path.clone())
+                .read_conflict_cache()
+                .unwrap()
+                .iter()
+                .map(|record| {
+                    let mut pair = [&record.left, &record.right].map(|path| {
+                        QualifiedPath::from(path.clone())
+                            .strip_n_left(2)
+                            .to_string()
+                    });
+                    pair.sort();
+                    pair.join(" ")
+                })
+                .collect::<Vec<_>>();
+            pairs.sort();
+            pairs
+        };
+        assert_eq!(checked_pairs(vec!["check"]).len(), 10);
+        assert_eq!(
+            checked_pairs(vec!["check", "root/foo", "root/bar", "--explain"]),
+            vec!["root/bar root/foo"]
+        );
+        assert_eq!(
+            checked_pairs(vec!["check", "--against", "root/foo"]),
+            vec![
+                "root root/foo",
+                "root/bar root/foo",
+                "root/baz root/foo",
+                "root/foo root/inner/leaf",
+            ]
+        );
+        assert_eq!(
+            checked_pairs(vec![
+                "check",
+                "--subtree",
+                "root/inner",
+                "--against",
+                "root/baz"
+            ]),
+            vec!["root/baz root/inner/leaf"]
+        );
+        assert!(
+            repo.execute(ArgSource::SUPPLIED(vec!["check", "root/inner", "root/foo"]))
+                .is_err()
+        );
+        assert!(
+            repo.execute(ArgSource::SUPPLIED(vec![
+                "check",
+                "--against",
+                "root/missing"
+            ]))
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn check_diff_records_history() {
+        let path = TempDir::new().unwrap();
+        let repo_path = PathBuf::from(path.path());
+        prepare_empty_git_repo(repo_path.clone()).unwrap();
+        populate_with_features(repo_path.clone()).unwrap();
+        let repo = CommandRepository::new(
+            Box::new(CheckCommand),
+            GitPath::CustomDirectory(repo_path.clone()),
+        );
+        repo.execute(ArgSource::SUPPLIED(vec!["check"])).unwrap();
+        commit_file_to_branch(repo_path.clone(), "_main/_feature/_root/foo", "a", "foo").unwrap();
+        commit_file_to_branch(repo_path.clone(), "_main/_feature/_root/bar", "a", "bar").unwrap();
+        repo.execute(ArgSource::SUPPLIED(vec!["check", "--diff"]))
+            .unwrap();
+        repo.execute(ArgSource::SUPPLIED(vec![
+            "check",
+            "--diff",
+            "--since",
+            "1 hour ago",
+        ]))
+        .unwrap();
+
+        let interface = GitInterface::in_directory(repo_path);
+        let cache = interface.read_conflict_cache().unwrap();
+        let foo = QualifiedPath::from("main/feature/root/foo");
+        let bar = QualifiedPath::from("main/feature/root/bar");
+        let statuses = cache
+            .get_history(&foo, &bar)
+            .map(|record| record.status)
+            .collect::<Vec<_>>();
+        assert_eq!(statuses, vec![ConflictStatus::Ok, ConflictStatus::Conflict]);
+        // unchanged pairs are not recorded again
+        let baz = QualifiedPath::from("main/feature/root/baz");
+        assert_eq!(cache.get_history(&foo, &baz).count(), 2);
+        assert_eq!(cache.get_history(&bar, &baz).count(), 2);
+        let root = QualifiedPath::from("main/feature/root");
+        assert_eq!(cache.get_history(&root, &baz).count(), 1);
+    }
 }
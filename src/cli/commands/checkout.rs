@@ -4,6 +4,53 @@ use crate::model::QualifiedPath;
 use clap::{Arg, Command};
 use std::error::Error;
 
+/// Resolves a branch argument: paths starting with `/` are absolute, all others are relative
+/// to the current branch.
+pub fn resolve_branch(
+    branch: &str,
+    context: &CommandContext,
+) -> Result<QualifiedPath, Box<dyn Error>> {
+    let path = match branch.strip_prefix("/") {
+        Some(absolute) => QualifiedPath::from(absolute.to_string()),
+        None => context.git.get_current_qualified_path()? + QualifiedPath::from(branch.to_string()),
+    };
+    if !context.git.get_model().has_branch(&path) {
+        return Err(Box::new(CommandError::new(
+            format!("Branch {} does not exist", path).as_str(),
+        )));
+    }
+    Ok(path)
+}
+
+/// Completes a branch argument that is resolved by [`resolve_branch`].
+pub fn complete_branches(
+    completion_helper: &CompletionHelper,
+    context: &CommandContext,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    let all_branches = context.git.get_model().get_qualified_paths_with_branches();
+    let result = match completion_helper.get_last() {
+        Some(last) => {
+            if last.starts_with("/") {
+                completion_helper.complete_qualified_paths(
+                    QualifiedPath::new(),
+                    all_branches
+                        .iter()
+                        .map(|path| QualifiedPath::from("") + path.clone()),
+                    false,
+                )
+            } else {
+                completion_helper.complete_qualified_paths(
+                    context.git.get_current_qualified_path()?,
+                    all_branches.iter().map(|path| path.clone()),
+                    false,
+                )
+            }
+        }
+        None => vec![],
+    };
+    Ok(result)
+}
+
 #[derive(Clone, Debug)]
 pub struct CheckoutCommand;
 impl CommandDefinition for CheckoutCommand {
@@ -20,11 +67,7 @@ impl CommandInterface for CheckoutCommand {
             .arg_helper
             .get_argument_value::<String>("branch")
             .unwrap();
-        let full_target = if branch.starts_with("/") {
-            QualifiedPath::from(branch).strip_n_left(1)
-        } else {
-            context.git.get_current_qualified_path()? + QualifiedPath::from(branch)
-        };
+        let full_target = resolve_branch(&branch, context)?;
         let result = context.git.checkout(&full_target)?;
         context.log_from_output(&result);
         Ok(())
@@ -34,34 +77,12 @@ impl CommandInterface for CheckoutCommand {
         completion_helper: CompletionHelper,
         context: &mut CommandContext,
     ) -> Result<Vec<String>, Box<dyn Error>> {
-        let maybe_editing = completion_helper.currently_editing();
-        if maybe_editing.is_none() {
-            return Ok(vec![]);
-        }
-        let all_branches = context.git.get_model().get_qualified_paths_with_branches();
-        let result = match maybe_editing.unwrap().get_id().as_str() {
-            "branch" => match completion_helper.get_last() {
-                Some(last) => {
-                    if last.starts_with("/") {
-                        completion_helper.complete_qualified_paths(
-                            QualifiedPath::new(),
-                            all_branches
-                                .iter()
-                                .map(|path| QualifiedPath::from("") + path.clone()),
-                            false,
-                        )
-                    } else {
-                        completion_helper.complete_qualified_paths(
-                            context.git.get_current_qualified_path()?,
-                            all_branches.iter().map(|path| path.clone()),
-                            false,
-                        )
-                    }
-                }
-                None => vec![],
+        match completion_helper.currently_editing() {
+            Some(arg) => match arg.get_id().as_str() {
+                "branch" => complete_branches(&completion_helper, context),
+                _ => Ok(vec![]),
             },
-            _ => vec![],
-        };
-        Ok(result)
+            None => Ok(vec![]),
+        }
     }
 }
@@ -25,9 +25,10 @@ impl CommandInterface for HiddenCompletionCommand {
         if to_complete.is_empty() {
             return Ok(());
         }
+        let mut remaining = to_complete.clone();
         let maybe_last_child = context
             .root_command
-            .find_last_child_recursive(&mut to_complete.clone());
+            .find_last_child_recursive(&mut remaining);
         let last_item = <&str>::clone(to_complete.last().unwrap());
         match maybe_last_child {
             Some(last_child) => {
@@ -76,8 +77,14 @@ impl CommandInterface for HiddenCompletionCommand {
                         }
                     }
                 }
+                // the appendix of the completion helper starts with the name of the completed command
+                let appendix = if remaining.first() == Some(&last_child.clap_command.get_name()) {
+                    remaining
+                } else {
+                    to_complete[to_complete.len() - remaining.len() - 1..].to_vec()
+                };
                 let completion = last_child.command.shell_complete(
-                    CompletionHelper::new(&last_child.clap_command, to_complete[1..].to_vec()),
+                    CompletionHelper::new(&last_child.clap_command, appendix),
                     context,
                 )?;
                 match completion.len() {
@@ -1,13 +1,17 @@
 use crate::cli::completion::*;
 use crate::cli::*;
 use crate::git::conflict::{ConflictChecker, ConflictStatistic, ConflictStatistics};
-use crate::model::{HasBranchFilteringNodePathTransformer, NodePathTransformer, QualifiedPath};
+use crate::model::{
+    DerivationRecord, FeatureModelFormula, FeatureState, HasBranchFilteringNodePathTransformer,
+    NodePathTransformer, ProductConfig, QualifiedPath, Reason,
+};
 use clap::{Arg, ArgAction, Command};
 use colored::Colorize;
 use petgraph::algo::maximal_cliques;
 use petgraph::graph::UnGraph;
 use std::collections::HashMap;
 use std::error::Error;
+use std::fs::read_to_string;
 
 fn map_paths_to_id(
     paths: &Vec<QualifiedPath>,
@@ -63,10 +67,116 @@ fn clique_to_paths(
 }
 
 fn make_post_derivation_message(features: &Vec<QualifiedPath>) -> String {
-    let mut base = "# DO NOT EDIT OR REMOVE THIS COMMIT\nDERIVATION FINISHED\n".to_string();
-    let strings = features.iter().map(|f| f.to_string()).collect::<Vec<String>>();
-    base.push_str(strings.join("\n").as_str());
-    base
+    DerivationRecord::new(features.clone()).to_commit_message()
+}
+
+fn check_feature_states(
+    features: &[QualifiedPath],
+    context: &CommandContext,
+) -> Result<(), Box<dyn Error>> {
+    for feature in features.iter() {
+        let state = context
+            .git
+            .get_model()
+            .get_node_path(feature)
+            .and_then(|path| path.get_metadata().get_feature_metadata().get_state());
+        if context.git.get_model().is_archived(feature) || state == Some(FeatureState::Archived) {
+            return Err(Box::new(CommandError::new(
+                format!(
+                    "Cannot derive product: feature {} is archived",
+                    feature.strip_n_left(2)
+                )
+                .as_str(),
+            )));
+        }
+        if state == Some(FeatureState::Deprecated) {
+            context.log_to_stderr(format!(
+                "Warning: feature {} is deprecated",
+                feature.strip_n_left(2)
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Checks the selection against the constraints of the feature model. Ancestors of selected
+/// features are part of the product anyway, as feature branches start at their parent.
+fn validate_selection(
+    area_path: &QualifiedPath,
+    features: &mut Vec<QualifiedPath>,
+    complete: bool,
+    context: &CommandContext,
+) -> Result<(), Box<dyn Error>> {
+    let area = context.git.get_model().get_area(area_path).unwrap();
+    let feature_root = area.get_path_to_feature_root();
+    let (constraints, _) = context
+        .git
+        .read_metadata_store()?
+        .partition_constraints(&feature_root);
+    if constraints.is_empty() {
+        return Ok(());
+    }
+    let formula = FeatureModelFormula::from_area(&area, &constraints);
+    let mut selection: Vec<QualifiedPath> = Vec::new();
+    for feature in features.iter() {
+        for n in feature_root.len() + 1..=feature.len() {
+            let path = feature.strip_n_right(n);
+            if !selection.contains(&path) {
+                selection.push(path);
+            }
+        }
+    }
+    let format_reasons = |reasons: Vec<Reason>| {
+        reasons
+            .iter()
+            .map(|reason| format!("  {}", reason.to_relative_string(&feature_root)))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    if !formula.is_satisfiable(&selection) {
+        return Err(Box::new(CommandError::new(
+            format!(
+                "Cannot derive product: the selection is contradictory\n{}",
+                format_reasons(formula.explain(&selection))
+            )
+            .as_str(),
+        )));
+    }
+    let implied = formula.implied(&selection);
+    if !implied.is_empty() {
+        let names = implied
+            .iter()
+            .map(|f| f.strip_n_left(feature_root.len()).to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        if !complete {
+            return Err(Box::new(CommandError::new(
+                format!(
+                    "Cannot derive product: the selection requires {}. Use --complete to add them.",
+                    names
+                )
+                .as_str(),
+            )));
+        }
+        context.log_to_stdout(format!("Adding implied features {}", names));
+        for feature in implied {
+            if context.git.get_model().has_branch(&feature) {
+                features.push(feature.clone());
+            }
+            selection.push(feature);
+        }
+    }
+    let violated = formula.violated(&selection);
+    if !violated.is_empty() {
+        return Err(Box::new(CommandError::new(
+            format!(
+                "Cannot derive product: the selection violates\n{}",
+                format_reasons(violated)
+            )
+            .as_str(),
+        )));
+    }
+    Ok(())
 }
 
 fn make_no_conflict_log() -> String {
@@ -88,78 +198,214 @@ impl CommandDefinition for DeriveCommand {
             .arg(
                 Arg::new("features")
                     .action(ArgAction::Append)
-                    .required(true),
+                    .required_unless_present_any(["config", "all"]),
             )
             .arg(
                 Arg::new("product")
                     .short('p')
-                    .required(true)
+                    .required_unless_present_any(["config", "all"])
                     .help("Specifies the name of the resulting product branch"),
             )
+            .arg(
+                Arg::new("config")
+                    .long("config")
+                    .conflicts_with_all(["features", "product", "all"])
+                    .help("Derives the product described by a JSON product configuration file"),
+            )
+            .arg(
+                Arg::new("complete")
+                    .long("complete")
+                    .action(ArgAction::SetTrue)
+                    .help("Adds features implied by the constraints of the feature model"),
+            )
+            .arg(make_verify())
+            .arg(
+                Arg::new("all")
+                    .long("all")
+                    .action(ArgAction::SetTrue)
+                    .conflicts_with_all(["features", "product"])
+                    .help("Rebuilds every configured product"),
+            )
+    }
+}
+
+/// Derives the product described by `config`. Returns false if the selected features
+/// cannot be merged without conflicts, in which case no product is created.
+pub fn derive_product(
+    config: &ProductConfig,
+    context: &mut CommandContext,
+) -> Result<bool, Box<dyn Error>> {
+    let area_path = config.get_area_path();
+    if context.git.get_model().get_area(&area_path).is_none() {
+        return Err(Box::new(CommandError::new(
+            format!("Cannot derive product: area {} does not exist", area_path).as_str(),
+        )));
+    }
+    let naming = context.git.get_model().get_naming();
+    let target_path = config.get_product_path(naming);
+    let mut all_features = config.get_feature_paths(naming);
+    check_feature_states(&all_features, context)?;
+    validate_selection(
+        &area_path,
+        &mut all_features,
+        config.options.complete,
+        context,
+    )?;
+    let exists = context.git.get_model().has_branch(&target_path);
+    if exists && !config.options.replace {
+        return Err(Box::new(CommandError::new(
+            format!(
+                "Cannot derive product: {} already exists",
+                target_path.strip_n_left(2)
+            )
+            .as_str(),
+        )));
+    }
+
+    context.log_to_stdout("Checking for conflicts");
+    let (id_to_path, path_to_id) = map_paths_to_id(&all_features);
+    let conflicts: ConflictStatistics = ConflictChecker::new(context.git)
+        .with_verify_command(config.options.verify.clone())
+        .check(&all_features)?
+        .collect();
+    if conflicts.n_errors() > 0 {
+        return Err("Errors occurred while checking for conflicts.".into());
+    }
+    let edges = build_edges(&conflicts, &path_to_id);
+    let mut graph = UnGraph::<usize, ()>::default();
+    for id in 0..all_features.len() {
+        graph.add_node(id);
+    }
+    graph.extend_with_edges(&edges);
+    let max_clique = get_max_clique(&graph);
+    let mergeable_features = clique_to_paths(max_clique, &id_to_path);
+    if mergeable_features.len() == all_features.len() {
+        if exists {
+            context.git.delete_branch_with_backup(&target_path)?;
+        }
+        context.git.create_branch_from(&target_path, &area_path)?;
+        let worktree = context.git.create_worktree()?;
+        worktree.checkout(&target_path)?;
+        worktree.interface().merge(&all_features)?;
+        worktree
+            .interface()
+            .empty_commit(make_post_derivation_message(&all_features).as_str())?;
+        context.log_to_stdout(
+            "Derivation finished ".to_string() + make_no_conflict_log().as_str() + ".",
+        );
+        Ok(true)
+    } else {
+        context.log_to_stdout(
+            format!("Can merge {} features ", mergeable_features.len())
+                + make_no_conflict_log().as_str()
+                + ".",
+        );
+        context.log_to_stdout(
+            format!(
+                "{} features ",
+                all_features.len() - mergeable_features.len()
+            ) + make_conflict_log().as_str()
+                + ".",
+        );
+        context.log_to_stdout(
+            "A partial derivation will be performed with all conflict-free features.",
+        );
+        Ok(false)
+    }
+}
+
+fn save_product_config(
+    config: &ProductConfig,
+    context: &CommandContext,
+) -> Result<(), Box<dyn Error>> {
+    let naming = context.git.get_model().get_naming();
+    let mut store = context.git.read_product_configs()?;
+    if store.get(&config.get_product_path(naming)) != Some(config) {
+        store.set(config.clone(), naming);
+        context.git.write_product_configs(&store)?;
+    }
+    Ok(())
+}
+
+fn derive_all(context: &mut CommandContext) -> Result<(), Box<dyn Error>> {
+    let configs = context
+        .git
+        .read_product_configs()?
+        .iter()
+        .cloned()
+        .collect::<Vec<_>>();
+    if configs.is_empty() {
+        context.log_to_stdout("No products are configured");
+        return Ok(());
     }
+    let mut failed = 0;
+    for mut config in configs {
+        config.options.replace = true;
+        let product = config.get_product_path(context.git.get_model().get_naming());
+        context.log_to_stdout(format!("Rebuilding {}", product.strip_n_left(2)));
+        match derive_product(&config, context) {
+            Ok(true) => {}
+            Ok(false) => failed += 1,
+            Err(e) => {
+                context.log_to_stderr(e.to_string());
+                failed += 1;
+            }
+        }
+    }
+    if failed > 0 {
+        return Err(Box::new(CommandError::new(
+            format!("Failed to rebuild {} product(s)", failed).as_str(),
+        )));
+    }
+    Ok(())
 }
 
 impl CommandInterface for DeriveCommand {
     fn run_command(&self, context: &mut CommandContext) -> Result<(), Box<dyn Error>> {
-        let target_product_name = context
-            .arg_helper
-            .get_argument_value::<String>("product")
-            .unwrap();
-        let current_path = context.git.get_current_qualified_path()?;
-        let current_area = context.git.get_current_area()?;
-        let target_path =
-            current_area.get_path_to_product_root() + QualifiedPath::from(target_product_name);
-
-        let all_features = context
+        if context
             .arg_helper
-            .get_argument_values::<String>("features")
+            .get_argument_value::<bool>("all")
             .unwrap()
-            .into_iter()
-            .map(|e| current_area.get_path_to_feature_root() + QualifiedPath::from(e))
-            .collect::<Vec<_>>();
-
-        context.log_to_stdout("Checking for conflicts");
-        let (id_to_path, path_to_id) = map_paths_to_id(&all_features);
-        let conflicts: ConflictStatistics = ConflictChecker::new(&context.git)
-            .check(&all_features)?
-            .collect();
-        if conflicts.n_errors() > 0 {
-            return Err("Errors occurred while checking for conflicts.".into());
+        {
+            return derive_all(context);
         }
-        let edges = build_edges(&conflicts, &path_to_id);
-        let graph = UnGraph::<usize, ()>::from_edges(&edges);
-        let max_clique = get_max_clique(&graph);
-        let mergeable_features = clique_to_paths(max_clique, &id_to_path);
-        if mergeable_features.len() == all_features.len() {
-            let area_path = current_area.get_qualified_path();
-            drop(current_area);
-            context.git.checkout(&area_path)?;
-            context.git.create_branch(&target_path)?;
-            context.git.checkout(&target_path)?;
-            context.git.merge(&all_features)?;
-            context
-                .git
-                .empty_commit(make_post_derivation_message(&all_features).as_str())?;
-            context.git.checkout(&current_path)?;
-            context.log_to_stdout(
-                "Derivation finished ".to_string() + make_no_conflict_log().as_str() + ".",
-            );
-        } else {
-            context.log_to_stdout(
-                format!("Can merge {} features ", mergeable_features.len())
-                    + make_no_conflict_log().as_str()
-                    + ".",
-            );
-            context.log_to_stdout(
-                format!(
-                    "{} features ",
-                    all_features.len() - mergeable_features.len()
-                ) + make_conflict_log().as_str()
-                    + ".",
-            );
-            context.log_to_stdout(
-                "A partial derivation will be performed with all conflict-free features.",
-            )
+        let config = match context.arg_helper.get_argument_value::<String>("config") {
+            Some(file) => {
+                ProductConfig::from_json(read_to_string(&file)?.as_str()).map_err(|e| {
+                    CommandError::new(
+                        format!("Invalid product configuration {}: {}", file, e).as_str(),
+                    )
+                })?
+            }
+            None => {
+                let target_product_name = context
+                    .arg_helper
+                    .get_argument_value::<String>("product")
+                    .unwrap();
+                let features = context
+                    .arg_helper
+                    .get_argument_values::<String>("features")
+                    .unwrap()
+                    .into_iter()
+                    .map(QualifiedPath::from)
+                    .collect::<Vec<_>>();
+                ProductConfig::new(
+                    QualifiedPath::from(target_product_name),
+                    context.git.get_current_area()?.get_qualified_path(),
+                    features,
+                )
+            }
+        };
+        let mut config = config;
+        config.options.complete |= context
+            .arg_helper
+            .get_argument_value::<bool>("complete")
+            .unwrap();
+        if let Some(command) = context.arg_helper.get_argument_value::<String>("verify") {
+            config.options.verify = Some(command);
+        }
+        if derive_product(&config, context)? {
+            save_product_config(&config, context)?;
         }
         Ok(())
     }
@@ -194,8 +440,11 @@ impl CommandInterface for DeriveCommand {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::git::interface::test_utils::{populate_with_features, prepare_empty_git_repo};
+    use crate::git::interface::test_utils::{
+        commit_file_to_branch, populate_with_features, prepare_empty_git_repo,
+    };
     use crate::git::interface::{GitInterface, GitPath};
+    use crate::model::Constraint;
     use std::path::PathBuf;
     use tempfile::TempDir;
 
@@ -246,7 +495,9 @@ mod tests {
                     .unwrap()
                     .to_product(&QualifiedPath::from("myprod"))
                     .unwrap();
-                let commits = interface.get_commit_history(&product.get_qualified_path()).unwrap();
+                let commits = interface
+                    .get_commit_history(&product.get_qualified_path())
+                    .unwrap();
                 let derivation_commit = commits[0].clone();
                 assert_eq!(
                     derivation_commit.message(),
@@ -260,4 +511,189 @@ mod tests {
             Err(e) => panic!("{}", e),
         }
     }
+
+    #[test]
+    fn derivation_single_feature() {
+        let path = TempDir::new().unwrap();
+        prepare_empty_git_repo(PathBuf::from(path.path())).unwrap();
+        populate_with_features(PathBuf::from(path.path())).unwrap();
+        let repo = CommandRepository::new(
+            Box::new(DeriveCommand),
+            GitPath::CustomDirectory(PathBuf::from(path.path())),
+        );
+        repo.execute(ArgSource::SUPPLIED(vec![
+            "derive", "-p", "myprod", "root/foo",
+        ]))
+        .unwrap();
+        let interface = GitInterface::in_directory(PathBuf::from(path.path()));
+        assert!(
+            interface
+                .get_model()
+                .has_branch(&QualifiedPath::from("main/product/myprod"))
+        );
+    }
+
+    #[test]
+    fn derivation_from_config() {
+        let path = TempDir::new().unwrap();
+        let repo_path = PathBuf::from(path.path());
+        prepare_empty_git_repo(repo_path.clone()).unwrap();
+        populate_with_features(repo_path.clone()).unwrap();
+        let config_dir = TempDir::new().unwrap();
+        let config_file = config_dir.path().join("pro.json");
+        std::fs::write(
+            &config_file,
+            r#"{"product": "pro", "area": "main", "features": ["root/foo", "root/bar"]}"#,
+        )
+        .unwrap();
+        let repo = CommandRepository::new(
+            Box::new(DeriveCommand),
+            GitPath::CustomDirectory(repo_path.clone()),
+        );
+        repo.execute(ArgSource::SUPPLIED(vec![
+            "derive",
+            "--config",
+            config_file.to_str().unwrap(),
+        ]))
+        .unwrap();
+        let product = QualifiedPath::from("main/product/pro");
+        let interface = GitInterface::in_directory(repo_path.clone());
+        assert!(interface.get_model().has_branch(&product));
+        assert!(
+            interface
+                .read_product_configs()
+                .unwrap()
+                .get(&product)
+                .is_some()
+        );
+        // deriving an existing product requires the replace option
+        assert!(
+            repo.execute(ArgSource::SUPPLIED(vec![
+                "derive",
+                "--config",
+                config_file.to_str().unwrap(),
+            ]))
+            .is_err()
+        );
+
+        commit_file_to_branch(
+            repo_path.clone(),
+            "_main/_feature/_root/foo",
+            "foo.txt",
+            "foo",
+        )
+        .unwrap();
+        repo.execute(ArgSource::SUPPLIED(vec!["derive", "--all"]))
+            .unwrap();
+        let interface = GitInterface::in_directory(repo_path);
+        assert!(
+            interface
+                .get_files_managed_by_branch(&product)
+                .unwrap()
+                .contains(&"foo.txt".to_string())
+        );
+        assert_eq!(interface.get_branch_backups().unwrap(), vec![product]);
+    }
+
+    #[test]
+    fn derivation_validates_constraints() {
+        let path = TempDir::new().unwrap();
+        let repo_path = PathBuf::from(path.path());
+        prepare_empty_git_repo(repo_path.clone()).unwrap();
+        populate_with_features(repo_path.clone()).unwrap();
+        let interface = GitInterface::in_directory(repo_path.clone());
+        let mut store = interface.read_metadata_store().unwrap();
+        store.set_constraints(vec![
+            Constraint::parse("\"main/feature/root/foo\" => \"main/feature/root/baz\"").unwrap(),
+            Constraint::parse("\"main/feature/root/bar\" => !\"main/feature/root/baz\"").unwrap(),
+        ]);
+        interface.write_metadata_store(&store).unwrap();
+        let repo = CommandRepository::new(
+            Box::new(DeriveCommand),
+            GitPath::CustomDirectory(repo_path.clone()),
+        );
+        // contradictory
+        assert!(
+            repo.execute(ArgSource::SUPPLIED(vec![
+                "derive", "-p", "myprod", "root/foo", "root/bar",
+            ]))
+            .is_err()
+        );
+        // incomplete
+        assert!(
+            repo.execute(ArgSource::SUPPLIED(vec![
+                "derive", "-p", "myprod", "root/foo"
+            ]))
+            .is_err()
+        );
+        repo.execute(ArgSource::SUPPLIED(vec![
+            "derive",
+            "-p",
+            "myprod",
+            "root/foo",
+            "--complete",
+        ]))
+        .unwrap();
+        let interface = GitInterface::in_directory(repo_path);
+        let product = QualifiedPath::from("main/product/myprod");
+        let history = interface.get_commit_history(&product).unwrap();
+        assert_eq!(
+            DerivationRecord::latest(&history).unwrap().features(),
+            &vec![
+                QualifiedPath::from("main/feature/root/foo"),
+                QualifiedPath::from("main/feature/root/baz"),
+            ]
+        );
+        assert!(
+            interface
+                .read_product_configs()
+                .unwrap()
+                .get(&product)
+                .unwrap()
+                .options
+                .complete
+        );
+    }
+
+    #[test]
+    fn derivation_verifies_semantic_conflicts() {
+        let path = TempDir::new().unwrap();
+        let repo_path = PathBuf::from(path.path());
+        prepare_empty_git_repo(repo_path.clone()).unwrap();
+        populate_with_features(repo_path.clone()).unwrap();
+        commit_file_to_branch(repo_path.clone(), "_main/_feature/_root/foo", "a", "").unwrap();
+        commit_file_to_branch(repo_path.clone(), "_main/_feature/_root/bar", "b", "").unwrap();
+        let repo = CommandRepository::new(
+            Box::new(DeriveCommand),
+            GitPath::CustomDirectory(repo_path.clone()),
+        );
+        let verify = "! (test -f a && test -f b)";
+        repo.execute(ArgSource::SUPPLIED(vec![
+            "derive", "-p", "broken", "root/foo", "root/bar", "--verify", verify,
+        ]))
+        .unwrap();
+        repo.execute(ArgSource::SUPPLIED(vec![
+            "derive", "-p", "fine", "root/foo", "root/baz", "--verify", verify,
+        ]))
+        .unwrap();
+        let interface = GitInterface::in_directory(repo_path);
+        assert!(
+            !interface
+                .get_model()
+                .has_branch(&QualifiedPath::from("main/product/broken"))
+        );
+        let fine = QualifiedPath::from("main/product/fine");
+        assert!(interface.get_model().has_branch(&fine));
+        assert_eq!(
+            interface
+                .read_product_configs()
+                .unwrap()
+                .get(&fine)
+                .unwrap()
+                .options
+                .verify
+                .as_deref(),
+            Some(verify)
+        );
+    }
 }
@@ -0,0 +1,137 @@
+use crate::cli::completion::*;
+use crate::cli::*;
+use crate::model::QualifiedPath;
+use clap::{Arg, ArgAction, Command};
+use std::error::Error;
+
+/// Returns the closest ancestor of `path` that has a branch, which features are compared to.
+pub fn get_parent_branch(
+    path: &QualifiedPath,
+    context: &CommandContext,
+) -> Result<QualifiedPath, Box<dyn Error>> {
+    context
+        .git
+        .get_model()
+        .get_parent_with_branch(path)
+        .ok_or_else(|| {
+            Box::new(CommandError::new(
+                format!("{} has no parent to compare with", path).as_str(),
+            )) as Box<dyn Error>
+        })
+}
+
+#[derive(Clone, Debug)]
+pub struct DiffCommand;
+impl CommandDefinition for DiffCommand {
+    fn build_command(&self) -> Command {
+        Command::new("diff")
+            .about("Show the changes of a feature relative to its parent, or between two branches")
+            .disable_help_subcommand(true)
+            .arg(Arg::new("branch").required(true).help(
+                "The branch to compare. Relative to the current branch unless it starts with /",
+            ))
+            .arg(
+                Arg::new("other").help(
+                    "The branch to compare with. Shows the changes since the parent if omitted.",
+                ),
+            )
+            .arg(
+                Arg::new("stat")
+                    .long("stat")
+                    .action(ArgAction::SetTrue)
+                    .help("Only show a summary of the changed files"),
+            )
+    }
+}
+impl CommandInterface for DiffCommand {
+    fn run_command(&self, context: &mut CommandContext) -> Result<(), Box<dyn Error>> {
+        let branch = context
+            .arg_helper
+            .get_argument_value::<String>("branch")
+            .unwrap();
+        let stat = context
+            .arg_helper
+            .get_argument_value::<bool>("stat")
+            .unwrap();
+        let path = resolve_branch(&branch, context)?;
+        let diff = match context.arg_helper.get_argument_value::<String>("other") {
+            Some(other) => {
+                let other = resolve_branch(&other, context)?;
+                context.git.diff(&path, &other, false, stat)?
+            }
+            None => {
+                let parent = get_parent_branch(&path, context)?;
+                context.git.diff(&parent, &path, true, stat)?
+            }
+        };
+        context.log_to_stdout(diff);
+        Ok(())
+    }
+    fn shell_complete(
+        &self,
+        completion_helper: CompletionHelper,
+        context: &mut CommandContext,
+    ) -> Result<Vec<String>, Box<dyn Error>> {
+        match completion_helper.currently_editing() {
+            Some(arg) => match arg.get_id().as_str() {
+                "branch" | "other" => complete_branches(&completion_helper, context),
+                _ => Ok(vec![]),
+            },
+            None => Ok(vec![]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::interface::test_utils::{
+        commit_file_to_branch, populate_with_features, prepare_empty_git_repo,
+    };
+    use crate::git::interface::{GitInterface, GitPath};
+    use std::path::PathBuf;
+    use tempfile::TempDir;
+
+    #[test]
+    fn diff_feature_against_parent() {
+        let path = TempDir::new().unwrap();
+        let repo_path = PathBuf::from(path.path());
+        prepare_empty_git_repo(repo_path.clone()).unwrap();
+        populate_with_features(repo_path.clone()).unwrap();
+        commit_file_to_branch(repo_path.clone(), "_main/_feature/_root/foo", "foo", "foo").unwrap();
+        commit_file_to_branch(repo_path.clone(), "_main/_feature/root", "root", "root").unwrap();
+        let interface = GitInterface::in_directory(repo_path.clone());
+        interface
+            .checkout(&QualifiedPath::from("main/feature/root"))
+            .unwrap();
+
+        let root = QualifiedPath::from("main/feature/root");
+        let foo = QualifiedPath::from("main/feature/root/foo");
+        let changes = interface.diff(&root, &foo, true, true).unwrap();
+        assert!(changes.contains("foo"));
+        assert!(!changes.contains("root"));
+        let changes = interface.diff(&root, &foo, false, true).unwrap();
+        assert!(changes.contains("root"));
+
+        let repo = CommandRepository::new(
+            Box::new(DiffCommand),
+            GitPath::CustomDirectory(repo_path.clone()),
+        );
+        repo.execute(ArgSource::SUPPLIED(vec!["diff", "foo"]))
+            .unwrap();
+        repo.execute(ArgSource::SUPPLIED(vec![
+            "diff",
+            "/main/feature/root/bar",
+            "foo",
+        ]))
+        .unwrap();
+        assert!(
+            repo.execute(ArgSource::SUPPLIED(vec!["diff", "missing"]))
+                .is_err()
+        );
+        assert!(
+            repo.execute(ArgSource::SUPPLIED(vec!["diff", "/main"]))
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,556 @@
+use crate::cli::*;
+use crate::git::interface::GitInterface;
+use crate::model::*;
+use clap::{Arg, ArgAction, Command};
+use std::error::Error;
+use std::path::PathBuf;
+
+/// The scratch branch that older versions of the conflict checker left behind when they were
+/// interrupted.
+const TEMPORARY_BRANCH: &str = "tmp";
+
+/// An automatic fix of a [`Problem`]. Fixes never lose data.
+enum Fix {
+    RenameRef {
+        namespace: String,
+        from: String,
+        to: String,
+    },
+    RemoveWorktree(PathBuf),
+}
+
+impl Fix {
+    fn describe(&self) -> String {
+        match self {
+            Fix::RenameRef {
+                namespace,
+                from,
+                to,
+            } => format!("rename {}{} to {}{}", namespace, from, namespace, to),
+            Fix::RemoveWorktree(path) => format!("remove the worktree {}", path.display()),
+        }
+    }
+    fn apply(&self, git: &GitInterface) -> Result<(), Box<dyn Error>> {
+        match self {
+            Fix::RenameRef {
+                namespace,
+                from,
+                to,
+            } => git.rename_ref(namespace, from, to)?,
+            Fix::RemoveWorktree(path) => git.remove_worktree(path)?,
+        }
+        Ok(())
+    }
+}
+
+enum Remedy {
+    Fix(Fix),
+    /// Describes what the user has to do, because there is no safe automatic fix.
+    Manual(String),
+}
+
+fn manual<S: Into<String>>(instruction: S) -> Remedy {
+    Remedy::Manual(instruction.into())
+}
+
+/// A violated invariant of the repository.
+struct Problem {
+    /// Warnings describe unusual states that may be intended and do not fail the check.
+    is_warning: bool,
+    description: String,
+    remedy: Remedy,
+}
+
+impl Problem {
+    fn error<S: Into<String>>(description: S, remedy: Remedy) -> Self {
+        Self {
+            is_warning: false,
+            description: description.into(),
+            remedy,
+        }
+    }
+    fn warning<S: Into<String>>(description: S, remedy: Remedy) -> Self {
+        Self {
+            is_warning: true,
+            description: description.into(),
+            remedy,
+        }
+    }
+}
+
+/// Returns every node of the tree below the virtual root.
+fn get_all_nodes(git: &GitInterface) -> Vec<NodePath<AnyNodeType>> {
+    let mut nodes = Vec::new();
+    for area in git.get_model().get_areas() {
+        nodes.push(area.clone().to_any_type());
+        nodes.extend(area.iter_children_req());
+    }
+    nodes
+}
+
+/// Reports every ref that does not fit into the tree. Refs whose intended path can be guessed
+/// can be renamed to the well-formed name of that path.
+fn check_foreign_refs(git: &GitInterface) -> Vec<Problem> {
+    let naming = git.get_model().get_naming();
+    git.get_model()
+        .get_foreign_refs()
+        .iter()
+        .map(|foreign_ref| {
+            let description = format!(
+                "{}{} does not fit into the tree: {}",
+                foreign_ref.namespace, foreign_ref.name, foreign_ref.reason
+            );
+            let remedy = match naming.repair_ref_name(&foreign_ref.name) {
+                Some(path) => Remedy::Fix(Fix::RenameRef {
+                    namespace: foreign_ref.namespace.clone(),
+                    from: foreign_ref.name.clone(),
+                    to: naming.to_ref_name(&path),
+                }),
+                None => manual("rename or delete it, or ignore it in the naming scheme"),
+            };
+            Problem::error(description, remedy)
+        })
+        .collect()
+}
+
+fn check_temporary_refs(git: &GitInterface) -> Result<Vec<Problem>, Box<dyn Error>> {
+    let mut problems = Vec::new();
+    let temporary = QualifiedPath::from(TEMPORARY_BRANCH);
+    if git.get_model().has_branch(&temporary) {
+        // the branch may as well be an area that happens to be called tmp
+        problems.push(Problem::warning(
+            format!(
+                "{} may be a leftover of an interrupted conflict check",
+                temporary
+            ),
+            manual(format!(
+                "delete it with git branch -D {} if it is not an area",
+                TEMPORARY_BRANCH
+            )),
+        ));
+    }
+    for worktree in git.list_tangl_worktrees()? {
+        problems.push(Problem::error(
+            format!(
+                "The temporary worktree {} was not removed",
+                worktree.display()
+            ),
+            Remedy::Fix(Fix::RemoveWorktree(worktree)),
+        ));
+    }
+    Ok(problems)
+}
+
+/// Reports areas and features without a branch. Features without a branch are abstract, which
+/// may be intended, e.g. after importing a feature model.
+fn check_branchless_nodes(git: &GitInterface) -> Vec<Problem> {
+    let model = git.get_model();
+    get_all_nodes(git)
+        .into_iter()
+        .filter_map(|node| {
+            let path = node.get_qualified_path();
+            if node.get_metadata().has_branch() || model.is_archived(&path) {
+                return None;
+            }
+            match node.concretize() {
+                NodePathType::Area(_) => Some(Problem::error(
+                    format!("The area {} has no branch", path),
+                    manual("create the branch of the area"),
+                )),
+                NodePathType::Feature(_) => Some(Problem::warning(
+                    format!("{} has no branch and is treated as abstract feature", path),
+                    manual("create its branch if it is meant to be concrete"),
+                )),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Reports branches that do not contain the commit their parent branched off from its own
+/// parent, i.e. branches that were not created from their parent.
+fn check_ancestry(git: &GitInterface) -> Result<Vec<Problem>, Box<dyn Error>> {
+    let model = git.get_model();
+    let mut problems = Vec::new();
+    for node in get_all_nodes(git) {
+        let path = node.get_qualified_path();
+        let parent = match node.get_parent_with_branch() {
+            Some(parent) if node.get_metadata().has_branch() => parent,
+            _ => continue,
+        };
+        if let NodePathType::Tag(_) = node.concretize() {
+            continue;
+        }
+        let grandparent = model
+            .get_node_path(&parent)
+            .and_then(|node| node.get_parent_with_branch());
+        let descends = match &grandparent {
+            Some(grandparent) => match git.get_merge_base(&parent, grandparent)? {
+                Some(fork_point) => git.is_ancestor(&fork_point, &path)?,
+                // reported for the parent
+                None => true,
+            },
+            None => git.get_merge_base(&parent, &path)?.is_some(),
+        };
+        if !descends {
+            problems.push(Problem::error(
+                format!(
+                    "{} does not descend from the history of its parent {}",
+                    path, parent
+                ),
+                manual("rebase it onto its parent"),
+            ));
+        }
+    }
+    Ok(problems)
+}
+
+/// Reports features that lack commits of their parent feature.
+fn check_parent_commits(git: &GitInterface) -> Result<Vec<Problem>, Box<dyn Error>> {
+    let mut problems = Vec::new();
+    for node in get_all_nodes(git) {
+        let path = node.get_qualified_path();
+        let parent = match node.get_parent_with_branch() {
+            Some(parent) if node.get_metadata().has_branch() => parent,
+            _ => continue,
+        };
+        if !matches!(node.concretize(), NodePathType::Feature(_)) {
+            continue;
+        }
+        // unrelated histories are reported by check_ancestry
+        if git.get_merge_base(&parent, &path)?.is_none() {
+            continue;
+        }
+        let missing = git.count_commits_not_in(&parent, &path)?;
+        if missing > 0 {
+            problems.push(Problem::warning(
+                format!(
+                    "{} is missing {} commit(s) of its parent {}",
+                    path, missing, parent
+                ),
+                manual(format!(
+                    "merge them with: tangl feature sync {}",
+                    path.strip_n_left(2)
+                )),
+            ));
+        }
+    }
+    Ok(problems)
+}
+
+/// Reports tags that belong to a path without branch or point to a commit outside of the
+/// history of the branch they belong to.
+fn check_tags(git: &GitInterface) -> Result<Vec<Problem>, Box<dyn Error>> {
+    let model = git.get_model();
+    let mut problems = Vec::new();
+    for node in get_all_nodes(git) {
+        let tag = node.get_qualified_path();
+        if !matches!(node.concretize(), NodePathType::Tag(_)) {
+            continue;
+        }
+        let owner = tag.strip_n_right(tag.len() - 1);
+        if !model.has_branch(&owner) {
+            problems.push(Problem::error(
+                format!("The tag {} belongs to {}, which has no branch", tag, owner),
+                manual("move the tag to an existing branch"),
+            ));
+        } else if !git.is_ancestor(&git.get_tag_commit(&tag)?, &owner)? {
+            problems.push(Problem::error(
+                format!(
+                    "The tag {} points to a commit outside of the history of {}",
+                    tag, owner
+                ),
+                manual("move the tag to a commit of its branch"),
+            ));
+        }
+    }
+    Ok(problems)
+}
+
+/// Reports products derived from or configured with features that do not exist anymore.
+fn check_products(git: &GitInterface) -> Result<Vec<Problem>, Box<dyn Error>> {
+    let model = git.get_model();
+    let exists = |feature: &QualifiedPath| model.has_branch(feature) || model.is_archived(feature);
+    let mut problems = Vec::new();
+    for area in model.get_areas() {
+        for (product, record) in git.get_derivation_records(&area)? {
+            for feature in record.features().iter().filter(|f| !exists(f)) {
+                problems.push(Problem::error(
+                    format!(
+                        "{} was derived from the deleted feature {}",
+                        product, feature
+                    ),
+                    manual("restore the feature or derive the product without it"),
+                ));
+            }
+        }
+    }
+    for config in git.read_product_configs()?.iter() {
+        let product = config.get_product_path(model.get_naming());
+        for feature in config.get_feature_paths(model.get_naming()) {
+            if !exists(&feature) {
+                problems.push(Problem::error(
+                    format!(
+                        "The configuration of {} selects the deleted feature {}",
+                        product, feature
+                    ),
+                    manual("restore the feature or remove it from the configuration"),
+                ));
+            }
+        }
+    }
+    Ok(problems)
+}
+
+/// Reports metadata and constraints of the persisted model that do not match the branches.
+fn check_persisted_model(git: &GitInterface) -> Result<Vec<Problem>, Box<dyn Error>> {
+    let model = git.get_model();
+    let store = git.read_metadata_store()?;
+    let backups = git.get_branch_backups()?;
+    let mut problems = Vec::new();
+    for (path, metadata) in store.iter() {
+        if model.get_node_path(&path).is_none() {
+            // deleted features keep their metadata until they are restored
+            if !backups.iter().any(|backup| backup.has_prefix(&path)) {
+                problems.push(Problem::error(
+                    format!("Metadata is stored for {}, which does not exist", path),
+                    // the feature may have been renamed, removing it would lose the metadata
+                    manual(
+                        "restore the feature, or transfer its metadata to its new name with tangl feature set",
+                    ),
+                ));
+            }
+        } else if metadata.get_state() == Some(FeatureState::Archived) && model.has_branch(&path) {
+            problems.push(Problem::error(
+                format!(
+                    "{} is marked as archived, but its branch was not archived",
+                    path
+                ),
+                manual("archive it with tangl feature archive"),
+            ));
+        }
+    }
+    for constraint in store.get_constraints() {
+        for feature in constraint.features() {
+            let path = QualifiedPath::from(feature.clone());
+            if model.get_node_path(&path).is_none() {
+                problems.push(Problem::error(
+                    format!(
+                        "The constraint {} references {}, which does not exist",
+                        constraint, path
+                    ),
+                    manual("restore the feature or remove the constraint"),
+                ));
+            }
+        }
+    }
+    Ok(problems)
+}
+
+fn find_problems(git: &GitInterface) -> Result<Vec<Problem>, Box<dyn Error>> {
+    let mut problems = check_foreign_refs(git);
+    problems.extend(check_temporary_refs(git)?);
+    problems.extend(check_branchless_nodes(git));
+    problems.extend(check_ancestry(git)?);
+    problems.extend(check_parent_commits(git)?);
+    problems.extend(check_tags(git)?);
+    problems.extend(check_products(git)?);
+    problems.extend(check_persisted_model(git)?);
+    Ok(problems)
+}
+
+#[derive(Clone, Debug)]
+pub struct DoctorCommand;
+
+impl CommandDefinition for DoctorCommand {
+    fn build_command(&self) -> Command {
+        Command::new("doctor")
+            .about("Checks the consistency of refs, branches and the persisted model")
+            .disable_help_subcommand(true)
+            .arg(
+                Arg::new("fix")
+                    .long("fix")
+                    .action(ArgAction::SetTrue)
+                    .help("Fixes all problems that can be fixed without losing data"),
+            )
+    }
+}
+
+impl CommandInterface for DoctorCommand {
+    fn run_command(&self, context: &mut CommandContext) -> Result<(), Box<dyn Error>> {
+        let fix = context
+            .arg_helper
+            .get_argument_value::<bool>("fix")
+            .unwrap();
+        let problems = find_problems(context.git)?;
+        if problems.is_empty() {
+            context.log_to_stdout("No problems found");
+            return Ok(());
+        }
+        let mut remaining = 0;
+        for problem in problems.iter() {
+            if problem.is_warning {
+                context.log_to_stdout(format!("Warning: {}", problem.description));
+            } else {
+                context.log_to_stdout(problem.description.clone());
+            }
+            match &problem.remedy {
+                Remedy::Fix(problem_fix) if fix => {
+                    problem_fix.apply(context.git)?;
+                    context.log_to_stdout(format!("  Fixed: {}", problem_fix.describe()));
+                }
+                Remedy::Fix(problem_fix) => {
+                    remaining += 1;
+                    context.log_to_stdout(format!("  Fix with --fix: {}", problem_fix.describe()));
+                }
+                Remedy::Manual(instruction) => {
+                    if !problem.is_warning {
+                        remaining += 1;
+                    }
+                    context
+                        .log_to_stdout(format!("  Cannot be fixed automatically: {}", instruction));
+                }
+            }
+        }
+        context.git.reload_model()?;
+        if remaining > 0 {
+            return Err(Box::new(CommandError::new(
+                format!("{} problem(s) remain", remaining).as_str(),
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::interface::GitPath;
+    use crate::git::interface::test_utils::{commit_file_to_branch, prepare_empty_git_repo};
+    use std::process::Command as Process;
+    use tempfile::TempDir;
+
+    fn git(repo_path: &PathBuf, args: &[&str]) {
+        Process::new("git")
+            .args(args)
+            .current_dir(repo_path)
+            .output()
+            .unwrap();
+    }
+
+    #[test]
+    fn doctor_fixes_malformed_refs() {
+        let path = TempDir::new().unwrap();
+        let repo_path = PathBuf::from(path.path());
+        prepare_empty_git_repo(repo_path.clone()).unwrap();
+        for branch in ["_main/docs", "_main/feature/root"] {
+            git(&repo_path, &["branch", branch]);
+        }
+        let interface = GitInterface::in_directory(repo_path.clone());
+        assert_eq!(interface.get_model().get_foreign_refs().len(), 2);
+        let repo = CommandRepository::new(
+            Box::new(DoctorCommand),
+            GitPath::CustomDirectory(repo_path.clone()),
+        );
+        assert!(repo.execute(ArgSource::SUPPLIED(vec!["doctor"])).is_err());
+        assert!(
+            repo.execute(ArgSource::SUPPLIED(vec!["doctor", "--fix"]))
+                .is_err()
+        );
+        let interface = GitInterface::in_directory(repo_path.clone());
+        assert!(
+            interface
+                .get_model()
+                .has_branch(&QualifiedPath::from("main/feature/root"))
+        );
+        let foreign_refs = interface.get_model().get_foreign_refs();
+        assert_eq!(foreign_refs.len(), 1);
+        assert_eq!(foreign_refs[0].name, "_main/docs");
+    }
+
+    #[test]
+    fn doctor_checks_invariants() {
+        let path = TempDir::new().unwrap();
+        let repo_path = PathBuf::from(path.path());
+        prepare_empty_git_repo(repo_path.clone()).unwrap();
+        git(&repo_path, &["branch", "_main/_feature/_root/old"]);
+        git(&repo_path, &["branch", "_main/_feature/_abstract/leaf"]);
+        commit_file_to_branch(repo_path.clone(), "main", "file2", "a").unwrap();
+        git(&repo_path, &["branch", "_main/_feature/root"]);
+        git(&repo_path, &["branch", "tmp"]);
+        commit_file_to_branch(repo_path.clone(), "main", "file3", "b").unwrap();
+        git(&repo_path, &["tag", "_main/_feature/_root/v1", "main"]);
+        let record = DerivationRecord::new(vec![
+            QualifiedPath::from("main/feature/root"),
+            QualifiedPath::from("main/feature/root/removed"),
+        ]);
+        git(&repo_path, &["checkout", "-b", "_main/_product/app"]);
+        git(
+            &repo_path,
+            &["commit", "--allow-empty", "-m", &record.to_commit_message()],
+        );
+        git(&repo_path, &["checkout", "main"]);
+        let interface = GitInterface::in_directory(repo_path.clone());
+        let mut store = interface.read_metadata_store().unwrap();
+        let mut metadata = FeatureMetadata::default();
+        metadata.set_owner(Some("alice".to_string()));
+        store.set(&QualifiedPath::from("main/feature/gone"), metadata);
+        interface.write_metadata_store(&store).unwrap();
+
+        let interface = GitInterface::in_directory(repo_path.clone());
+        // every problem with its remedy, as printed by the command
+        let reported = |interface: &GitInterface| {
+            let mut reported = find_problems(interface)
+                .unwrap()
+                .iter()
+                .map(|problem| {
+                    let remedy = match &problem.remedy {
+                        Remedy::Fix(problem_fix) => format!("fix: {}", problem_fix.describe()),
+                        Remedy::Manual(instruction) => format!("manual: {}", instruction),
+                    };
+                    let prefix = if problem.is_warning { "Warning: " } else { "" };
+                    format!("{}{} ({})", prefix, problem.description, remedy)
+                })
+                .collect::<Vec<_>>();
+            reported.sort();
+            reported
+        };
+        let expected = vec![
+            "Metadata is stored for main/feature/gone, which does not exist (manual: restore the feature, or transfer its metadata to its new name with tangl feature set)",
+            "The tag main/feature/root/v1 points to a commit outside of the history of main/feature/root (manual: move the tag to a commit of its branch)",
+            "Warning: main/feature/abstract has no branch and is treated as abstract feature (manual: create its branch if it is meant to be concrete)",
+            "Warning: main/feature/abstract/leaf is missing 2 commit(s) of its parent main (manual: merge them with: tangl feature sync abstract/leaf)",
+            "Warning: main/feature/root is missing 1 commit(s) of its parent main (manual: merge them with: tangl feature sync root)",
+            "Warning: main/feature/root/old is missing 1 commit(s) of its parent main/feature/root (manual: merge them with: tangl feature sync root/old)",
+            "Warning: tmp may be a leftover of an interrupted conflict check (manual: delete it with git branch -D tmp if it is not an area)",
+            "main/feature/root/old does not descend from the history of its parent main/feature/root (manual: rebase it onto its parent)",
+            "main/product/app was derived from the deleted feature main/feature/root/removed (manual: restore the feature or derive the product without it)",
+        ];
+        assert_eq!(reported(&interface), expected);
+
+        let repo = CommandRepository::new(
+            Box::new(DoctorCommand),
+            GitPath::CustomDirectory(repo_path.clone()),
+        );
+        assert!(
+            repo.execute(ArgSource::SUPPLIED(vec!["doctor", "--fix"]))
+                .is_err()
+        );
+        let interface = GitInterface::in_directory(repo_path.clone());
+        assert!(
+            interface
+                .get_model()
+                .has_branch(&QualifiedPath::from("tmp"))
+        );
+        assert_eq!(
+            interface
+                .read_metadata_store()
+                .unwrap()
+                .get(&QualifiedPath::from("main/feature/gone"))
+                .get_owner(),
+            Some(&"alice".to_string())
+        );
+        // none of the problems can be fixed without losing data
+        assert_eq!(reported(&interface), expected);
+    }
+}
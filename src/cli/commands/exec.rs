@@ -0,0 +1,321 @@
+use crate::cli::*;
+use crate::git::worktree::Worktree;
+use crate::model::*;
+use crate::util::matches_pattern;
+use clap::{Arg, ArgAction, Command};
+use colored::Colorize;
+use std::error::Error;
+use std::process::Output;
+
+/// The outcome of running the command on one product or variant.
+enum ExecResult {
+    Success,
+    Failure(Output),
+    Conflict,
+}
+
+impl ExecResult {
+    /// Returns the colored name of the result, padded to `width` visible characters.
+    fn to_cell(&self, width: usize) -> String {
+        match self {
+            ExecResult::Success => format!("{:width$}", "ok").green(),
+            ExecResult::Failure(_) => format!("{:width$}", "FAIL").red(),
+            ExecResult::Conflict => format!("{:width$}", "conflict").yellow(),
+        }
+        .to_string()
+    }
+}
+
+/// Returns the paths below `root` with a branch whose relative name matches any pattern.
+fn select_paths(
+    root: NodePath<AnyNodeType>,
+    patterns: &[String],
+    context: &CommandContext,
+) -> Vec<QualifiedPath> {
+    let strip = root.get_qualified_path().len();
+    let mut paths = root
+        .iter_children_req()
+        .map(|child| child.get_qualified_path())
+        .filter(|path| context.git.get_model().has_branch(path))
+        .filter(|path| {
+            let name = path.strip_n_left(strip).to_string();
+            patterns
+                .iter()
+                .any(|pattern| matches_pattern(pattern, &name))
+        })
+        .collect::<Vec<_>>();
+    paths.sort();
+    paths
+}
+
+fn run_in_worktree(worktree: &Worktree, command: &[String]) -> Result<ExecResult, Box<dyn Error>> {
+    let output = std::process::Command::new(&command[0])
+        .args(&command[1..])
+        .current_dir(worktree.path())
+        .output()
+        .map_err(|e| CommandError::new(format!("Cannot run {}: {}", command[0], e).as_str()))?;
+    Ok(if output.status.success() {
+        ExecResult::Success
+    } else {
+        ExecResult::Failure(output)
+    })
+}
+
+fn exec_product(
+    product: &QualifiedPath,
+    command: &[String],
+    context: &CommandContext,
+) -> Result<ExecResult, Box<dyn Error>> {
+    let worktree = context.git.create_worktree()?;
+    worktree.checkout_detached(product)?;
+    run_in_worktree(&worktree, command)
+}
+
+/// Runs the command on a temporary variant of the area that merges `features`.
+fn exec_variant(
+    area: &QualifiedPath,
+    features: &Vec<QualifiedPath>,
+    command: &[String],
+    context: &CommandContext,
+) -> Result<ExecResult, Box<dyn Error>> {
+    let worktree = context.git.create_worktree()?;
+    worktree.checkout_detached(area)?;
+    if !worktree.interface().merge(features)?.status.success() {
+        worktree.interface().abort_merge()?;
+        return Ok(ExecResult::Conflict);
+    }
+    run_in_worktree(&worktree, command)
+}
+
+fn log_failures(results: &[(String, &ExecResult)], context: &CommandContext) {
+    for (label, result) in results.iter() {
+        if let ExecResult::Failure(output) = result {
+            context.log_to_stdout(format!("--- {} ({}) ---", label, output.status));
+            context.log_from_output(output);
+        }
+    }
+}
+
+fn exec_products(
+    patterns: &[String],
+    command: &[String],
+    context: &CommandContext,
+) -> Result<usize, Box<dyn Error>> {
+    let area = context.git.get_current_area()?;
+    let product_root = area.get_path_to_product_root();
+    let products = match area.to_product_root() {
+        Some(root) => select_paths(root.to_any_type(), patterns, context),
+        None => vec![],
+    };
+    if products.is_empty() {
+        return Err("No product matches the given patterns".into());
+    }
+    let mut results = Vec::new();
+    for product in products.iter() {
+        results.push(exec_product(product, command, context)?);
+    }
+    let names = products
+        .iter()
+        .map(|p| p.strip_n_left(product_root.len()).to_string())
+        .collect::<Vec<_>>();
+    let width = names.iter().map(|n| n.len()).max().unwrap_or(0);
+    for (name, result) in names.iter().zip(results.iter()) {
+        context.log_to_stdout(format!("{:width$}  {}", name, result.to_cell(0)));
+    }
+    log_failures(
+        &names.into_iter().zip(results.iter()).collect::<Vec<_>>(),
+        context,
+    );
+    Ok(results
+        .iter()
+        .filter(|r| !matches!(r, ExecResult::Success))
+        .count())
+}
+
+/// Runs the command on every feature alone (the diagonal of the matrix) and on every pair of
+/// features, so that semantic conflicts between features become visible.
+fn exec_features(
+    patterns: &[String],
+    command: &[String],
+    context: &CommandContext,
+) -> Result<usize, Box<dyn Error>> {
+    let area = context.git.get_current_area()?;
+    let area_path = area.get_qualified_path();
+    let feature_root = area.get_path_to_feature_root();
+    let features = match area.to_feature_root() {
+        Some(root) => select_paths(root.to_any_type(), patterns, context),
+        None => vec![],
+    };
+    if features.is_empty() {
+        return Err("No feature matches the given patterns".into());
+    }
+    let mut matrix: Vec<Vec<Option<ExecResult>>> = Vec::new();
+    let mut labels = Vec::new();
+    for (i, l) in features.iter().enumerate() {
+        let mut row = Vec::new();
+        for (j, r) in features.iter().enumerate() {
+            if j < i {
+                row.push(None);
+                continue;
+            }
+            let variant = if i == j {
+                vec![l.clone()]
+            } else {
+                vec![l.clone(), r.clone()]
+            };
+            labels.push(
+                variant
+                    .iter()
+                    .map(|f| f.strip_n_left(feature_root.len()).to_string())
+                    .collect::<Vec<_>>()
+                    .join(" + "),
+            );
+            row.push(Some(exec_variant(&area_path, &variant, command, context)?));
+        }
+        matrix.push(row);
+    }
+    let names = features
+        .iter()
+        .map(|f| f.strip_n_left(feature_root.len()).to_string())
+        .collect::<Vec<_>>();
+    let width = names.iter().map(|n| n.len()).max().unwrap_or(0) + 4;
+    let header = (1..=features.len())
+        .map(|i| format!("{:<10}", i))
+        .collect::<String>();
+    context.log_to_stdout(format!("{:width$}  {}", "", header.trim_end()));
+    for (i, row) in matrix.iter().enumerate() {
+        let cells = row
+            .iter()
+            .map(|cell| match cell {
+                Some(result) => result.to_cell(10),
+                None => " ".repeat(10),
+            })
+            .collect::<String>();
+        let label = format!("{:<3} {}", i + 1, names[i]);
+        context.log_to_stdout(format!("{:width$}  {}", label, cells.trim_end()));
+    }
+    let results = matrix.iter().flatten().flatten().collect::<Vec<_>>();
+    log_failures(
+        &labels
+            .into_iter()
+            .zip(results.iter().copied())
+            .collect::<Vec<_>>(),
+        context,
+    );
+    Ok(results
+        .iter()
+        .filter(|r| !matches!(r, ExecResult::Success))
+        .count())
+}
+
+#[derive(Clone, Debug)]
+pub struct ExecCommand;
+
+impl CommandDefinition for ExecCommand {
+    fn build_command(&self) -> Command {
+        Command::new("exec")
+            .about("Runs a command on products or temporary variants and prints the results")
+            .disable_help_subcommand(true)
+            .arg(
+                Arg::new("products")
+                    .long("products")
+                    .num_args(1..)
+                    .action(ArgAction::Append)
+                    .required_unless_present("features")
+                    .conflicts_with("features")
+                    .help("Runs the command on every product matching the patterns, e.g. 'pro/*'"),
+            )
+            .arg(
+                Arg::new("features")
+                    .long("features")
+                    .num_args(1..)
+                    .action(ArgAction::Append)
+                    .help(
+                        "Runs the command on variants of every matching feature and every pair of them",
+                    ),
+            )
+            .arg(
+                Arg::new("command")
+                    .required(true)
+                    .num_args(1..)
+                    .last(true)
+                    .help("The command to run, separated by --"),
+            )
+    }
+}
+
+impl CommandInterface for ExecCommand {
+    fn run_command(&self, context: &mut CommandContext) -> Result<(), Box<dyn Error>> {
+        let command = context
+            .arg_helper
+            .get_argument_values::<String>("command")
+            .unwrap();
+        let failed = match context.arg_helper.get_argument_values::<String>("products") {
+            Some(patterns) => exec_products(&patterns, &command, context)?,
+            None => {
+                let patterns = context
+                    .arg_helper
+                    .get_argument_values::<String>("features")
+                    .unwrap();
+                exec_features(&patterns, &command, context)?
+            }
+        };
+        if failed > 0 {
+            return Err(Box::new(CommandError::new(
+                format!("{} run(s) did not succeed", failed).as_str(),
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::interface::GitPath;
+    use crate::git::interface::test_utils::{
+        commit_file_to_branch, populate_with_features, prepare_empty_git_repo,
+    };
+    use std::path::PathBuf;
+    use std::process::Command as Process;
+    use tempfile::TempDir;
+
+    #[test]
+    fn exec_on_products_and_variants() {
+        let path = TempDir::new().unwrap();
+        let repo_path = PathBuf::from(path.path());
+        prepare_empty_git_repo(repo_path.clone()).unwrap();
+        populate_with_features(repo_path.clone()).unwrap();
+        for branch in ["_main/_product/_pro/a", "_main/_product/_pro/b"] {
+            Process::new("git")
+                .args(["branch", branch])
+                .current_dir(&repo_path)
+                .output()
+                .unwrap();
+        }
+        commit_file_to_branch(repo_path.clone(), "_main/_product/_pro/a", "ok", "").unwrap();
+        commit_file_to_branch(repo_path.clone(), "_main/_feature/_root/foo", "a", "").unwrap();
+        commit_file_to_branch(repo_path.clone(), "_main/_feature/_root/bar", "b", "").unwrap();
+        let repo = CommandRepository::new(
+            Box::new(ExecCommand),
+            GitPath::CustomDirectory(repo_path.clone()),
+        );
+        let exec = |args: Vec<&str>| {
+            let mut all = vec!["exec"];
+            all.extend(args);
+            repo.execute(ArgSource::SUPPLIED(all))
+        };
+        assert!(exec(vec!["--products", "pro/a", "--", "test", "-f", "ok"]).is_ok());
+        assert!(exec(vec!["--products", "pro/*", "--", "test", "-f", "ok"]).is_err());
+        assert!(exec(vec!["--products", "none", "--", "true"]).is_err());
+
+        // the features only fail in combination
+        let semantic = vec!["--", "sh", "-c", "! (test -f a && test -f b)"];
+        let mut single = vec!["--features", "root/foo", "root/baz"];
+        single.extend(semantic.clone());
+        assert!(exec(single).is_ok());
+        let mut pair = vec!["--features", "root/foo", "root/bar"];
+        pair.extend(semantic);
+        assert!(exec(pair).is_err());
+    }
+}
@@ -1,11 +1,14 @@
 use crate::cli::completion::*;
 use crate::cli::*;
+use crate::git::interface::GitInterface;
 use crate::model::*;
-use clap::{Arg, Command};
+use clap::{Arg, ArgAction, Command};
+use colored::Colorize;
 use std::error::Error;
 
 fn add_feature(feature: QualifiedPath, context: &mut CommandContext) -> Result<(), Box<dyn Error>> {
     let node_path = context.git.get_current_node_path()?;
+    let current_branch = node_path.get_qualified_path();
     let current_path = match node_path.concretize() {
         NodePathType::Area(path) => path.get_path_to_feature_root(),
         NodePathType::Feature(path) => path.get_qualified_path(),
@@ -16,7 +19,12 @@ fn add_feature(feature: QualifiedPath, context: &mut CommandContext) -> Result<(
         }
     };
     let target_path = current_path + feature;
-    let output = context.git.create_branch(&target_path)?;
+    let start = context
+        .git
+        .get_model()
+        .get_parent_with_branch(&target_path)
+        .unwrap_or(current_branch);
+    let output = context.git.create_branch_from(&target_path, &start)?;
     context.log_from_output(&output);
     context.log_to_stdout(format!(
         "Created new feature {}",
@@ -24,13 +32,530 @@ fn add_feature(feature: QualifiedPath, context: &mut CommandContext) -> Result<(
     ));
     Ok(())
 }
-fn delete_feature(feature: QualifiedPath, context: &CommandContext) -> Result<(), Box<dyn Error>> {
+fn delete_feature(
+    feature: QualifiedPath,
+    recursive: bool,
+    force: bool,
+    context: &CommandContext,
+) -> Result<(), Box<dyn Error>> {
     let area = context.git.get_current_area()?;
     let complete_path = area.get_path_to_feature_root() + feature;
-    let output = context.git.delete_branch(&complete_path)?;
-    context.log_from_output(&output);
+    let display_path = complete_path.strip_n_left(2);
+    let node_path = match context.git.get_model().get_node_path(&complete_path) {
+        Some(path) => path,
+        None => {
+            return Err(Box::new(CommandError::new(
+                format!("Cannot delete feature {}: does not exist", display_path).as_str(),
+            )));
+        }
+    };
+    let has_children = node_path
+        .iter_children()
+        .any(|child| !matches!(child.concretize(), NodePathType::Tag(_)));
+    if has_children && !recursive {
+        return Err(Box::new(CommandError::new(
+            format!(
+                "Cannot delete feature {}: it has children. Use -r to delete them as well",
+                display_path
+            )
+            .as_str(),
+        )));
+    }
+    let referencing_products = context
+        .git
+        .get_derivation_records(&area)?
+        .into_iter()
+        .filter(|(_, record)| record.references(&complete_path))
+        .map(|(product, _)| product.strip_n_left(2).to_string())
+        .collect::<Vec<_>>();
+    if !referencing_products.is_empty() && !force {
+        return Err(Box::new(CommandError::new(
+            format!(
+                "Cannot delete feature {}: it is used by the products {}. Use --force to delete it anyway",
+                display_path,
+                referencing_products.join(", ")
+            )
+            .as_str(),
+        )));
+    }
+    let mut subtree = vec![node_path.clone()];
+    subtree.extend(node_path.iter_children_req());
+    let mut branches: Vec<QualifiedPath> = Vec::new();
+    let mut tags: Vec<QualifiedPath> = Vec::new();
+    for path in subtree {
+        match path.concretize() {
+            NodePathType::Tag(tag) => tags.push(tag.get_qualified_path()),
+            NodePathType::Feature(feature) => {
+                if !feature.get_metadata().has_branch() {
+                    continue;
+                }
+                if let Some(parent) = feature.get_parent_with_branch() {
+                    let n_commits = context
+                        .git
+                        .count_commits_not_in(&feature.get_qualified_path(), &parent)?;
+                    if n_commits > 0 {
+                        context.log_to_stderr(format!(
+                            "Warning: {} has {} commit(s) not merged into {}",
+                            feature.get_qualified_path().strip_n_left(2),
+                            n_commits,
+                            parent.strip_n_left(2)
+                        ));
+                    }
+                }
+                branches.push(feature.get_qualified_path());
+            }
+            _ => {}
+        }
+    }
+    drop(node_path);
+    drop(area);
+    // nothing is deleted unless the whole subtree can be deleted
+    let checked_out = context
+        .git
+        .get_checked_out_paths()?
+        .into_iter()
+        .filter(|path| branches.contains(path))
+        .map(|path| path.strip_n_left(2).to_string())
+        .collect::<Vec<_>>();
+    if !checked_out.is_empty() {
+        return Err(Box::new(CommandError::new(
+            format!(
+                "Cannot delete feature {}: {} is checked out. Check out another branch first",
+                display_path,
+                checked_out.join(", ")
+            )
+            .as_str(),
+        )));
+    }
+    for tag in tags {
+        context.git.delete_tag_with_backup(&tag)?;
+    }
+    for branch in branches {
+        context.git.delete_branch_with_backup(&branch)?;
+        context.log_to_stdout(format!("Deleted feature {}", branch.strip_n_left(2)));
+    }
+    context.log_to_stdout(format!(
+        "Undo with: tangl feature --restore {}",
+        display_path
+    ));
+    Ok(())
+}
+fn restore_feature(feature: QualifiedPath, context: &CommandContext) -> Result<(), Box<dyn Error>> {
+    let area = context.git.get_current_area()?;
+    let complete_path = area.get_path_to_feature_root() + feature;
+    let restored = context.git.restore_backups(&complete_path)?;
+    if restored.is_empty() {
+        return Err(Box::new(CommandError::new(
+            format!("No backup found for {}", complete_path.strip_n_left(2)).as_str(),
+        )));
+    }
+    for branch in restored {
+        context.log_to_stdout(format!("Restored feature {}", branch.strip_n_left(2)));
+    }
+    Ok(())
+}
+fn move_feature(
+    from: QualifiedPath,
+    to: QualifiedPath,
+    context: &CommandContext,
+) -> Result<(), Box<dyn Error>> {
+    let area = context.git.get_current_area()?;
+    let feature_root = area.get_path_to_feature_root();
+    let source = feature_root.clone() + from;
+    let target = feature_root + to;
+    let source_node = match context.git.get_model().get_node_path(&source) {
+        Some(path) => path,
+        None => {
+            return Err(Box::new(CommandError::new(
+                format!(
+                    "Cannot move feature {}: does not exist",
+                    source.strip_n_left(2)
+                )
+                .as_str(),
+            )));
+        }
+    };
+    if target.has_prefix(&source) {
+        return Err(Box::new(CommandError::new(
+            format!("Cannot move feature {} into itself", source.strip_n_left(2)).as_str(),
+        )));
+    }
+    if context.git.get_model().get_node_path(&target).is_some() {
+        return Err(Box::new(CommandError::new(
+            format!(
+                "Cannot move feature: {} already exists",
+                target.strip_n_left(2)
+            )
+            .as_str(),
+        )));
+    }
+    // nothing is renamed unless the derivation records can be updated afterwards
+    let to_update = context
+        .git
+        .get_derivation_records(&area)?
+        .into_iter()
+        .filter(|(_, record)| record.references(&source))
+        .collect::<Vec<_>>();
+    let checked_out = context
+        .git
+        .get_checked_out_paths()?
+        .into_iter()
+        .filter(|path| to_update.iter().any(|(product, _)| product == path))
+        .map(|path| path.strip_n_left(2).to_string())
+        .collect::<Vec<_>>();
+    if !checked_out.is_empty() {
+        return Err(Box::new(CommandError::new(
+            format!(
+                "Cannot move feature {}: the derivation record of {} cannot be updated while it is checked out. Check out another branch first",
+                source.strip_n_left(2),
+                checked_out.join(", ")
+            )
+            .as_str(),
+        )));
+    }
+    let worktree = if to_update.is_empty() {
+        None
+    } else {
+        Some(context.git.create_worktree()?)
+    };
+    let mut to_rename = vec![source_node.clone()];
+    to_rename.extend(source_node.iter_children_req());
+    let rename = |is_tag: bool, from: &QualifiedPath, to: &QualifiedPath| {
+        let result = if is_tag {
+            context.git.rename_tag(from, to)
+        } else {
+            context.git.rename_branch(from, to)
+        };
+        match result {
+            Ok(output) if output.status.success() => true,
+            Ok(output) => {
+                context.log_from_output(&output);
+                false
+            }
+            Err(e) => {
+                context.log_to_stderr(e.to_string());
+                false
+            }
+        }
+    };
+    // (is tag, old path, new path) of the refs renamed so far
+    let mut renamed: Vec<(bool, QualifiedPath, QualifiedPath)> = Vec::new();
+    for path in to_rename {
+        let old_path = path.get_qualified_path();
+        let new_path = target.clone() + old_path.strip_n_left(source.len());
+        let is_tag = match path.concretize() {
+            NodePathType::Tag(_) => true,
+            NodePathType::Feature(feature) if feature.get_metadata().has_branch() => false,
+            NodePathType::Feature(_) => continue,
+            _ => unreachable!(),
+        };
+        if !rename(is_tag, &old_path, &new_path) {
+            // move the subtree completely or not at all
+            for (is_tag, old_path, new_path) in renamed.iter().rev() {
+                if !rename(*is_tag, new_path, old_path) {
+                    context.log_to_stderr(format!(
+                        "Warning: unable to move {} back to {}",
+                        new_path.strip_n_left(2),
+                        old_path.strip_n_left(2)
+                    ));
+                }
+            }
+            return Err(Box::new(CommandError::new(
+                format!("Failed to move {}", old_path.strip_n_left(2)).as_str(),
+            )));
+        }
+        renamed.push((is_tag, old_path, new_path));
+    }
+    context.git.move_feature_metadata(&source, &target)?;
+    let mut configs = context.git.read_product_configs()?;
+    let mut configs_changed = false;
+    for config in configs.iter_mut() {
+        configs_changed |=
+            config.rename_feature(&source, &target, context.git.get_model().get_naming());
+    }
+    if configs_changed {
+        context.git.write_product_configs(&configs)?;
+    }
+    if let Some(worktree) = worktree {
+        for (product, record) in to_update {
+            let updated = record.rename(&source, &target);
+            worktree.checkout(&product)?;
+            worktree
+                .interface()
+                .empty_commit(updated.to_update_message().as_str())?;
+            context.log_to_stdout(format!(
+                "Updated derivation record of {}",
+                product.strip_n_left(2)
+            ));
+        }
+    }
+    context.log_to_stdout(format!(
+        "Moved feature {} to {}",
+        source.strip_n_left(2),
+        target.strip_n_left(2)
+    ));
+    Ok(())
+}
+fn resolve_feature(
+    feature: QualifiedPath,
+    context: &CommandContext,
+) -> Result<QualifiedPath, Box<dyn Error>> {
+    let path = context.git.get_current_area()?.get_path_to_feature_root() + feature;
+    match context.git.get_model().get_node_path(&path) {
+        Some(node_path) => match node_path.concretize() {
+            NodePathType::Feature(_) => Ok(path),
+            _ => Err(Box::new(CommandError::new(
+                format!("{} is not a feature", path.strip_n_left(2)).as_str(),
+            ))),
+        },
+        None => Err(Box::new(CommandError::new(
+            format!("Feature {} does not exist", path.strip_n_left(2)).as_str(),
+        ))),
+    }
+}
+fn get_feature_metadata(path: &QualifiedPath, context: &CommandContext) -> FeatureMetadata {
+    context
+        .git
+        .get_model()
+        .get_node_path(path)
+        .map(|node_path| node_path.get_metadata().get_feature_metadata().clone())
+        .unwrap_or_default()
+}
+fn archive_feature(
+    feature: QualifiedPath,
+    undo: bool,
+    context: &mut CommandContext,
+) -> Result<(), Box<dyn Error>> {
+    let path = resolve_feature(feature, context)?;
+    let mut metadata = get_feature_metadata(&path, context);
+    if undo {
+        if !context.git.get_model().is_archived(&path) {
+            return Err(Box::new(CommandError::new(
+                format!("Feature {} is not archived", path.strip_n_left(2)).as_str(),
+            )));
+        }
+        context.git.unarchive_branch(&path)?;
+        metadata.set_state(Some(FeatureState::Deprecated));
+        context.git.set_feature_metadata(&path, metadata)?;
+        context.log_to_stdout(format!(
+            "Restored archived feature {} as deprecated",
+            path.strip_n_left(2)
+        ));
+        return Ok(());
+    }
+    if !context.git.get_model().has_branch(&path) {
+        return Err(Box::new(CommandError::new(
+            format!(
+                "Cannot archive {}: feature has no branch",
+                path.strip_n_left(2)
+            )
+            .as_str(),
+        )));
+    }
+    context.git.archive_branch(&path)?;
+    metadata.set_state(Some(FeatureState::Archived));
+    context.git.set_feature_metadata(&path, metadata)?;
+    context.log_to_stdout(format!(
+        "Archived feature {}. Undo with: tangl feature archive --undo {}",
+        path.strip_n_left(2),
+        path.strip_n_left(2)
+    ));
     Ok(())
 }
+fn format_list(items: Vec<String>) -> String {
+    if items.is_empty() {
+        "none".to_string()
+    } else {
+        items.join(", ")
+    }
+}
+/// Returns the lines describing the feature `path`.
+fn show_feature(path: &QualifiedPath, git: &GitInterface) -> Result<Vec<String>, Box<dyn Error>> {
+    let area = git.get_current_area()?;
+    let node_path = git.get_model().get_node_path(path).unwrap();
+    let metadata = node_path.get_metadata().get_feature_metadata().clone();
+    let display_path = path.strip_n_left(2);
+
+    let mut lines = vec![match metadata.get_state() {
+        Some(state) => format!(
+            "Feature {} {}",
+            display_path.to_string().blue(),
+            state.format_state_display()
+        ),
+        None => format!("Feature {}", display_path.to_string().blue()),
+    }];
+    if let Some(description) = metadata.get_description() {
+        lines.push(format!("Description: {}", description));
+    }
+    if let Some(owner) = metadata.get_owner() {
+        lines.push(format!("Owner: {}", owner));
+    }
+    let parents = (1..display_path.len())
+        .map(|n| display_path.strip_n_right(n).to_string())
+        .collect::<Vec<_>>();
+    lines.push(format!("Parents: {}", format_list(parents)));
+    let children = node_path
+        .iter_children()
+        .filter_map(|child| match child.concretize() {
+            NodePathType::Feature(child) => {
+                Some(child.get_qualified_path().strip_n_left(2).to_string())
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    lines.push(format!("Children: {}", format_list(children)));
+    let tags = node_path
+        .get_tags()
+        .iter()
+        .map(|tag| tag.to_string())
+        .collect::<Vec<_>>();
+    lines.push(format!("Tags: {}", format_list(tags)));
+
+    let has_revision = git.get_model().has_branch(path) || git.get_model().is_archived(path);
+    match node_path.get_parent_with_branch() {
+        Some(parent) if has_revision => {
+            let parent_name = if parent.len() > 2 {
+                parent.strip_n_left(2).to_string()
+            } else {
+                parent.to_string()
+            };
+            lines.push(format!(
+                "Commits ahead of {}: {}",
+                parent_name,
+                git.count_commits_not_in(path, &parent)?
+            ));
+            let files = git.get_files_changed_since(path, &parent)?;
+            if files.is_empty() {
+                lines.push(format!("Files owned relative to {}: none", parent_name));
+            } else {
+                lines.push(format!("Files owned relative to {}:", parent_name));
+                lines.extend(files.iter().map(|file| format!("  {}", file)));
+            }
+        }
+        _ => {}
+    }
+
+    let products = git
+        .get_derivation_records(&area)?
+        .into_iter()
+        .filter(|(_, record)| record.features().contains(path))
+        .map(|(product, _)| product.strip_n_left(2).to_string())
+        .collect::<Vec<_>>();
+    lines.push(format!("Products: {}", format_list(products)));
+    Ok(lines)
+}
+fn complete_features(
+    completion_helper: &CompletionHelper,
+    context: &CommandContext,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    let maybe_feature_root = context.git.get_current_area()?.to_feature_root();
+    let result = match maybe_feature_root {
+        Some(path) => completion_helper.complete_qualified_paths(
+            path.get_qualified_path(),
+            HasBranchFilteringNodePathTransformer::new(true)
+                .transform(path.iter_children_req())
+                .map(|path| path.get_qualified_path()),
+            false,
+        ),
+        None => {
+            vec![]
+        }
+    };
+    Ok(result)
+}
+/// Merges the parent of `feature` into it or rebases it onto its parent, and does the same for
+/// all of its children if `recursive`. Parents are synced before their children.
+///
+/// Returns the features that could not be synced because of conflicts.
+fn sync_features(
+    feature: &QualifiedPath,
+    recursive: bool,
+    rebase: bool,
+    context: &CommandContext,
+) -> Result<Vec<QualifiedPath>, Box<dyn Error>> {
+    let node_path = context.git.get_model().get_node_path(feature).unwrap();
+    let mut targets = vec![node_path.clone()];
+    if recursive {
+        targets.extend(node_path.iter_children_req());
+    }
+    let current_branch = context.git.get_current_qualified_path()?;
+    let worktree = context.git.create_worktree()?;
+    let mut failed: Vec<QualifiedPath> = Vec::new();
+    // the parent of the feature root is the area, which is printed as is
+    let display = |path: &QualifiedPath| match path.len() {
+        1 => path.clone(),
+        _ => path.strip_n_left(2),
+    };
+    for target in targets {
+        let path = target.get_qualified_path();
+        let parent = match target.get_parent_with_branch() {
+            Some(parent) if target.get_metadata().has_branch() => parent,
+            _ => continue,
+        };
+        if let NodePathType::Tag(_) = target.concretize() {
+            continue;
+        }
+        let missing = context.git.count_commits_not_in(&parent, &path)?;
+        if missing == 0 {
+            context.log_to_stdout(format!(
+                "{} is up to date with {}",
+                display(&path),
+                display(&parent)
+            ));
+            continue;
+        }
+        // a branch cannot be checked out in two worktrees at once
+        let interface = if path == current_branch {
+            &*context.git
+        } else {
+            if let Err(e) = worktree.checkout(&path) {
+                context.log_to_stdout(format!(
+                    "Warning: unable to check out {}: {}",
+                    display(&path),
+                    e
+                ));
+                failed.push(path);
+                continue;
+            }
+            worktree.interface()
+        };
+        let success = if rebase {
+            let output = interface.rebase(&parent)?;
+            if !output.status.success() {
+                interface.abort_rebase()?;
+            }
+            output.status.success()
+        } else {
+            let output = interface.merge(&vec![parent.clone()])?;
+            if !output.status.success() {
+                interface.abort_merge()?;
+            }
+            output.status.success()
+        };
+        if !success {
+            context.log_to_stdout(format!(
+                "Unable to sync {} with {}: conflict",
+                display(&path),
+                display(&parent)
+            ));
+            failed.push(path);
+        } else if rebase {
+            context.log_to_stdout(format!(
+                "Rebased {} onto {}",
+                display(&path),
+                display(&parent)
+            ));
+        } else {
+            context.log_to_stdout(format!(
+                "Merged {} commit(s) of {} into {}",
+                missing,
+                display(&parent),
+                display(&path)
+            ));
+        }
+    }
+    Ok(failed)
+}
 fn print_feature_tree(context: &CommandContext, show_tags: bool) -> Result<(), Box<dyn Error>> {
     let area = context.git.get_current_area()?;
     match area.to_feature_root() {
@@ -51,24 +576,52 @@ impl CommandDefinition for FeatureCommand {
             .disable_help_subcommand(true)
             .arg(Arg::new("feature").help("Creates new feature as the child of the current one. Requires to be checked out on a feature branch."))
             .arg(Arg::new("delete").short('D').help("Deletes a feature branch"))
+            .arg(make_recursive())
+            .arg(make_force())
+            .arg(make_restore())
             .arg(make_show_tags())
     }
+    fn get_subcommands(&self) -> Vec<Box<dyn CommandImpl>> {
+        vec![
+            Box::new(FeatureMoveCommand),
+            Box::new(FeatureSetCommand),
+            Box::new(FeatureArchiveCommand),
+            Box::new(FeatureShowCommand),
+            Box::new(FeatureSyncCommand),
+        ]
+    }
 }
 impl CommandInterface for FeatureCommand {
     fn run_command(&self, context: &mut CommandContext) -> Result<(), Box<dyn Error>> {
+        if context.arg_helper.has_subcommand() {
+            return Ok(());
+        }
         let maybe_feature_name = context.arg_helper.get_argument_value::<String>("feature");
         let maybe_delete = context.arg_helper.get_argument_value::<String>("delete");
         let show_tags = context
             .arg_helper
             .get_argument_value::<bool>("show_tags")
             .unwrap();
+        let maybe_restore = context.arg_helper.get_argument_value::<String>("restore");
+        let recursive = context
+            .arg_helper
+            .get_argument_value::<bool>("recursive")
+            .unwrap();
+        let force = context
+            .arg_helper
+            .get_argument_value::<bool>("force")
+            .unwrap();
         match maybe_delete {
             Some(delete) => {
-                delete_feature(QualifiedPath::from(delete), &context)?;
+                delete_feature(QualifiedPath::from(delete), recursive, force, context)?;
                 return Ok(());
             }
             None => {}
         }
+        if let Some(restore) = maybe_restore {
+            restore_feature(QualifiedPath::from(restore), context)?;
+            return Ok(());
+        }
         match maybe_feature_name {
             Some(feature_name) => {
                 add_feature(QualifiedPath::from(feature_name), context)?;
@@ -86,20 +639,18 @@ impl CommandInterface for FeatureCommand {
     ) -> Result<Vec<String>, Box<dyn Error>> {
         let result = match completion_helper.currently_editing() {
             Some(arg) => match arg.get_id().as_str() {
-                "delete" => {
-                    let maybe_feature_root = context.git.get_current_area()?.to_feature_root();
-                    match maybe_feature_root {
-                        Some(path) => completion_helper.complete_qualified_paths(
-                            path.get_qualified_path(),
-                            HasBranchFilteringNodePathTransformer::new(true)
-                                .transform(path.iter_children_req())
-                                .map(|path| path.get_qualified_path()),
-                            false,
-                        ),
-                        None => {
-                            vec![]
-                        }
-                    }
+                "delete" => complete_features(&completion_helper, context)?,
+                "restore" => {
+                    let feature_root = context.git.get_current_area()?.get_path_to_feature_root();
+                    completion_helper.complete_qualified_paths(
+                        feature_root.clone(),
+                        context
+                            .git
+                            .get_branch_backups()?
+                            .into_iter()
+                            .filter(|path| path.has_prefix(&feature_root)),
+                        false,
+                    )
                 }
                 _ => {
                     vec![]
@@ -112,3 +663,707 @@ impl CommandInterface for FeatureCommand {
         Ok(result)
     }
 }
+
+#[derive(Clone, Debug)]
+pub struct FeatureMoveCommand;
+impl CommandDefinition for FeatureMoveCommand {
+    fn build_command(&self) -> Command {
+        Command::new("mv")
+            .about("Rename or move a feature including all of its children and tags")
+            .disable_help_subcommand(true)
+            .arg(Arg::new("from").required(true).help("The feature to move"))
+            .arg(
+                Arg::new("to")
+                    .required(true)
+                    .help("The new path of the feature"),
+            )
+    }
+}
+impl CommandInterface for FeatureMoveCommand {
+    fn run_command(&self, context: &mut CommandContext) -> Result<(), Box<dyn Error>> {
+        let from = context
+            .arg_helper
+            .get_argument_value::<String>("from")
+            .unwrap();
+        let to = context
+            .arg_helper
+            .get_argument_value::<String>("to")
+            .unwrap();
+        move_feature(QualifiedPath::from(from), QualifiedPath::from(to), context)
+    }
+    fn shell_complete(
+        &self,
+        completion_helper: CompletionHelper,
+        context: &mut CommandContext,
+    ) -> Result<Vec<String>, Box<dyn Error>> {
+        match completion_helper.currently_editing() {
+            Some(arg) => match arg.get_id().as_str() {
+                "from" => complete_features(&completion_helper, context),
+                _ => Ok(vec![]),
+            },
+            None => Ok(vec![]),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct FeatureSetCommand;
+impl CommandDefinition for FeatureSetCommand {
+    fn build_command(&self) -> Command {
+        Command::new("set")
+            .about("Set the lifecycle state, owner or description of a feature")
+            .disable_help_subcommand(true)
+            .arg(
+                Arg::new("feature")
+                    .required(true)
+                    .help("The feature to edit"),
+            )
+            .arg(
+                Arg::new("state")
+                    .long("state")
+                    .value_parser(["experimental", "stable", "deprecated"])
+                    .help("The lifecycle state of the feature"),
+            )
+            .arg(
+                Arg::new("owner")
+                    .long("owner")
+                    .help("The owner of the feature. Pass an empty value to remove it."),
+            )
+            .arg(
+                Arg::new("description")
+                    .long("description")
+                    .help("The description of the feature. Pass an empty value to remove it."),
+            )
+    }
+}
+impl CommandInterface for FeatureSetCommand {
+    fn run_command(&self, context: &mut CommandContext) -> Result<(), Box<dyn Error>> {
+        let feature = context
+            .arg_helper
+            .get_argument_value::<String>("feature")
+            .unwrap();
+        let path = resolve_feature(QualifiedPath::from(feature), context)?;
+        if context.git.get_model().is_archived(&path) {
+            return Err(Box::new(CommandError::new(
+                format!(
+                    "Feature {} is archived. Restore it with: tangl feature archive --undo {}",
+                    path.strip_n_left(2),
+                    path.strip_n_left(2)
+                )
+                .as_str(),
+            )));
+        }
+        let mut metadata = get_feature_metadata(&path, context);
+        if let Some(state) = context.arg_helper.get_argument_value::<String>("state") {
+            metadata.set_state(Some(state.parse::<FeatureState>()?));
+        }
+        if let Some(owner) = context.arg_helper.get_argument_value::<String>("owner") {
+            metadata.set_owner(Some(owner).filter(|owner| !owner.is_empty()));
+        }
+        if let Some(description) = context
+            .arg_helper
+            .get_argument_value::<String>("description")
+        {
+            metadata.set_description(Some(description).filter(|d| !d.is_empty()));
+        }
+        context.git.set_feature_metadata(&path, metadata)?;
+        context.log_to_stdout(format!("Updated feature {}", path.strip_n_left(2)));
+        Ok(())
+    }
+    fn shell_complete(
+        &self,
+        completion_helper: CompletionHelper,
+        context: &mut CommandContext,
+    ) -> Result<Vec<String>, Box<dyn Error>> {
+        match completion_helper.currently_editing() {
+            Some(arg) => match arg.get_id().as_str() {
+                "feature" => complete_features(&completion_helper, context),
+                _ => Ok(vec![]),
+            },
+            None => Ok(vec![]),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct FeatureArchiveCommand;
+impl CommandDefinition for FeatureArchiveCommand {
+    fn build_command(&self) -> Command {
+        Command::new("archive")
+            .about("Archive a feature. Its branch is removed, but products derived from it keep working.")
+            .disable_help_subcommand(true)
+            .arg(Arg::new("feature").required(true).help("The feature to archive"))
+            .arg(
+                Arg::new("undo")
+                    .long("undo")
+                    .action(ArgAction::SetTrue)
+                    .help("Restores the branch of an archived feature"),
+            )
+    }
+}
+impl CommandInterface for FeatureArchiveCommand {
+    fn run_command(&self, context: &mut CommandContext) -> Result<(), Box<dyn Error>> {
+        let feature = context
+            .arg_helper
+            .get_argument_value::<String>("feature")
+            .unwrap();
+        let undo = context
+            .arg_helper
+            .get_argument_value::<bool>("undo")
+            .unwrap();
+        archive_feature(QualifiedPath::from(feature), undo, context)
+    }
+    fn shell_complete(
+        &self,
+        completion_helper: CompletionHelper,
+        context: &mut CommandContext,
+    ) -> Result<Vec<String>, Box<dyn Error>> {
+        match completion_helper.currently_editing() {
+            Some(arg) => match arg.get_id().as_str() {
+                "feature" => complete_features(&completion_helper, context),
+                _ => Ok(vec![]),
+            },
+            None => Ok(vec![]),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct FeatureShowCommand;
+impl CommandDefinition for FeatureShowCommand {
+    fn build_command(&self) -> Command {
+        Command::new("show")
+            .about("Show details of a feature")
+            .disable_help_subcommand(true)
+            .arg(
+                Arg::new("feature")
+                    .required(true)
+                    .help("The feature to show"),
+            )
+    }
+}
+impl CommandInterface for FeatureShowCommand {
+    fn run_command(&self, context: &mut CommandContext) -> Result<(), Box<dyn Error>> {
+        let feature = context
+            .arg_helper
+            .get_argument_value::<String>("feature")
+            .unwrap();
+        let path = resolve_feature(QualifiedPath::from(feature), context)?;
+        let lines = show_feature(&path, context.git)?;
+        context.log_to_stdout(lines.join("\n"));
+        Ok(())
+    }
+    fn shell_complete(
+        &self,
+        completion_helper: CompletionHelper,
+        context: &mut CommandContext,
+    ) -> Result<Vec<String>, Box<dyn Error>> {
+        match completion_helper.currently_editing() {
+            Some(arg) => match arg.get_id().as_str() {
+                "feature" => complete_features(&completion_helper, context),
+                _ => Ok(vec![]),
+            },
+            None => Ok(vec![]),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct FeatureSyncCommand;
+impl CommandDefinition for FeatureSyncCommand {
+    fn build_command(&self) -> Command {
+        Command::new("sync")
+            .about("Bring a feature up to date with its parent feature")
+            .disable_help_subcommand(true)
+            .arg(Arg::new("feature").help("The feature to sync. Defaults to the current feature."))
+            .arg(
+                Arg::new("recursive")
+                    .short('r')
+                    .long("recursive")
+                    .action(ArgAction::SetTrue)
+                    .help("Also sync all children, each with its own parent"),
+            )
+            .arg(
+                Arg::new("rebase")
+                    .long("rebase")
+                    .action(ArgAction::SetTrue)
+                    .help("Rebase onto the parent instead of merging it"),
+            )
+    }
+}
+impl CommandInterface for FeatureSyncCommand {
+    fn run_command(&self, context: &mut CommandContext) -> Result<(), Box<dyn Error>> {
+        let path = match context.arg_helper.get_argument_value::<String>("feature") {
+            Some(feature) => resolve_feature(QualifiedPath::from(feature), context)?,
+            None => match context.git.get_current_node_path()?.concretize() {
+                NodePathType::Feature(path) => path.get_qualified_path(),
+                _ => {
+                    return Err(Box::new(CommandError::new(
+                        "Cannot sync: Current branch is not a feature branch",
+                    )));
+                }
+            },
+        };
+        let recursive = context
+            .arg_helper
+            .get_argument_value::<bool>("recursive")
+            .unwrap();
+        let rebase = context
+            .arg_helper
+            .get_argument_value::<bool>("rebase")
+            .unwrap();
+        let failed = sync_features(&path, recursive, rebase, context)?;
+        if !failed.is_empty() {
+            return Err(Box::new(CommandError::new(
+                format!(
+                    "{} feature(s) could not be synced. Merge their parents manually.",
+                    failed.len()
+                )
+                .as_str(),
+            )));
+        }
+        Ok(())
+    }
+    fn shell_complete(
+        &self,
+        completion_helper: CompletionHelper,
+        context: &mut CommandContext,
+    ) -> Result<Vec<String>, Box<dyn Error>> {
+        match completion_helper.currently_editing() {
+            Some(arg) => match arg.get_id().as_str() {
+                "feature" => complete_features(&completion_helper, context),
+                _ => Ok(vec![]),
+            },
+            None => Ok(vec![]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::commands::DeriveCommand;
+    use crate::git::interface::test_utils::{
+        commit_file_to_branch, populate_with_features, prepare_empty_git_repo,
+    };
+    use crate::git::interface::{GitInterface, GitPath};
+    use std::path::PathBuf;
+    use tempfile::TempDir;
+
+    #[test]
+    fn move_feature_with_children_and_products() {
+        let path = TempDir::new().unwrap();
+        let repo_path = PathBuf::from(path.path());
+        prepare_empty_git_repo(repo_path.clone()).unwrap();
+        populate_with_features(repo_path.clone()).unwrap();
+        CommandRepository::new(
+            Box::new(DeriveCommand),
+            GitPath::CustomDirectory(repo_path.clone()),
+        )
+        .execute(ArgSource::SUPPLIED(vec![
+            "derive", "-p", "myprod", "root/foo", "root/bar",
+        ]))
+        .unwrap();
+        let repo = CommandRepository::new(
+            Box::new(FeatureCommand),
+            GitPath::CustomDirectory(repo_path.clone()),
+        );
+        // the record of a checked out product cannot be updated, so nothing is moved
+        let mut interface = GitInterface::in_directory(repo_path.clone());
+        interface
+            .checkout(&QualifiedPath::from("main/product/myprod"))
+            .unwrap();
+        assert!(
+            repo.execute(ArgSource::SUPPLIED(vec!["feature", "mv", "root", "base"]))
+                .is_err()
+        );
+        interface.reload_model().unwrap();
+        assert!(
+            interface
+                .get_model()
+                .has_branch(&QualifiedPath::from("main/feature/root"))
+        );
+        interface.checkout(&QualifiedPath::from("main")).unwrap();
+        repo.execute(ArgSource::SUPPLIED(vec!["feature", "mv", "root", "base"]))
+            .unwrap();
+
+        let interface = GitInterface::in_directory(repo_path);
+        let model = interface.get_model();
+        assert!(model.has_branch(&QualifiedPath::from("main/feature/base")));
+        assert!(model.has_branch(&QualifiedPath::from("main/feature/base/foo")));
+        assert!(!model.has_branch(&QualifiedPath::from("main/feature/root")));
+        assert!(!model.has_branch(&QualifiedPath::from("main/feature/root/foo")));
+        let records = interface
+            .get_derivation_records(&interface.get_current_area().unwrap())
+            .unwrap();
+        assert_eq!(
+            records[0].1.features(),
+            &vec![
+                QualifiedPath::from("main/feature/base/foo"),
+                QualifiedPath::from("main/feature/base/bar"),
+            ]
+        );
+        let configs = interface.read_product_configs().unwrap();
+        assert_eq!(
+            configs
+                .get(&QualifiedPath::from("main/product/myprod"))
+                .unwrap()
+                .features,
+            vec!["base/foo", "base/bar"]
+        );
+    }
+
+    #[test]
+    fn delete_feature_safely() {
+        let path = TempDir::new().unwrap();
+        let repo_path = PathBuf::from(path.path());
+        prepare_empty_git_repo(repo_path.clone()).unwrap();
+        populate_with_features(repo_path.clone()).unwrap();
+        CommandRepository::new(
+            Box::new(DeriveCommand),
+            GitPath::CustomDirectory(repo_path.clone()),
+        )
+        .execute(ArgSource::SUPPLIED(vec![
+            "derive", "-p", "myprod", "root/foo", "root/bar",
+        ]))
+        .unwrap();
+        let repo = CommandRepository::new(
+            Box::new(FeatureCommand),
+            GitPath::CustomDirectory(repo_path.clone()),
+        );
+        // has children
+        assert!(
+            repo.execute(ArgSource::SUPPLIED(vec!["feature", "-D", "root"]))
+                .is_err()
+        );
+        // referenced by a product
+        assert!(
+            repo.execute(ArgSource::SUPPLIED(vec!["feature", "-D", "root", "-r"]))
+                .is_err()
+        );
+        // checked out in another worktree
+        let other = TempDir::new().unwrap();
+        let worktree = |args: &[&str]| {
+            std::process::Command::new("git")
+                .arg("worktree")
+                .args(args)
+                .current_dir(&repo_path)
+                .status()
+                .unwrap()
+        };
+        let other_path = other.path().join("baz");
+        let other_path = other_path.to_str().unwrap();
+        assert!(worktree(&["add", other_path, "_main/_feature/_root/baz"]).success());
+        assert!(
+            repo.execute(ArgSource::SUPPLIED(vec![
+                "feature", "-D", "root", "-r", "--force",
+            ]))
+            .is_err()
+        );
+        let interface = GitInterface::in_directory(repo_path.clone());
+        assert!(
+            interface
+                .get_model()
+                .has_branch(&QualifiedPath::from("main/feature/root/foo"))
+        );
+        assert!(worktree(&["remove", other_path]).success());
+        repo.execute(ArgSource::SUPPLIED(vec![
+            "feature", "-D", "root", "-r", "--force",
+        ]))
+        .unwrap();
+        let interface = GitInterface::in_directory(repo_path.clone());
+        assert!(
+            interface
+                .get_current_area()
+                .unwrap()
+                .to_feature_root()
+                .is_none()
+        );
+
+        repo.execute(ArgSource::SUPPLIED(vec!["feature", "--restore", "root"]))
+            .unwrap();
+        let interface = GitInterface::in_directory(repo_path);
+        let model = interface.get_model();
+        assert!(model.has_branch(&QualifiedPath::from("main/feature/root")));
+        assert!(model.has_branch(&QualifiedPath::from("main/feature/root/foo")));
+        assert!(model.has_branch(&QualifiedPath::from("main/feature/root/baz")));
+    }
+
+    #[test]
+    fn feature_lifecycle() {
+        let path = TempDir::new().unwrap();
+        let repo_path = PathBuf::from(path.path());
+        prepare_empty_git_repo(repo_path.clone()).unwrap();
+        populate_with_features(repo_path.clone()).unwrap();
+        let repo = CommandRepository::new(
+            Box::new(FeatureCommand),
+            GitPath::CustomDirectory(repo_path.clone()),
+        );
+        let derive = CommandRepository::new(
+            Box::new(DeriveCommand),
+            GitPath::CustomDirectory(repo_path.clone()),
+        );
+        repo.execute(ArgSource::SUPPLIED(vec![
+            "feature",
+            "set",
+            "root/foo",
+            "--state",
+            "deprecated",
+            "--owner",
+            "alice",
+        ]))
+        .unwrap();
+        let foo = QualifiedPath::from("main/feature/root/foo");
+        let interface = GitInterface::in_directory(repo_path.clone());
+        let metadata = interface
+            .get_model()
+            .get_node_path(&foo)
+            .unwrap()
+            .get_metadata()
+            .get_feature_metadata()
+            .clone();
+        assert_eq!(metadata.get_state(), Some(FeatureState::Deprecated));
+        assert_eq!(metadata.get_owner(), Some(&"alice".to_string()));
+        derive
+            .execute(ArgSource::SUPPLIED(vec![
+                "derive", "-p", "old", "root/foo", "root/bar",
+            ]))
+            .unwrap();
+
+        repo.execute(ArgSource::SUPPLIED(vec!["feature", "archive", "root/foo"]))
+            .unwrap();
+        let interface = GitInterface::in_directory(repo_path.clone());
+        assert!(!interface.get_model().has_branch(&foo));
+        assert!(interface.get_model().is_archived(&foo));
+        assert!(!interface.get_commit_history(&foo).unwrap().is_empty());
+        assert!(
+            derive
+                .execute(ArgSource::SUPPLIED(vec![
+                    "derive", "-p", "new", "root/foo", "root/bar",
+                ]))
+                .is_err()
+        );
+
+        repo.execute(ArgSource::SUPPLIED(vec![
+            "feature", "archive", "--undo", "root/foo",
+        ]))
+        .unwrap();
+        let interface = GitInterface::in_directory(repo_path);
+        assert!(interface.get_model().has_branch(&foo));
+        assert!(!interface.get_model().is_archived(&foo));
+    }
+
+    #[test]
+    fn show_feature_details() {
+        let path = TempDir::new().unwrap();
+        let repo_path = PathBuf::from(path.path());
+        prepare_empty_git_repo(repo_path.clone()).unwrap();
+        populate_with_features(repo_path.clone()).unwrap();
+        commit_file_to_branch(
+            repo_path.clone(),
+            "_main/_feature/_root/foo",
+            "foo.txt",
+            "foo",
+        )
+        .unwrap();
+        let repo = CommandRepository::new(
+            Box::new(FeatureCommand),
+            GitPath::CustomDirectory(repo_path.clone()),
+        );
+        repo.execute(ArgSource::SUPPLIED(vec![
+            "feature",
+            "set",
+            "root/foo",
+            "--description",
+            "The foo feature",
+        ]))
+        .unwrap();
+        repo.execute(ArgSource::SUPPLIED(vec![
+            "feature", "set", "root/foo", "--owner", "alice",
+        ]))
+        .unwrap();
+        CommandRepository::new(
+            Box::new(DeriveCommand),
+            GitPath::CustomDirectory(repo_path.clone()),
+        )
+        .execute(ArgSource::SUPPLIED(vec![
+            "derive", "-p", "myprod", "root/foo",
+        ]))
+        .unwrap();
+        repo.execute(ArgSource::SUPPLIED(vec!["feature", "show", "root/foo"]))
+            .unwrap();
+        assert!(
+            repo.execute(ArgSource::SUPPLIED(vec!["feature", "show", "root/nope"]))
+                .is_err()
+        );
+
+        let interface = GitInterface::in_directory(repo_path);
+        let foo = QualifiedPath::from("main/feature/root/foo");
+        let root = QualifiedPath::from("main/feature/root");
+        let lines = show_feature(&foo, &interface).unwrap();
+        for expected in [
+            "Description: The foo feature",
+            "Owner: alice",
+            "Parents: root",
+            "Children: none",
+            "Commits ahead of root: 1",
+            "Files owned relative to root:",
+            "  foo.txt",
+            "Products: myprod",
+        ] {
+            assert!(lines.contains(&expected.to_string()), "{:?}", lines);
+        }
+        let lines = show_feature(&root, &interface).unwrap();
+        assert!(lines.contains(&"Parents: none".to_string()), "{:?}", lines);
+        let mut children = lines
+            .iter()
+            .find_map(|line| line.strip_prefix("Children: "))
+            .unwrap()
+            .split(", ")
+            .collect::<Vec<_>>();
+        children.sort();
+        assert_eq!(children, vec!["root/bar", "root/baz", "root/foo"]);
+        assert_eq!(interface.count_commits_not_in(&foo, &root).unwrap(), 1);
+        assert_eq!(
+            interface.get_files_changed_since(&foo, &root).unwrap(),
+            vec!["foo.txt".to_string()]
+        );
+        assert!(
+            interface
+                .get_files_changed_since(&root, &foo)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn move_feature_refuses_collision() {
+        let path = TempDir::new().unwrap();
+        let repo_path = PathBuf::from(path.path());
+        prepare_empty_git_repo(repo_path.clone()).unwrap();
+        populate_with_features(repo_path.clone()).unwrap();
+        let result = CommandRepository::new(
+            Box::new(FeatureCommand),
+            GitPath::CustomDirectory(repo_path.clone()),
+        )
+        .execute(ArgSource::SUPPLIED(vec![
+            "feature", "mv", "root/foo", "root/bar",
+        ]));
+        assert!(result.is_err());
+        let interface = GitInterface::in_directory(repo_path);
+        assert!(
+            interface
+                .get_model()
+                .has_branch(&QualifiedPath::from("main/feature/root/foo"))
+        );
+    }
+
+    #[test]
+    fn move_feature_is_undone_on_failure() {
+        let path = TempDir::new().unwrap();
+        let repo_path = PathBuf::from(path.path());
+        prepare_empty_git_repo(repo_path.clone()).unwrap();
+        populate_with_features(repo_path.clone()).unwrap();
+        // a stale lock lets the rename of root/foo fail after root was renamed
+        let lock_dir = repo_path.join(".git/refs/heads/_main/_feature/_base");
+        std::fs::create_dir_all(&lock_dir).unwrap();
+        std::fs::write(lock_dir.join("foo.lock"), "").unwrap();
+        let result = CommandRepository::new(
+            Box::new(FeatureCommand),
+            GitPath::CustomDirectory(repo_path.clone()),
+        )
+        .execute(ArgSource::SUPPLIED(vec!["feature", "mv", "root", "base"]));
+        assert!(result.is_err());
+        let interface = GitInterface::in_directory(repo_path);
+        let model = interface.get_model();
+        assert!(model.has_branch(&QualifiedPath::from("main/feature/root")));
+        assert!(model.has_branch(&QualifiedPath::from("main/feature/root/foo")));
+        assert!(!model.has_branch(&QualifiedPath::from("main/feature/base")));
+    }
+
+    #[test]
+    fn create_and_sync_features_from_parent() {
+        let path = TempDir::new().unwrap();
+        let repo_path = PathBuf::from(path.path());
+        prepare_empty_git_repo(repo_path.clone()).unwrap();
+        populate_with_features(repo_path.clone()).unwrap();
+        commit_file_to_branch(repo_path.clone(), "_main/_feature/_root/foo", "foo", "foo").unwrap();
+        let mut interface = GitInterface::in_directory(repo_path.clone());
+        interface
+            .checkout(&QualifiedPath::from("main/feature/root"))
+            .unwrap();
+        let repo = CommandRepository::new(
+            Box::new(FeatureCommand),
+            GitPath::CustomDirectory(repo_path.clone()),
+        );
+        repo.execute(ArgSource::SUPPLIED(vec!["feature", "foo/child"]))
+            .unwrap();
+        interface.reload_model().unwrap();
+        let root = QualifiedPath::from("main/feature/root");
+        let foo = QualifiedPath::from("main/feature/root/foo");
+        let child = QualifiedPath::from("main/feature/root/foo/child");
+        assert_eq!(interface.count_commits_not_in(&foo, &child).unwrap(), 0);
+
+        commit_file_to_branch(repo_path.clone(), "_main/_feature/root", "root", "root").unwrap();
+        assert_eq!(interface.count_commits_not_in(&root, &foo).unwrap(), 1);
+        repo.execute(ArgSource::SUPPLIED(vec![
+            "feature", "sync", "-r", "root/foo",
+        ]))
+        .unwrap();
+        assert_eq!(interface.count_commits_not_in(&root, &foo).unwrap(), 0);
+        assert_eq!(interface.count_commits_not_in(&foo, &child).unwrap(), 0);
+        assert_eq!(
+            interface.get_current_qualified_path().unwrap(),
+            QualifiedPath::from("main/feature/root")
+        );
+
+        commit_file_to_branch(repo_path.clone(), "_main/_feature/_root/bar", "foo", "bar").unwrap();
+        interface
+            .checkout(&QualifiedPath::from("main/feature/root/bar"))
+            .unwrap();
+        commit_file_to_branch(repo_path.clone(), "_main/_feature/root", "foo", "root").unwrap();
+        let bar = QualifiedPath::from("main/feature/root/bar");
+        let missing = interface.count_commits_not_in(&root, &bar).unwrap();
+        let result = repo.execute(ArgSource::SUPPLIED(vec!["feature", "sync", "--rebase"]));
+        assert!(result.is_err());
+        assert_eq!(
+            interface.count_commits_not_in(&root, &bar).unwrap(),
+            missing
+        );
+        assert_eq!(interface.get_current_qualified_path().unwrap(), bar);
+    }
+
+    #[test]
+    fn sync_skips_branches_checked_out_elsewhere() {
+        let path = TempDir::new().unwrap();
+        let repo_path = PathBuf::from(path.path());
+        prepare_empty_git_repo(repo_path.clone()).unwrap();
+        populate_with_features(repo_path.clone()).unwrap();
+        let other = TempDir::new().unwrap();
+        let other_path = other.path().join("bar");
+        let status = std::process::Command::new("git")
+            .arg("worktree")
+            .arg("add")
+            .arg(&other_path)
+            .arg("_main/_feature/_root/bar")
+            .current_dir(&repo_path)
+            .status()
+            .unwrap();
+        assert!(status.success());
+        commit_file_to_branch(repo_path.clone(), "_main/_feature/root", "root", "root").unwrap();
+        let interface = GitInterface::in_directory(repo_path.clone());
+        let root = QualifiedPath::from("main/feature/root");
+        let foo = QualifiedPath::from("main/feature/root/foo");
+        let bar = QualifiedPath::from("main/feature/root/bar");
+        let baz = QualifiedPath::from("main/feature/root/baz");
+        let repo = CommandRepository::new(
+            Box::new(FeatureCommand),
+            GitPath::CustomDirectory(repo_path.clone()),
+        );
+        let result = repo.execute(ArgSource::SUPPLIED(vec!["feature", "sync", "-r", "root"]));
+        assert!(result.is_err());
+        assert_eq!(interface.count_commits_not_in(&root, &bar).unwrap(), 1);
+        assert_eq!(interface.count_commits_not_in(&root, &foo).unwrap(), 0);
+        assert_eq!(interface.count_commits_not_in(&root, &baz).unwrap(), 0);
+    }
+}
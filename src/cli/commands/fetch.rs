@@ -0,0 +1,26 @@
+use crate::cli::*;
+use clap::Command;
+use std::error::Error;
+
+#[derive(Clone, Debug)]
+pub struct FetchCommand;
+impl CommandDefinition for FetchCommand {
+    fn build_command(&self) -> Command {
+        Command::new("fetch")
+            .about("Fetch all branches and tags of the current area and the tangl metadata from a remote")
+            .disable_help_subcommand(true)
+            .arg(make_remote())
+    }
+}
+impl CommandInterface for FetchCommand {
+    fn run_command(&self, context: &mut CommandContext) -> Result<(), Box<dyn Error>> {
+        let remote = context
+            .arg_helper
+            .get_argument_value::<String>("remote")
+            .unwrap();
+        let area = context.git.get_current_area()?.get_qualified_path();
+        let output = context.git.fetch(&remote, &area)?;
+        context.log_from_output(&output);
+        Ok(())
+    }
+}
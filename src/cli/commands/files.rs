@@ -0,0 +1,144 @@
+use crate::cli::*;
+use crate::git::ownership::{FileOwnership, HOTSPOT_THRESHOLD};
+use crate::model::QualifiedPath;
+use clap::{Arg, ArgAction, Command};
+use colored::Colorize;
+use std::error::Error;
+
+fn format_owners(owners: &[QualifiedPath]) -> String {
+    owners
+        .iter()
+        .map(|owner| {
+            if owner.len() > 2 {
+                owner.strip_n_left(2).to_string()
+            } else {
+                owner.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[derive(Clone, Debug)]
+pub struct FilesCommand;
+impl CommandDefinition for FilesCommand {
+    fn build_command(&self) -> Command {
+        Command::new("files")
+            .about("Show which features own the files of the current area")
+            .disable_help_subcommand(true)
+            .arg(
+                Arg::new("hotspots")
+                    .long("hotspots")
+                    .action(ArgAction::SetTrue)
+                    .help(
+                        "Only show files owned by several features, which are likely to conflict",
+                    ),
+            )
+    }
+}
+impl CommandInterface for FilesCommand {
+    fn run_command(&self, context: &mut CommandContext) -> Result<(), Box<dyn Error>> {
+        let hotspots_only = context
+            .arg_helper
+            .get_argument_value::<bool>("hotspots")
+            .unwrap();
+        let area = context.git.get_current_area()?;
+        let ownership = FileOwnership::compute(context.git, &area)?;
+        if hotspots_only {
+            let hotspots = ownership.get_hotspots();
+            if hotspots.is_empty() {
+                context.log_to_stdout("No files are owned by several features");
+            }
+            for (file, owners) in hotspots {
+                context.log_to_stdout(format!(
+                    "{} ({} features): {}",
+                    file,
+                    owners.len(),
+                    format_owners(owners)
+                ));
+            }
+            return Ok(());
+        }
+        for (file, owners) in ownership.iter() {
+            let marker = if owners.len() >= HOTSPOT_THRESHOLD {
+                format!(" {}", "hotspot".red())
+            } else {
+                String::new()
+            };
+            context.log_to_stdout(format!("{}: {}{}", file, format_owners(owners), marker));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::interface::test_utils::{
+        commit_file_to_branch, populate_with_features, prepare_empty_git_repo,
+    };
+    use crate::git::interface::{GitInterface, GitPath};
+    use std::path::PathBuf;
+    use tempfile::TempDir;
+
+    #[test]
+    fn files_are_owned_by_features() {
+        let path = TempDir::new().unwrap();
+        let repo_path = PathBuf::from(path.path());
+        prepare_empty_git_repo(repo_path.clone()).unwrap();
+        populate_with_features(repo_path.clone()).unwrap();
+        commit_file_to_branch(repo_path.clone(), "_main/_feature/root", "root", "root").unwrap();
+        commit_file_to_branch(
+            repo_path.clone(),
+            "_main/_feature/_root/foo",
+            "shared",
+            "foo",
+        )
+        .unwrap();
+        commit_file_to_branch(
+            repo_path.clone(),
+            "_main/_feature/_root/bar",
+            "shared",
+            "bar",
+        )
+        .unwrap();
+        commit_file_to_branch(repo_path.clone(), "_main/_feature/_root/bar", "bar", "bar").unwrap();
+
+        let interface = GitInterface::in_directory(repo_path.clone());
+        let area = interface.get_current_area().unwrap();
+        let ownership = FileOwnership::compute(&interface, &area).unwrap();
+        let main = QualifiedPath::from("main");
+        let root = QualifiedPath::from("main/feature/root");
+        let foo = QualifiedPath::from("main/feature/root/foo");
+        let bar = QualifiedPath::from("main/feature/root/bar");
+        assert_eq!(ownership.get_owners("file1"), std::slice::from_ref(&main));
+        assert_eq!(ownership.get_owners("root"), std::slice::from_ref(&root));
+        assert_eq!(ownership.get_owners("bar"), std::slice::from_ref(&bar));
+        let hotspots = ownership.get_hotspots();
+        assert_eq!(hotspots.len(), 1);
+        assert_eq!(hotspots[0].0, "shared");
+
+        let candidates = vec![root.clone(), foo.clone(), bar.clone()];
+        let files = |files: &[&str]| files.iter().map(|f| f.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            ownership.get_features_owning_all(&files(&["bar"]), &candidates),
+            vec![bar.clone()]
+        );
+        assert_eq!(
+            ownership.get_features_owning_all(&files(&["shared"]), &candidates),
+            vec![foo.clone(), bar.clone()]
+        );
+        assert!(
+            ownership
+                .get_features_owning_all(&files(&["bar", "root"]), std::slice::from_ref(&foo))
+                .is_empty()
+        );
+
+        CommandRepository::new(
+            Box::new(FilesCommand),
+            GitPath::CustomDirectory(repo_path.clone()),
+        )
+        .execute(ArgSource::SUPPLIED(vec!["files", "--hotspots"]))
+        .unwrap();
+    }
+}
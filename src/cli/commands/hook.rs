@@ -0,0 +1,352 @@
+use crate::cli::*;
+use crate::git::conflict::{ConflictChecker, ConflictStatistic};
+use crate::model::{ConflictRecord, NodePathType, QualifiedPath};
+use clap::{Arg, ArgAction, Command};
+use std::error::Error;
+use std::fs::{create_dir_all, read_to_string, remove_file, write};
+use std::io::Read;
+
+const HOOK_POST_COMMIT: &str = "post-commit";
+const HOOK_PRE_PUSH: &str = "pre-push";
+const HOOK_MARKER: &str = "# installed by tangl";
+
+fn make_hook_script(hook: &str, block: bool) -> String {
+    let block = if block { " --block" } else { "" };
+    format!(
+        "#!/bin/sh\n{}\nexec tangl hook run {}{} \"$@\"\n",
+        HOOK_MARKER, hook, block
+    )
+}
+
+fn is_tangl_hook(content: &str) -> bool {
+    content.lines().any(|line| line == HOOK_MARKER)
+}
+
+fn install_hooks(block: bool, force: bool, context: &CommandContext) -> Result<(), Box<dyn Error>> {
+    let hooks_dir = context.git.get_hooks_dir()?;
+    create_dir_all(&hooks_dir)?;
+    for hook in [HOOK_POST_COMMIT, HOOK_PRE_PUSH] {
+        let path = hooks_dir.join(hook);
+        if path.exists() && !force && !is_tangl_hook(&read_to_string(&path)?) {
+            return Err(Box::new(CommandError::new(
+                format!(
+                    "Cannot install {} hook: {} already exists. Use --force to replace it.",
+                    hook,
+                    path.display()
+                )
+                .as_str(),
+            )));
+        }
+    }
+    for hook in [HOOK_POST_COMMIT, HOOK_PRE_PUSH] {
+        let path = hooks_dir.join(hook);
+        write(&path, make_hook_script(hook, block))?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))?;
+        }
+        context.log_to_stdout(format!("Installed {} hook", hook));
+    }
+    Ok(())
+}
+
+fn uninstall_hooks(context: &CommandContext) -> Result<(), Box<dyn Error>> {
+    let hooks_dir = context.git.get_hooks_dir()?;
+    for hook in [HOOK_POST_COMMIT, HOOK_PRE_PUSH] {
+        let path = hooks_dir.join(hook);
+        if path.exists() && is_tangl_hook(&read_to_string(&path)?) {
+            remove_file(&path)?;
+            context.log_to_stdout(format!("Removed {} hook", hook));
+        }
+    }
+    Ok(())
+}
+
+/// Returns the feature branches of the current area among the local refs of a pre-push
+/// hook input, skipping deleted refs.
+fn parse_pushed_features(input: &str, context: &CommandContext) -> Vec<QualifiedPath> {
+    let feature_root = match context.git.get_current_area() {
+        Ok(area) => area.get_path_to_feature_root(),
+        Err(_) => return vec![],
+    };
+    input
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let local_ref = parts.next()?;
+            let local_hash = parts.next()?;
+            if local_hash.chars().all(|c| c == '0') {
+                return None;
+            }
+            context
+                .git
+                .get_model()
+                .get_naming()
+                .parse_ref_name(local_ref.strip_prefix("refs/heads/")?)
+        })
+        .filter(|path| {
+            path.len() > feature_root.len()
+                && path.strip_n_right(feature_root.len()) == feature_root
+                && context.git.get_model().has_branch(path)
+        })
+        .collect()
+}
+
+/// Checks `feature` against all other features of the area, stores the results in the conflict
+/// cache and returns the pairs that conflict now but did not before.
+fn recheck_feature(
+    feature: &QualifiedPath,
+    context: &CommandContext,
+) -> Result<Vec<ConflictRecord>, Box<dyn Error>> {
+    let features = match context.git.get_current_area()?.to_feature_root() {
+        Some(feature_root) => collect_branch_features(feature_root.to_any_type()),
+        None => vec![],
+    };
+    let checker = ConflictChecker::new(context.git);
+    let statistics = checker
+        .check_against(feature, &features)?
+        .collect::<Vec<_>>();
+    for statistic in statistics.iter() {
+        if !matches!(statistic, ConflictStatistic::OK(_)) {
+            context.log_to_stderr(statistic.to_string());
+        }
+    }
+    Ok(checker.record(&statistics)?)
+}
+
+fn run_hook(hook: &str, block: bool, context: &CommandContext) -> Result<(), Box<dyn Error>> {
+    let features = if hook == HOOK_PRE_PUSH {
+        let mut input = String::new();
+        std::io::stdin().read_to_string(&mut input)?;
+        parse_pushed_features(&input, context)
+    } else {
+        // commits on a detached HEAD or a foreign branch are not checked
+        match context.git.get_current_qualified_path() {
+            Ok(current)
+                if context
+                    .git
+                    .get_model()
+                    .get_node_path(&current)
+                    .is_some_and(|path| matches!(path.concretize(), NodePathType::Feature(_))) =>
+            {
+                vec![current]
+            }
+            _ => vec![],
+        }
+    };
+    let mut regressions = Vec::new();
+    for feature in features.iter() {
+        regressions.extend(recheck_feature(feature, context)?);
+    }
+    for record in regressions.iter() {
+        context.log_to_stderr(format!(
+            "Warning: {} and {} conflict now, but merged cleanly before",
+            record.get_left_path().strip_n_left(2),
+            record.get_right_path().strip_n_left(2)
+        ));
+    }
+    if block && hook == HOOK_PRE_PUSH && !regressions.is_empty() {
+        return Err(Box::new(CommandError::new(
+            format!(
+                "Push blocked: {} pair(s) of features conflict now",
+                regressions.len()
+            )
+            .as_str(),
+        )));
+    }
+    Ok(())
+}
+
+#[derive(Clone, Debug)]
+pub struct HookCommand;
+
+impl CommandDefinition for HookCommand {
+    fn build_command(&self) -> Command {
+        Command::new("hook")
+            .about("Manages git hooks that recheck changed features for conflicts")
+            .disable_help_subcommand(true)
+            .subcommand_required(true)
+    }
+    fn get_subcommands(&self) -> Vec<Box<dyn CommandImpl>> {
+        vec![
+            Box::new(HookInstallCommand),
+            Box::new(HookUninstallCommand),
+            Box::new(HookRunCommand),
+        ]
+    }
+}
+
+impl CommandInterface for HookCommand {
+    fn run_command(&self, _context: &mut CommandContext) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct HookInstallCommand;
+
+impl CommandDefinition for HookInstallCommand {
+    fn build_command(&self) -> Command {
+        Command::new("install")
+            .about("Installs post-commit and pre-push hooks")
+            .disable_help_subcommand(true)
+            .arg(
+                Arg::new("block")
+                    .long("block")
+                    .action(ArgAction::SetTrue)
+                    .help("Blocks pushes that make previously mergeable features conflict"),
+            )
+            .arg(
+                Arg::new("force")
+                    .long("force")
+                    .action(ArgAction::SetTrue)
+                    .help("Replaces existing hooks that were not installed by tangl"),
+            )
+    }
+}
+
+impl CommandInterface for HookInstallCommand {
+    fn run_command(&self, context: &mut CommandContext) -> Result<(), Box<dyn Error>> {
+        let block = context
+            .arg_helper
+            .get_argument_value::<bool>("block")
+            .unwrap();
+        let force = context
+            .arg_helper
+            .get_argument_value::<bool>("force")
+            .unwrap();
+        install_hooks(block, force, context)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct HookUninstallCommand;
+
+impl CommandDefinition for HookUninstallCommand {
+    fn build_command(&self) -> Command {
+        Command::new("uninstall")
+            .about("Removes the hooks installed by tangl")
+            .disable_help_subcommand(true)
+    }
+}
+
+impl CommandInterface for HookUninstallCommand {
+    fn run_command(&self, context: &mut CommandContext) -> Result<(), Box<dyn Error>> {
+        uninstall_hooks(context)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct HookRunCommand;
+
+impl CommandDefinition for HookRunCommand {
+    fn build_command(&self) -> Command {
+        Command::new("run")
+            .hide(true)
+            .disable_help_subcommand(true)
+            .arg(
+                Arg::new("hook")
+                    .required(true)
+                    .value_parser([HOOK_POST_COMMIT, HOOK_PRE_PUSH]),
+            )
+            .arg(Arg::new("block").long("block").action(ArgAction::SetTrue))
+            .arg(Arg::new("args").num_args(0..).trailing_var_arg(true))
+    }
+}
+
+impl CommandInterface for HookRunCommand {
+    fn run_command(&self, context: &mut CommandContext) -> Result<(), Box<dyn Error>> {
+        let hook = context
+            .arg_helper
+            .get_argument_value::<String>("hook")
+            .unwrap();
+        let block = context
+            .arg_helper
+            .get_argument_value::<bool>("block")
+            .unwrap();
+        run_hook(&hook, block, context)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::interface::test_utils::{
+        commit_file_to_branch, populate_with_features, prepare_empty_git_repo,
+    };
+    use crate::git::interface::{GitInterface, GitPath};
+    use crate::model::ConflictStatus;
+    use std::path::PathBuf;
+    use std::process::Command as Process;
+    use tempfile::TempDir;
+
+    #[test]
+    fn install_and_uninstall_hooks() {
+        let path = TempDir::new().unwrap();
+        let repo_path = PathBuf::from(path.path());
+        prepare_empty_git_repo(repo_path.clone()).unwrap();
+        let repo = CommandRepository::new(
+            Box::new(HookCommand),
+            GitPath::CustomDirectory(repo_path.clone()),
+        );
+        let hooks_dir = repo_path.join(".git").join("hooks");
+        write(hooks_dir.join(HOOK_PRE_PUSH), "#!/bin/sh\n").unwrap();
+        assert!(
+            repo.execute(ArgSource::SUPPLIED(vec!["hook", "install"]))
+                .is_err()
+        );
+        repo.execute(ArgSource::SUPPLIED(vec![
+            "hook", "install", "--force", "--block",
+        ]))
+        .unwrap();
+        let script = read_to_string(hooks_dir.join(HOOK_PRE_PUSH)).unwrap();
+        assert!(script.contains("tangl hook run pre-push --block"));
+        repo.execute(ArgSource::SUPPLIED(vec!["hook", "install"]))
+            .unwrap();
+        repo.execute(ArgSource::SUPPLIED(vec!["hook", "uninstall"]))
+            .unwrap();
+        assert!(!hooks_dir.join(HOOK_POST_COMMIT).exists());
+        assert!(!hooks_dir.join(HOOK_PRE_PUSH).exists());
+    }
+
+    #[test]
+    fn post_commit_hook_records_conflicts() {
+        let path = TempDir::new().unwrap();
+        let repo_path = PathBuf::from(path.path());
+        prepare_empty_git_repo(repo_path.clone()).unwrap();
+        populate_with_features(repo_path.clone()).unwrap();
+        let check = CommandRepository::new(
+            Box::new(CheckCommand),
+            GitPath::CustomDirectory(repo_path.clone()),
+        );
+        check.execute(ArgSource::SUPPLIED(vec!["check"])).unwrap();
+        let foo = QualifiedPath::from("main/feature/root/foo");
+        let bar = QualifiedPath::from("main/feature/root/bar");
+        let interface = GitInterface::in_directory(repo_path.clone());
+        let cache = interface.read_conflict_cache().unwrap();
+        assert_eq!(cache.get(&foo, &bar).unwrap().status, ConflictStatus::Ok);
+
+        commit_file_to_branch(repo_path.clone(), "_main/_feature/_root/bar", "a", "bar").unwrap();
+        commit_file_to_branch(repo_path.clone(), "_main/_feature/_root/foo", "a", "foo").unwrap();
+        Process::new("git")
+            .args(["checkout", "_main/_feature/_root/foo"])
+            .current_dir(&repo_path)
+            .output()
+            .unwrap();
+        let hook = CommandRepository::new(
+            Box::new(HookCommand),
+            GitPath::CustomDirectory(repo_path.clone()),
+        );
+        hook.execute(ArgSource::SUPPLIED(vec!["hook", "run", "post-commit"]))
+            .unwrap();
+        let record = interface
+            .read_conflict_cache()
+            .unwrap()
+            .get(&bar, &foo)
+            .cloned()
+            .unwrap();
+        assert_eq!(record.status, ConflictStatus::Conflict);
+        assert_eq!(record.left_tip, interface.get_tip(&foo).unwrap());
+    }
+}
@@ -1,7 +1,65 @@
 use crate::cli::*;
-use clap::Command;
+use crate::model::QualifiedPath;
+use clap::{Arg, ArgAction, Command};
 use std::error::Error;
 
+/// Applies the naming options of the command to the naming scheme of the repository.
+fn configure_naming(context: &mut CommandContext) -> Result<(), Box<dyn Error>> {
+    let mut naming = context.git.read_naming_scheme()?;
+    let previous = naming.clone();
+    if let Some(prefix) = context.arg_helper.get_argument_value::<String>("prefix") {
+        naming.prefix = prefix;
+    }
+    if let Some(feature_root) = context
+        .arg_helper
+        .get_argument_value::<String>("feature_root")
+    {
+        naming.feature_root = feature_root;
+    }
+    if let Some(product_root) = context
+        .arg_helper
+        .get_argument_value::<String>("product_root")
+    {
+        naming.product_root = product_root;
+    }
+    if let Some(areas) = context.arg_helper.get_argument_values::<String>("area") {
+        naming.areas = areas;
+    }
+    if let Some(ignore) = context.arg_helper.get_argument_values::<String>("ignore") {
+        naming.ignore = ignore;
+    }
+    if naming == previous {
+        return Ok(());
+    }
+    for root in [&naming.feature_root, &naming.product_root] {
+        QualifiedPath::from(root.clone()).validate()?;
+    }
+    if naming.feature_root == naming.product_root {
+        return Err(Box::new(CommandError::new(
+            "The feature root and the product root must have different names",
+        )));
+    }
+    let old_branches = context
+        .git
+        .get_model()
+        .get_qualified_paths_with_branches()
+        .clone();
+    context.git.write_naming_scheme(&naming)?;
+    let hidden = old_branches
+        .iter()
+        .filter(|path| !context.git.get_model().has_branch(path))
+        .map(|path| path.to_string())
+        .collect::<Vec<_>>();
+    if !hidden.is_empty() {
+        context.log_to_stderr(format!(
+            "Warning: these branches do not belong to tangl anymore: {}",
+            hidden.join(", ")
+        ));
+    }
+    context.log_to_stdout(format!("Configured naming scheme:\n{}", naming.to_json()));
+    Ok(())
+}
+
 #[derive(Clone, Debug)]
 pub struct InitCommand;
 
@@ -10,6 +68,33 @@ impl CommandDefinition for InitCommand {
         Command::new("init")
             .about("Initialize a repository")
             .disable_help_subcommand(true)
+            .arg(
+                Arg::new("prefix")
+                    .long("prefix")
+                    .help("Prepended to the names of all branches and tags of tangl, e.g. tangl/"),
+            )
+            .arg(
+                Arg::new("feature_root")
+                    .long("feature-root")
+                    .help("The name of the node that contains the features of an area"),
+            )
+            .arg(
+                Arg::new("product_root")
+                    .long("product-root")
+                    .help("The name of the node that contains the products of an area"),
+            )
+            .arg(
+                Arg::new("area")
+                    .long("area")
+                    .action(ArgAction::Append)
+                    .help("An area managed by tangl. All areas are managed if omitted."),
+            )
+            .arg(
+                Arg::new("ignore")
+                    .long("ignore")
+                    .action(ArgAction::Append)
+                    .help("A pattern of branches and tags that do not belong to tangl, e.g. 'release-*'"),
+            )
     }
 }
 
@@ -17,6 +102,64 @@ impl CommandInterface for InitCommand {
     fn run_command(&self, context: &mut CommandContext) -> Result<(), Box<dyn Error>> {
         let output = context.git.initialize_repo()?;
         context.log_from_output(&output);
-        Ok(())
+        configure_naming(context)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::interface::test_utils::prepare_empty_git_repo;
+    use crate::git::interface::{GitInterface, GitPath};
+    use std::path::PathBuf;
+    use std::process::Command as Process;
+    use tempfile::TempDir;
+
+    #[test]
+    fn init_with_custom_naming_scheme() {
+        let path = TempDir::new().unwrap();
+        let repo_path = PathBuf::from(path.path());
+        prepare_empty_git_repo(repo_path.clone()).unwrap();
+        for branch in [
+            "release-1.0",
+            "_main/docs",
+            "_main/_features/root",
+            "_main/_variants/pro",
+        ] {
+            Process::new("git")
+                .args(["branch", branch])
+                .current_dir(&repo_path)
+                .output()
+                .unwrap();
+        }
+        let repo = CommandRepository::new(
+            Box::new(InitCommand),
+            GitPath::CustomDirectory(repo_path.clone()),
+        );
+        repo.execute(ArgSource::SUPPLIED(vec![
+            "init",
+            "--feature-root",
+            "features",
+            "--product-root",
+            "variants",
+            "--ignore",
+            "release-*",
+        ]))
+        .unwrap();
+        let interface = GitInterface::in_directory(repo_path.clone());
+        let model = interface.get_model();
+        assert!(model.has_branch(&QualifiedPath::from("main/features/root")));
+        assert!(model.has_branch(&QualifiedPath::from("main/variants/pro")));
+        assert!(
+            model
+                .get_area(&QualifiedPath::from("release-1.0"))
+                .is_none()
+        );
+        let area = model.get_area(&QualifiedPath::from("main")).unwrap();
+        assert_eq!(
+            area.get_path_to_product_root(),
+            QualifiedPath::from("main/variants")
+        );
+        assert!(area.to_feature_root().is_some());
     }
 }
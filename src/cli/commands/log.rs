@@ -0,0 +1,123 @@
+use crate::cli::completion::*;
+use crate::cli::*;
+use crate::model::{Commit, QualifiedPath};
+use clap::{Arg, Command};
+use std::error::Error;
+
+fn format_commit(commit: &Commit, paths: &[QualifiedPath]) -> String {
+    let hash = &commit.hash()[..commit.hash().len().min(7)];
+    if paths.is_empty() {
+        format!("{} {}", hash, commit.message())
+    } else {
+        let names = paths
+            .iter()
+            .map(|path| path.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("{} ({}) {}", hash, names, commit.message())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct LogCommand;
+impl CommandDefinition for LogCommand {
+    fn build_command(&self) -> Command {
+        Command::new("log")
+            .about("Show the commits of a feature that are not in its parent")
+            .disable_help_subcommand(true)
+            .arg(Arg::new("branch").help(
+                "The branch to show. Relative to the current branch unless it starts with /. Defaults to the current branch.",
+            ))
+    }
+}
+impl CommandInterface for LogCommand {
+    fn run_command(&self, context: &mut CommandContext) -> Result<(), Box<dyn Error>> {
+        let path = match context.arg_helper.get_argument_value::<String>("branch") {
+            Some(branch) => resolve_branch(&branch, context)?,
+            None => context.git.get_current_qualified_path()?,
+        };
+        let parent = get_parent_branch(&path, context)?;
+        let commits = context.git.get_commits_not_in(&path, &parent)?;
+        if commits.is_empty() {
+            context.log_to_stdout(format!(
+                "{} has no commits that are not in {}",
+                path, parent
+            ));
+        }
+        for (commit, paths) in commits.iter() {
+            context.log_to_stdout(format_commit(commit, paths));
+        }
+        Ok(())
+    }
+    fn shell_complete(
+        &self,
+        completion_helper: CompletionHelper,
+        context: &mut CommandContext,
+    ) -> Result<Vec<String>, Box<dyn Error>> {
+        match completion_helper.currently_editing() {
+            Some(arg) => match arg.get_id().as_str() {
+                "branch" => complete_branches(&completion_helper, context),
+                _ => Ok(vec![]),
+            },
+            None => Ok(vec![]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::interface::test_utils::{
+        commit_file_to_branch, populate_with_features, prepare_empty_git_repo,
+    };
+    use crate::git::interface::{GitInterface, GitPath};
+    use std::path::PathBuf;
+    use std::process::Command as Process;
+    use tempfile::TempDir;
+
+    #[test]
+    fn log_commits_unique_to_feature() {
+        let path = TempDir::new().unwrap();
+        let repo_path = PathBuf::from(path.path());
+        prepare_empty_git_repo(repo_path.clone()).unwrap();
+        populate_with_features(repo_path.clone()).unwrap();
+        commit_file_to_branch(repo_path.clone(), "_main/_feature/root", "root", "root").unwrap();
+        commit_file_to_branch(repo_path.clone(), "_main/_feature/_root/foo", "a", "a").unwrap();
+        commit_file_to_branch(repo_path.clone(), "_main/_feature/_root/foo", "b", "b").unwrap();
+        Process::new("git")
+            .args([
+                "tag",
+                "_main/_feature/_root/_foo/v1",
+                "_main/_feature/_root/foo",
+            ])
+            .current_dir(&repo_path)
+            .output()
+            .unwrap();
+        let interface = GitInterface::in_directory(repo_path.clone());
+
+        let commits = interface
+            .get_commits_not_in(
+                &QualifiedPath::from("main/feature/root/foo"),
+                &QualifiedPath::from("main/feature/root"),
+            )
+            .unwrap();
+        assert_eq!(commits.len(), 2);
+        assert_eq!(commits[0].0.message(), "change b");
+        assert_eq!(
+            commits[0].1,
+            vec![
+                QualifiedPath::from("main/feature/root/foo/v1"),
+                QualifiedPath::from("main/feature/root/foo"),
+            ]
+        );
+        assert!(commits[1].1.is_empty());
+
+        let repo = CommandRepository::new(
+            Box::new(LogCommand),
+            GitPath::CustomDirectory(repo_path.clone()),
+        );
+        repo.execute(ArgSource::SUPPLIED(vec!["log", "/main/feature/root/foo"]))
+            .unwrap();
+        assert!(repo.execute(ArgSource::SUPPLIED(vec!["log"])).is_err());
+    }
+}
@@ -0,0 +1,212 @@
+use crate::cli::*;
+use crate::model::{DerivationRecord, NamingScheme, QualifiedPath};
+use clap::{Arg, ArgAction, Command};
+use std::collections::HashSet;
+use std::error::Error;
+
+/// Decodes a branch name the way tangl did before names were escaped, which dropped every
+/// `_` and `*`.
+fn decode_legacy(naming: &NamingScheme, name: &str) -> QualifiedPath {
+    let name = name.strip_prefix(&naming.prefix).unwrap_or(name);
+    QualifiedPath::from(name.replace(['_', '*'], ""))
+}
+
+fn migrate(dry_run: bool, context: &mut CommandContext) -> Result<(), Box<dyn Error>> {
+    let naming = context.git.get_model().get_naming().clone();
+    let refs = context.git.list_path_refs()?;
+    // refs outside of the namespace of tangl are left alone
+    let decoded = refs
+        .iter()
+        .filter_map(|(namespace, name)| {
+            naming
+                .parse_ref_name(name)
+                .map(|path| (*namespace, name, path))
+        })
+        .collect::<Vec<_>>();
+    let branches = decoded
+        .iter()
+        .filter(|(namespace, _, _)| *namespace == "refs/heads/")
+        .map(|(_, _, path)| path.clone())
+        .collect::<HashSet<_>>();
+    let mut n_changes = 0;
+    let mut moved = Vec::new();
+    for (namespace, name, path) in decoded.iter() {
+        let canonical = naming.to_ref_name(path);
+        if canonical != **name {
+            n_changes += 1;
+            context.log_to_stdout(format!(
+                "Rename {}{} to {}{}",
+                namespace, name, namespace, canonical
+            ));
+            if !dry_run {
+                context.git.rename_ref(namespace, name, &canonical)?;
+            }
+        }
+        // metadata and configurations refer to features by the names tangl used to show
+        let legacy = decode_legacy(&naming, name);
+        if *namespace == "refs/heads/" && legacy != *path && !branches.contains(&legacy) {
+            moved.push((legacy, path.clone()));
+        }
+    }
+    // children first, so that moving a parent does not move them to a lossy name
+    moved.sort_by_key(|(legacy, _)| std::cmp::Reverse(legacy.len()));
+    let store = context.git.read_metadata_store()?;
+    let mut configs = context.git.read_product_configs()?;
+    moved.retain(|(legacy, _)| {
+        store.iter().any(|(path, _)| path.has_prefix(legacy))
+            || configs.iter().any(|config| {
+                DerivationRecord::new(config.get_feature_paths(&naming)).references(legacy)
+            })
+    });
+    for (legacy, path) in moved.iter() {
+        n_changes += 1;
+        context.log_to_stdout(format!(
+            "Move metadata and product configurations of {} to {}",
+            legacy.to_string(),
+            path.to_string()
+        ));
+        if !dry_run {
+            context.git.move_feature_metadata(legacy, path)?;
+            for config in configs.iter_mut() {
+                config.rename_feature(legacy, path, &naming);
+            }
+        }
+    }
+    if !dry_run && !moved.is_empty() {
+        context.git.write_product_configs(&configs)?;
+    }
+    if n_changes == 0 {
+        context.log_to_stdout("Nothing to migrate");
+    }
+    Ok(())
+}
+
+#[derive(Clone, Debug)]
+pub struct MigrateCommand;
+
+impl CommandDefinition for MigrateCommand {
+    fn build_command(&self) -> Command {
+        Command::new("migrate")
+            .about("Renames refs and references created with the lossy naming of older versions")
+            .disable_help_subcommand(true)
+            .arg(
+                Arg::new("dry_run")
+                    .long("dry-run")
+                    .action(ArgAction::SetTrue)
+                    .help("Only shows what would be changed"),
+            )
+    }
+}
+
+impl CommandInterface for MigrateCommand {
+    fn run_command(&self, context: &mut CommandContext) -> Result<(), Box<dyn Error>> {
+        let dry_run = context
+            .arg_helper
+            .get_argument_value::<bool>("dry_run")
+            .unwrap();
+        migrate(dry_run, context)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::interface::test_utils::prepare_empty_git_repo;
+    use crate::git::interface::{GitInterface, GitPath};
+    use crate::model::FeatureMetadata;
+    use std::path::PathBuf;
+    use std::process::Command as Process;
+    use tempfile::TempDir;
+
+    #[test]
+    fn migrate_legacy_names() {
+        let path = TempDir::new().unwrap();
+        let repo_path = PathBuf::from(path.path());
+        prepare_empty_git_repo(repo_path.clone()).unwrap();
+        for branch in [
+            "_scratch",
+            "_main/_feature/root",
+            "_main/_feature/_root/dark_mode",
+            "_main/_feature/_root/_hidden",
+        ] {
+            Process::new("git")
+                .args(["branch", branch])
+                .current_dir(&repo_path)
+                .output()
+                .unwrap();
+        }
+        let mut interface = GitInterface::in_directory(repo_path.clone());
+        let naming = NamingScheme {
+            ignore: vec!["_scratch".to_string()],
+            ..NamingScheme::default()
+        };
+        interface.write_naming_scheme(&naming).unwrap();
+        let mut metadata = FeatureMetadata::default();
+        metadata.set_owner(Some("alice".to_string()));
+        let mut store = interface.read_metadata_store().unwrap();
+        store.set(&QualifiedPath::from("main/feature/root/darkmode"), metadata);
+        interface.write_metadata_store(&store).unwrap();
+
+        let repo = CommandRepository::new(
+            Box::new(MigrateCommand),
+            GitPath::CustomDirectory(repo_path.clone()),
+        );
+        repo.execute(ArgSource::SUPPLIED(vec!["migrate", "--dry-run"]))
+            .unwrap();
+        assert_eq!(
+            interface
+                .list_path_refs()
+                .unwrap()
+                .iter()
+                .filter(|(_, name)| name.ends_with("_hidden"))
+                .count(),
+            1
+        );
+        repo.execute(ArgSource::SUPPLIED(vec!["migrate"])).unwrap();
+
+        let interface = GitInterface::in_directory(repo_path);
+        let model = interface.get_model();
+        assert!(model.has_branch(&QualifiedPath::from("main/feature/root/_hidden")));
+        assert!(model.has_branch(&QualifiedPath::from("main/feature/root/dark_mode")));
+        let store = interface.read_metadata_store().unwrap();
+        assert!(
+            interface
+                .list_path_refs()
+                .unwrap()
+                .contains(&("refs/heads/", "_scratch".to_string()))
+        );
+        assert_eq!(
+            store
+                .get(&QualifiedPath::from("main/feature/root/dark_mode"))
+                .get_owner(),
+            Some(&"alice".to_string())
+        );
+    }
+    #[test]
+    fn unreadable_naming_scheme_is_reported() {
+        let path = TempDir::new().unwrap();
+        let repo_path = PathBuf::from(path.path());
+        prepare_empty_git_repo(repo_path.clone()).unwrap();
+        Process::new("sh")
+            .args([
+                "-c",
+                "blob=$(echo '{' | git hash-object -w --stdin) && \
+                 tree=$(printf '100644 blob %s\tnaming.json\n' $blob | git mktree) && \
+                 git update-ref refs/tangl/data $(git commit-tree $tree -m broken)",
+            ])
+            .current_dir(&repo_path)
+            .output()
+            .unwrap();
+        let result = CommandRepository::new(
+            Box::new(MigrateCommand),
+            GitPath::CustomDirectory(repo_path),
+        )
+        .execute(ArgSource::SUPPLIED(vec!["migrate", "--dry-run"]));
+        assert!(
+            result
+                .unwrap_err()
+                .to_string()
+                .starts_with("Cannot read naming scheme")
+        );
+    }
+}
@@ -1,10 +1,23 @@
+mod blame;
 mod check;
 mod checkout;
 mod complete;
 mod derive;
+mod diff;
+mod doctor;
+mod exec;
 mod feature;
+mod fetch;
+mod files;
+mod hook;
 mod init;
+mod log;
+mod migrate;
+mod model;
 mod product;
+mod pull;
+mod push;
+mod sample;
 mod spread;
 mod status;
 mod tag;
@@ -12,13 +25,26 @@ mod tangl;
 mod tree;
 mod untie;
 
+pub use blame::*;
 pub use check::*;
 pub use checkout::*;
 pub use complete::*;
 pub use derive::*;
+pub use diff::*;
+pub use doctor::*;
+pub use exec::*;
 pub use feature::*;
+pub use fetch::*;
+pub use files::*;
+pub use hook::*;
 pub use init::*;
+pub use log::*;
+pub use migrate::*;
+pub use model::*;
 pub use product::*;
+pub use pull::*;
+pub use push::*;
+pub use sample::*;
 pub use spread::*;
 pub use status::*;
 pub use tag::*;
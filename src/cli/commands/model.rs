@@ -0,0 +1,301 @@
+use crate::cli::*;
+use crate::model::*;
+use clap::{Arg, Command};
+use std::error::Error;
+use std::fs::{read_to_string, write};
+
+const FORMAT_UVL: &str = "uvl";
+const FORMAT_FEATUREIDE: &str = "featureide-xml";
+
+fn make_format() -> Arg {
+    Arg::new("format")
+        .long("format")
+        .value_parser([FORMAT_UVL, FORMAT_FEATUREIDE])
+        .help("The format of the feature model")
+}
+
+fn export_model(format: &str, context: &CommandContext) -> Result<String, Box<dyn Error>> {
+    let area = context.git.get_current_area()?;
+    let store = context.git.read_metadata_store()?;
+    let (constraints, _) = store.partition_constraints(&area.get_path_to_feature_root());
+    let model = FeatureModel::from_area(&area, &constraints)?;
+    Ok(match format {
+        FORMAT_FEATUREIDE => model.to_featureide_xml(),
+        _ => model.to_uvl(),
+    })
+}
+
+fn import_model(
+    file: &str,
+    format: Option<String>,
+    context: &mut CommandContext,
+) -> Result<(), Box<dyn Error>> {
+    let format = format.unwrap_or(if file.ends_with(".xml") {
+        FORMAT_FEATUREIDE.to_string()
+    } else {
+        FORMAT_UVL.to_string()
+    });
+    let input = read_to_string(file)?;
+    let model = match format.as_str() {
+        FORMAT_FEATUREIDE => FeatureModel::from_featureide_xml(&input),
+        _ => FeatureModel::from_uvl(&input),
+    }
+    .map_err(|e| CommandError::new(format!("Cannot import {}: {}", file, e).as_str()))?;
+    let area = context.git.get_current_area()?;
+    let area_path = area.get_qualified_path();
+    let feature_root = area.get_path_to_feature_root();
+    drop(area);
+    let (paths, constraints) = model
+        .to_paths(&feature_root)
+        .map_err(|e| CommandError::new(format!("Cannot import {}: {}", file, e).as_str()))?;
+
+    for (path, is_abstract) in paths {
+        if is_abstract || context.git.get_model().has_branch(&path) {
+            continue;
+        }
+        let start = context
+            .git
+            .get_model()
+            .get_parent_with_branch(&path)
+            .unwrap_or(area_path.clone());
+        context.git.create_branch_from(&path, &start)?;
+        context.log_to_stdout(format!("Created feature {}", path.strip_n_left(2)));
+    }
+    let mut store = context.git.read_metadata_store()?;
+    let (_, mut others) = store.partition_constraints(&feature_root);
+    let n_constraints = constraints.len();
+    others.extend(constraints);
+    store.set_constraints(others);
+    context.git.write_metadata_store(&store)?;
+    context.log_to_stdout(format!("Imported {} constraint(s)", n_constraints));
+    Ok(())
+}
+
+#[derive(Clone, Debug)]
+pub struct ModelCommand;
+
+impl CommandDefinition for ModelCommand {
+    fn build_command(&self) -> Command {
+        Command::new("model")
+            .about(
+                "Shows the constraints of the feature model or exchanges it with modelling tools",
+            )
+            .disable_help_subcommand(true)
+    }
+    fn get_subcommands(&self) -> Vec<Box<dyn CommandImpl>> {
+        vec![
+            Box::new(ModelExportCommand),
+            Box::new(ModelImportCommand),
+            Box::new(ModelCountCommand),
+        ]
+    }
+}
+
+impl CommandInterface for ModelCommand {
+    fn run_command(&self, context: &mut CommandContext) -> Result<(), Box<dyn Error>> {
+        if context.arg_helper.has_subcommand() {
+            return Ok(());
+        }
+        let feature_root = context.git.get_current_area()?.get_path_to_feature_root();
+        let store = context.git.read_metadata_store()?;
+        let (constraints, _) = store.partition_constraints(&feature_root);
+        if constraints.is_empty() {
+            context.log_to_stdout("No constraints");
+        }
+        for constraint in constraints {
+            let relative = constraint.map_features(&|f| {
+                Ok::<String, ()>(
+                    QualifiedPath::from(f.to_string())
+                        .strip_n_left(feature_root.len())
+                        .to_string(),
+                )
+            });
+            context.log_to_stdout(relative.unwrap().to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ModelExportCommand;
+
+impl CommandDefinition for ModelExportCommand {
+    fn build_command(&self) -> Command {
+        Command::new("export")
+            .about("Exports the features and constraints of the current area")
+            .disable_help_subcommand(true)
+            .arg(make_format().default_value(FORMAT_UVL))
+            .arg(
+                Arg::new("output")
+                    .short('o')
+                    .long("output")
+                    .help("The file to write to. Prints the model if omitted."),
+            )
+    }
+}
+
+impl CommandInterface for ModelExportCommand {
+    fn run_command(&self, context: &mut CommandContext) -> Result<(), Box<dyn Error>> {
+        let format = context
+            .arg_helper
+            .get_argument_value::<String>("format")
+            .unwrap();
+        let exported = export_model(&format, context)?;
+        match context.arg_helper.get_argument_value::<String>("output") {
+            Some(file) => {
+                write(&file, exported)?;
+                context.log_to_stdout(format!("Exported feature model to {}", file));
+            }
+            None => context.log_to_stdout(exported),
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ModelImportCommand;
+
+impl CommandDefinition for ModelImportCommand {
+    fn build_command(&self) -> Command {
+        Command::new("import")
+            .about("Creates the features and constraints of a feature model in the current area")
+            .disable_help_subcommand(true)
+            .arg(
+                Arg::new("file")
+                    .required(true)
+                    .help("The feature model to import"),
+            )
+            .arg(make_format().help(
+                "The format of the feature model. Inferred from the file extension if omitted.",
+            ))
+    }
+}
+
+impl CommandInterface for ModelImportCommand {
+    fn run_command(&self, context: &mut CommandContext) -> Result<(), Box<dyn Error>> {
+        let file = context
+            .arg_helper
+            .get_argument_value::<String>("file")
+            .unwrap();
+        let format = context.arg_helper.get_argument_value::<String>("format");
+        import_model(&file, format, context)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ModelCountCommand;
+
+impl CommandDefinition for ModelCountCommand {
+    fn build_command(&self) -> Command {
+        Command::new("count")
+            .about("Counts the valid configurations of the feature model of the current area")
+            .disable_help_subcommand(true)
+    }
+}
+
+impl CommandInterface for ModelCountCommand {
+    fn run_command(&self, context: &mut CommandContext) -> Result<(), Box<dyn Error>> {
+        let area = context.git.get_current_area()?;
+        let store = context.git.read_metadata_store()?;
+        let (constraints, _) = store.partition_constraints(&area.get_path_to_feature_root());
+        match FeatureModelFormula::from_area(&area, &constraints).count() {
+            Some(count) => context.log_to_stdout(format!("{} valid configurations", count)),
+            None => context.log_to_stdout(format!("More than {} valid configurations", u128::MAX)),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::commands::FeatureCommand;
+    use crate::git::interface::test_utils::{populate_with_features, prepare_empty_git_repo};
+    use crate::git::interface::{GitInterface, GitPath};
+    use std::path::PathBuf;
+    use tempfile::TempDir;
+
+    #[test]
+    fn import_and_export_model() {
+        let path = TempDir::new().unwrap();
+        let repo_path = PathBuf::from(path.path());
+        prepare_empty_git_repo(repo_path.clone()).unwrap();
+        populate_with_features(repo_path.clone()).unwrap();
+        let model_dir = TempDir::new().unwrap();
+        let model_file = model_dir.path().join("model.uvl");
+        std::fs::write(
+            &model_file,
+            "features\n\tmain {abstract}\n\t\toptional\n\t\t\troot\n\t\t\t\toptional\n\t\t\t\t\tfoo\n\t\t\t\t\tnew\n\t\t\t\t\t\toptional\n\t\t\t\t\t\t\tleaf\nconstraints\n\tleaf => !foo\n",
+        )
+        .unwrap();
+        let repo = CommandRepository::new(
+            Box::new(ModelCommand),
+            GitPath::CustomDirectory(repo_path.clone()),
+        );
+        repo.execute(ArgSource::SUPPLIED(vec![
+            "model",
+            "import",
+            model_file.to_str().unwrap(),
+        ]))
+        .unwrap();
+        let interface = GitInterface::in_directory(repo_path.clone());
+        assert!(
+            interface
+                .get_model()
+                .has_branch(&QualifiedPath::from("main/feature/root/new/leaf"))
+        );
+        assert_eq!(
+            interface.read_metadata_store().unwrap().get_constraints()[0].to_string(),
+            "\"main/feature/root/new/leaf\" => !\"main/feature/root/foo\""
+        );
+
+        let export_file = model_dir.path().join("export.xml");
+        repo.execute(ArgSource::SUPPLIED(vec![
+            "model",
+            "export",
+            "--format",
+            "featureide-xml",
+            "-o",
+            export_file.to_str().unwrap(),
+        ]))
+        .unwrap();
+        let exported =
+            FeatureModel::from_featureide_xml(&read_to_string(export_file).unwrap()).unwrap();
+        let (paths, constraints) = exported
+            .to_paths(&QualifiedPath::from("main/feature"))
+            .unwrap();
+        assert_eq!(paths.len(), 6);
+        assert!(paths.contains(&(QualifiedPath::from("main/feature/root/baz"), false)));
+        assert_eq!(
+            constraints,
+            interface
+                .read_metadata_store()
+                .unwrap()
+                .get_constraints()
+                .clone()
+        );
+
+        // archived features with children stay in the model as abstract features
+        CommandRepository::new(
+            Box::new(FeatureCommand),
+            GitPath::CustomDirectory(repo_path.clone()),
+        )
+        .execute(ArgSource::SUPPLIED(vec!["feature", "archive", "root/new"]))
+        .unwrap();
+        let export_file = model_dir.path().join("archived.uvl");
+        repo.execute(ArgSource::SUPPLIED(vec![
+            "model",
+            "export",
+            "-o",
+            export_file.to_str().unwrap(),
+        ]))
+        .unwrap();
+        let exported = FeatureModel::from_uvl(&read_to_string(export_file).unwrap()).unwrap();
+        let (paths, _) = exported
+            .to_paths(&QualifiedPath::from("main/feature"))
+            .unwrap();
+        assert_eq!(paths.len(), 6);
+        assert!(paths.contains(&(QualifiedPath::from("main/feature/root/new"), true)));
+        assert!(paths.contains(&(QualifiedPath::from("main/feature/root/new/leaf"), false)));
+    }
+}
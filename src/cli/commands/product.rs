@@ -4,11 +4,98 @@ use crate::model::*;
 use clap::{Arg, Command};
 use std::error::Error;
 
-fn delete_product(product: QualifiedPath, context: &CommandContext) -> Result<(), Box<dyn Error>> {
+fn delete_product(
+    product: QualifiedPath,
+    recursive: bool,
+    context: &CommandContext,
+) -> Result<(), Box<dyn Error>> {
     let area = context.git.get_current_area()?;
     let complete_path = area.get_path_to_product_root() + product;
-    let output = context.git.delete_branch(&complete_path)?;
-    context.log_from_output(&output);
+    let display_path = complete_path.strip_n_left(1);
+    let node_path = match context.git.get_model().get_node_path(&complete_path) {
+        Some(path) => path,
+        None => {
+            return Err(Box::new(CommandError::new(
+                format!("Cannot delete product {}: does not exist", display_path).as_str(),
+            )));
+        }
+    };
+    let has_children = node_path
+        .iter_children()
+        .any(|child| !matches!(child.concretize(), NodePathType::Tag(_)));
+    if has_children && !recursive {
+        return Err(Box::new(CommandError::new(
+            format!(
+                "Cannot delete product {}: it has children. Use -r to delete them as well",
+                display_path
+            )
+            .as_str(),
+        )));
+    }
+    let mut subtree = vec![node_path.clone()];
+    subtree.extend(node_path.iter_children_req());
+    let mut branches: Vec<QualifiedPath> = Vec::new();
+    let mut tags: Vec<QualifiedPath> = Vec::new();
+    for path in subtree {
+        match path.concretize() {
+            NodePathType::Tag(tag) => tags.push(tag.get_qualified_path()),
+            NodePathType::Product(product) if product.get_metadata().has_branch() => {
+                branches.push(product.get_qualified_path());
+            }
+            _ => {}
+        }
+    }
+    drop(node_path);
+    drop(area);
+    for branch in branches.iter() {
+        let history = context.git.get_commit_history(branch)?;
+        match history
+            .iter()
+            .position(|commit| DerivationRecord::is_derivation_message(commit.message()))
+        {
+            Some(0) => {}
+            Some(n_commits) => context.log_to_stderr(format!(
+                "Warning: {} has {} commit(s) since the last derivation",
+                branch.strip_n_left(1),
+                n_commits
+            )),
+            None => context.log_to_stderr(format!(
+                "Warning: {} has no derivation record, its commits may not be part of any feature",
+                branch.strip_n_left(1)
+            )),
+        }
+    }
+    for tag in tags {
+        context.git.delete_tag_with_backup(&tag)?;
+    }
+    let mut configs = context.git.read_product_configs()?;
+    let mut configs_changed = false;
+    for branch in branches {
+        context.git.delete_branch_with_backup(&branch)?;
+        configs_changed |= configs.remove(&branch).is_some();
+        context.log_to_stdout(format!("Deleted product {}", branch.strip_n_left(1)));
+    }
+    if configs_changed {
+        context.git.write_product_configs(&configs)?;
+    }
+    context.log_to_stdout(format!(
+        "Undo with: tangl product --restore {}",
+        display_path.strip_n_left(1)
+    ));
+    Ok(())
+}
+fn restore_product(product: QualifiedPath, context: &CommandContext) -> Result<(), Box<dyn Error>> {
+    let area = context.git.get_current_area()?;
+    let complete_path = area.get_path_to_product_root() + product;
+    let restored = context.git.restore_backups(&complete_path)?;
+    if restored.is_empty() {
+        return Err(Box::new(CommandError::new(
+            format!("No backup found for {}", complete_path.strip_n_left(1)).as_str(),
+        )));
+    }
+    for branch in restored {
+        context.log_to_stdout(format!("Restored product {}", branch.strip_n_left(1)));
+    }
     Ok(())
 }
 fn print_product_tree(context: &CommandContext) -> Result<(), Box<dyn Error>> {
@@ -34,17 +121,28 @@ impl CommandDefinition for ProductCommand {
                     .short('D')
                     .help("Deletes a product branch"),
             )
+            .arg(make_recursive())
+            .arg(make_restore())
     }
 }
 impl CommandInterface for ProductCommand {
     fn run_command(&self, context: &mut CommandContext) -> Result<(), Box<dyn Error>> {
         let maybe_delete = context.arg_helper.get_argument_value::<String>("delete");
-        match maybe_delete {
-            Some(delete) => {
-                delete_product(QualifiedPath::from(delete), &context)?;
+        let maybe_restore = context.arg_helper.get_argument_value::<String>("restore");
+        let recursive = context
+            .arg_helper
+            .get_argument_value::<bool>("recursive")
+            .unwrap();
+        match (maybe_delete, maybe_restore) {
+            (Some(delete), _) => {
+                delete_product(QualifiedPath::from(delete), recursive, context)?;
                 Ok(())
             }
-            None => {
+            (None, Some(restore)) => {
+                restore_product(QualifiedPath::from(restore), context)?;
+                Ok(())
+            }
+            (None, None) => {
                 print_product_tree(&context)?;
                 Ok(())
             }
@@ -0,0 +1,283 @@
+use crate::cli::*;
+use crate::model::QualifiedPath;
+use clap::Command;
+use std::error::Error;
+
+/// Brings the local branches of `area` up to date with their remote-tracking branches.
+/// Missing branches are created, branches that are behind are fast-forwarded and diverged
+/// branches are merged. Branches that were deleted or archived locally are not recreated.
+///
+/// Returns the branches that could not be merged because of conflicts.
+fn integrate_upstream(
+    remote: &str,
+    area: &QualifiedPath,
+    context: &CommandContext,
+) -> Result<Vec<QualifiedPath>, Box<dyn Error>> {
+    let model = context.git.get_model();
+    let current_branch = context.git.get_current_qualified_path().ok();
+    let removed = context.git.get_removed_upstream_branches(remote, area)?;
+    let worktree = context.git.create_worktree()?;
+    let mut failed: Vec<QualifiedPath> = Vec::new();
+    for path in model.get_remote_branches(remote) {
+        if !path.has_prefix(area) {
+            continue;
+        }
+        if let Some((_, contained)) = removed.iter().find(|(removed, _)| removed == path) {
+            if *contained {
+                context.log_to_stdout(format!(
+                    "Skipped {}, it was deleted or archived locally. Push to delete it on {}.",
+                    path, remote
+                ));
+            } else {
+                context.log_to_stderr(format!(
+                    "Warning: {} was deleted or archived locally, but has new commits on {}. Restore it to integrate them.",
+                    path, remote
+                ));
+            }
+            continue;
+        }
+        if !model.has_branch(path) {
+            context.git.reset_to_upstream(remote, path)?;
+            context.log_to_stdout(format!("Created {} from {}", path, remote));
+            continue;
+        }
+        let (ahead, behind) = context.git.count_ahead_behind_upstream(remote, path)?;
+        if behind == 0 {
+            continue;
+        }
+        let is_current = current_branch.as_ref() == Some(path);
+        if ahead == 0 && !is_current {
+            context.git.reset_to_upstream(remote, path)?;
+            context.log_to_stdout(format!("Fast-forwarded {}", path));
+            continue;
+        }
+        // a branch cannot be checked out in two worktrees at once
+        let interface = if is_current {
+            &*context.git
+        } else {
+            worktree.checkout(path)?;
+            worktree.interface()
+        };
+        let output = interface.merge_upstream(remote, path)?;
+        if !output.status.success() {
+            interface.abort_merge()?;
+            context.log_to_stdout(format!(
+                "Unable to merge {} into {}: conflict",
+                remote, path
+            ));
+            failed.push(path.clone());
+        } else if ahead == 0 {
+            context.log_to_stdout(format!("Fast-forwarded {}", path));
+        } else {
+            context.log_to_stdout(format!(
+                "Merged {} commit(s) of {} into {}",
+                behind, remote, path
+            ));
+        }
+    }
+    Ok(failed)
+}
+
+#[derive(Clone, Debug)]
+pub struct PullCommand;
+impl CommandDefinition for PullCommand {
+    fn build_command(&self) -> Command {
+        Command::new("pull")
+            .about("Fetch the current area from a remote and integrate it into the local branches")
+            .disable_help_subcommand(true)
+            .arg(make_remote())
+    }
+}
+impl CommandInterface for PullCommand {
+    fn run_command(&self, context: &mut CommandContext) -> Result<(), Box<dyn Error>> {
+        let remote = context
+            .arg_helper
+            .get_argument_value::<String>("remote")
+            .unwrap();
+        let area = context.git.get_current_area()?.get_qualified_path();
+        let output = context.git.fetch(&remote, &area)?;
+        context.log_from_output(&output);
+        let failed = integrate_upstream(&remote, &area, context)?;
+        for name in context.git.update_metadata_from_remote(&remote)? {
+            context.log_to_stderr(format!(
+                "Warning: {} diverged from {} and was kept. Push with --force to replace the version of {}.",
+                name, remote, remote
+            ));
+        }
+        if !failed.is_empty() {
+            return Err(Box::new(CommandError::new(
+                format!(
+                    "{} branch(es) could not be merged. Merge them manually.",
+                    failed.len()
+                )
+                .as_str(),
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::commands::{FeatureCommand, PushCommand};
+    use crate::git::interface::test_utils::{
+        commit_file_to_branch, populate_with_features, prepare_empty_git_repo,
+    };
+    use crate::git::interface::{GitInterface, GitPath};
+    use crate::model::FeatureMetadata;
+    use std::path::Path;
+    use std::process::Command as Process;
+    use tempfile::TempDir;
+
+    fn git(repo_path: &Path, args: &[&str]) -> String {
+        let output = Process::new("git")
+            .args(args)
+            .current_dir(repo_path)
+            .output()
+            .unwrap();
+        String::from_utf8(output.stdout).unwrap()
+    }
+
+    #[test]
+    fn push_and_pull_area() {
+        let path = TempDir::new().unwrap();
+        let local = path.path().join("local");
+        let remote = path.path().join("remote.git");
+        let clone = path.path().join("clone");
+        std::fs::create_dir(&local).unwrap();
+        prepare_empty_git_repo(local.clone()).unwrap();
+        populate_with_features(local.clone()).unwrap();
+        commit_file_to_branch(local.clone(), "_main/_feature/_root/foo", "foo", "foo").unwrap();
+        let mut interface = GitInterface::in_directory(local.clone());
+        let foo = QualifiedPath::from("main/feature/root/foo");
+        let mut metadata = FeatureMetadata::default();
+        metadata.set_owner(Some("alice".to_string()));
+        interface.set_feature_metadata(&foo, metadata).unwrap();
+        git(
+            path.path(),
+            &["init", "--bare", "--initial-branch=main", "remote.git"],
+        );
+        git(
+            &local,
+            &["remote", "add", "origin", remote.to_str().unwrap()],
+        );
+
+        let push = CommandRepository::new(
+            Box::new(PushCommand),
+            GitPath::CustomDirectory(local.clone()),
+        );
+        push.execute(ArgSource::SUPPLIED(vec!["push"])).unwrap();
+        let remote_refs = git(&remote, &["for-each-ref", "--format=%(refname)"]);
+        assert!(remote_refs.contains("refs/heads/_main/_feature/_root/foo"));
+        assert!(remote_refs.contains("refs/tangl/data"));
+        let interface = GitInterface::in_directory(local.clone());
+        assert!(interface.get_model().has_remote_branch("origin", &foo));
+
+        git(
+            path.path(),
+            &["clone", "-q", remote.to_str().unwrap(), "clone"],
+        );
+        let pull = CommandRepository::new(
+            Box::new(PullCommand),
+            GitPath::CustomDirectory(clone.clone()),
+        );
+        pull.execute(ArgSource::SUPPLIED(vec!["pull"])).unwrap();
+        let cloned = GitInterface::in_directory(clone.clone());
+        assert!(cloned.get_model().has_branch(&foo));
+        assert!(
+            cloned
+                .get_model()
+                .has_branch(&QualifiedPath::from("main/feature/root/baz"))
+        );
+        assert_eq!(
+            cloned
+                .get_model()
+                .get_node_path(&foo)
+                .unwrap()
+                .get_metadata()
+                .get_feature_metadata()
+                .get_owner(),
+            Some(&"alice".to_string())
+        );
+
+        commit_file_to_branch(local.clone(), "_main/_feature/_root/foo", "a", "a").unwrap();
+        commit_file_to_branch(local.clone(), "_main/_feature/root", "root", "root").unwrap();
+        push.execute(ArgSource::SUPPLIED(vec!["push"])).unwrap();
+        commit_file_to_branch(clone.clone(), "_main/_feature/_root/foo", "c", "c").unwrap();
+        let mut cloned = GitInterface::in_directory(clone.clone());
+        cloned
+            .fetch("origin", &QualifiedPath::from("main"))
+            .unwrap();
+        assert_eq!(
+            cloned.count_ahead_behind_upstream("origin", &foo).unwrap(),
+            (1, 1)
+        );
+        pull.execute(ArgSource::SUPPLIED(vec!["pull"])).unwrap();
+        let root = QualifiedPath::from("main/feature/root");
+        assert_eq!(
+            cloned.count_ahead_behind_upstream("origin", &foo).unwrap(),
+            (2, 0)
+        );
+        assert_eq!(
+            cloned.count_ahead_behind_upstream("origin", &root).unwrap(),
+            (0, 0)
+        );
+    }
+
+    #[test]
+    fn pull_keeps_removed_features_removed() {
+        let path = TempDir::new().unwrap();
+        let local = path.path().join("local");
+        let remote = path.path().join("remote.git");
+        std::fs::create_dir(&local).unwrap();
+        prepare_empty_git_repo(local.clone()).unwrap();
+        populate_with_features(local.clone()).unwrap();
+        git(
+            path.path(),
+            &["init", "--bare", "--initial-branch=main", "remote.git"],
+        );
+        git(
+            &local,
+            &["remote", "add", "origin", remote.to_str().unwrap()],
+        );
+        let push = CommandRepository::new(
+            Box::new(PushCommand),
+            GitPath::CustomDirectory(local.clone()),
+        );
+        let pull = CommandRepository::new(
+            Box::new(PullCommand),
+            GitPath::CustomDirectory(local.clone()),
+        );
+        let feature = CommandRepository::new(
+            Box::new(FeatureCommand),
+            GitPath::CustomDirectory(local.clone()),
+        );
+        push.execute(ArgSource::SUPPLIED(vec!["push"])).unwrap();
+
+        feature
+            .execute(ArgSource::SUPPLIED(vec!["feature", "-D", "root/bar"]))
+            .unwrap();
+        feature
+            .execute(ArgSource::SUPPLIED(vec!["feature", "archive", "root/baz"]))
+            .unwrap();
+        pull.execute(ArgSource::SUPPLIED(vec!["pull"])).unwrap();
+        let bar = QualifiedPath::from("main/feature/root/bar");
+        let baz = QualifiedPath::from("main/feature/root/baz");
+        let interface = GitInterface::in_directory(local.clone());
+        assert!(!interface.get_model().has_branch(&bar));
+        assert!(!interface.get_model().has_branch(&baz));
+        assert!(interface.get_model().is_archived(&baz));
+
+        push.execute(ArgSource::SUPPLIED(vec!["push"])).unwrap();
+        let remote_refs = git(&remote, &["for-each-ref", "--format=%(refname)"]);
+        assert!(remote_refs.contains("refs/heads/_main/_feature/_root/foo"));
+        assert!(!remote_refs.contains("refs/heads/_main/_feature/_root/bar"));
+        assert!(!remote_refs.contains("refs/heads/_main/_feature/_root/baz"));
+        assert!(remote_refs.contains("refs/tangl/archive/_main/_feature/_root/baz"));
+        assert!(!remote_refs.contains("refs/tangl/backup/"));
+        let interface = GitInterface::in_directory(local.clone());
+        assert!(!interface.get_model().has_remote_branch("origin", &bar));
+        assert!(!interface.get_model().has_remote_branch("origin", &baz));
+    }
+}
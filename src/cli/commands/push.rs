@@ -0,0 +1,46 @@
+use crate::cli::*;
+use clap::{Arg, ArgAction, Command};
+use std::error::Error;
+
+#[derive(Clone, Debug)]
+pub struct PushCommand;
+impl CommandDefinition for PushCommand {
+    fn build_command(&self) -> Command {
+        Command::new("push")
+            .about(
+                "Push all branches and tags of the current area and the tangl metadata to a remote",
+            )
+            .disable_help_subcommand(true)
+            .arg(make_remote())
+            .arg(
+                Arg::new("force")
+                    .long("force")
+                    .action(ArgAction::SetTrue)
+                    .help("Overwrite refs of the remote that are not ancestors of the local ones"),
+            )
+    }
+}
+impl CommandInterface for PushCommand {
+    fn run_command(&self, context: &mut CommandContext) -> Result<(), Box<dyn Error>> {
+        let remote = context
+            .arg_helper
+            .get_argument_value::<String>("remote")
+            .unwrap();
+        let force = context
+            .arg_helper
+            .get_argument_value::<bool>("force")
+            .unwrap();
+        let area = context.git.get_current_area()?.get_qualified_path();
+        for (path, contained) in context.git.get_removed_upstream_branches(&remote, &area)? {
+            if !contained {
+                context.log_to_stderr(format!(
+                    "Warning: {} was deleted or archived locally, but has new commits on {}. It was not deleted on {}.",
+                    path, remote, remote
+                ));
+            }
+        }
+        let output = context.git.push(&remote, &area, force)?;
+        context.log_from_output(&output);
+        Ok(())
+    }
+}
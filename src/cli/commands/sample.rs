@@ -0,0 +1,246 @@
+use crate::cli::*;
+use crate::git::conflict::{ConflictChecker, ConflictStatistic, ConflictStatistics};
+use crate::model::*;
+use clap::{Arg, ArgAction, Command, value_parser};
+use std::error::Error;
+
+const SAMPLE_NAMESPACE: &str = "sample";
+
+/// Excludes every pair of features that is known to conflict.
+fn make_conflict_constraints(
+    features: &[QualifiedPath],
+    context: &CommandContext,
+) -> Result<Vec<Constraint>, Box<dyn Error>> {
+    context.log_to_stdout("Checking for conflicts");
+    let conflicts: ConflictStatistics = ConflictChecker::new(context.git)
+        .check(&features.to_vec())?
+        .collect();
+    if conflicts.n_errors() > 0 {
+        return Err("Errors occurred while checking for conflicts.".into());
+    }
+    Ok(conflicts
+        .iter_conflict()
+        .map(|statistic| match statistic {
+            ConflictStatistic::CONFLICT((l, r)) | ConflictStatistic::SEMANTIC_CONFLICT((l, r)) => {
+                Constraint::Not(Box::new(Constraint::And(
+                    Box::new(Constraint::Feature(l.to_string())),
+                    Box::new(Constraint::Feature(r.to_string())),
+                )))
+            }
+            _ => unreachable!(),
+        })
+        .collect())
+}
+
+fn derive_samples(
+    samples: &[Vec<QualifiedPath>],
+    area_path: &QualifiedPath,
+    context: &mut CommandContext,
+) -> Result<(), Box<dyn Error>> {
+    let area = context.git.get_model().get_area(area_path).unwrap();
+    let feature_root = area.get_path_to_feature_root();
+    // samples of a previous run that are not overwritten would be misleading, other products
+    // below the namespace are not touched
+    let stale = area
+        .to_product_root()
+        .and_then(|root| root.to_product(&QualifiedPath::from(SAMPLE_NAMESPACE)))
+        .map(|namespace| {
+            namespace
+                .iter_children()
+                .map(|child| child.get_qualified_path())
+                .filter(|path| {
+                    path.last()
+                        .and_then(|name| name.parse::<usize>().ok())
+                        .is_some_and(|n| n > samples.len())
+                })
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    for path in stale {
+        if context.git.get_model().has_branch(&path) {
+            context.git.delete_branch_with_backup(&path)?;
+            context.log_to_stdout(format!("Deleted stale sample {}", path.strip_n_left(2)));
+        }
+    }
+    let mut failed = 0;
+    for (i, sample) in samples.iter().enumerate() {
+        let mut config = ProductConfig::new(
+            QualifiedPath::from(SAMPLE_NAMESPACE) + QualifiedPath::from((i + 1).to_string()),
+            area_path.clone(),
+            sample
+                .iter()
+                .map(|f| f.strip_n_left(feature_root.len()))
+                .collect(),
+        );
+        config.options.replace = true;
+        config.options.complete = true;
+        context.log_to_stdout(format!(
+            "Deriving {}",
+            config
+                .get_product_path(context.git.get_model().get_naming())
+                .strip_n_left(2)
+        ));
+        match derive_product(&config, context) {
+            Ok(true) => {}
+            Ok(false) => failed += 1,
+            Err(e) => {
+                context.log_to_stderr(e.to_string());
+                failed += 1;
+            }
+        }
+    }
+    if failed > 0 {
+        return Err(Box::new(CommandError::new(
+            format!("Failed to derive {} sample(s)", failed).as_str(),
+        )));
+    }
+    Ok(())
+}
+
+#[derive(Clone, Debug)]
+pub struct SampleCommand;
+
+impl CommandDefinition for SampleCommand {
+    fn build_command(&self) -> Command {
+        Command::new("sample")
+            .about("Computes feature selections that cover all interactions of t features")
+            .disable_help_subcommand(true)
+            .arg(
+                Arg::new("t")
+                    .long("t")
+                    .value_parser(value_parser!(usize))
+                    .default_value("2")
+                    .help("The number of features whose interactions are covered"),
+            )
+            .arg(
+                Arg::new("derive")
+                    .long("derive")
+                    .action(ArgAction::SetTrue)
+                    .help("Derives each sample into a product below the sample namespace"),
+            )
+    }
+}
+
+impl CommandInterface for SampleCommand {
+    fn run_command(&self, context: &mut CommandContext) -> Result<(), Box<dyn Error>> {
+        let t = context.arg_helper.get_argument_value::<usize>("t").unwrap();
+        let area = context.git.get_current_area()?;
+        let area_path = area.get_qualified_path();
+        let feature_root = area.get_path_to_feature_root();
+        let mut features = FeatureModelFormula::from_area(&area, &[])
+            .get_features()
+            .iter()
+            .filter(|f| context.git.get_model().has_branch(f))
+            .cloned()
+            .collect::<Vec<_>>();
+        drop(area);
+        if features.is_empty() {
+            context.log_to_stdout("Nothing to sample: no features in tree");
+            return Ok(());
+        }
+        features.sort();
+        let (mut constraints, _) = context
+            .git
+            .read_metadata_store()?
+            .partition_constraints(&feature_root);
+        constraints.extend(make_conflict_constraints(&features, context)?);
+        let area = context.git.get_model().get_area(&area_path).unwrap();
+        let formula = FeatureModelFormula::from_area(&area, &constraints);
+        drop(area);
+        if !formula.is_satisfiable(&[]) {
+            return Err("Cannot sample: the feature model has no valid configuration".into());
+        }
+        let samples = sample_t_wise(&formula, &features, t.min(features.len()));
+        for (i, sample) in samples.iter().enumerate() {
+            let names = sample
+                .iter()
+                .map(|f| f.strip_n_left(feature_root.len()).to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            context.log_to_stdout(format!("Sample {}: {}", i + 1, names));
+        }
+        if context
+            .arg_helper
+            .get_argument_value::<bool>("derive")
+            .unwrap()
+        {
+            derive_samples(&samples, &area_path, context)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::interface::test_utils::{
+        commit_file_to_branch, populate_with_features, prepare_empty_git_repo,
+    };
+    use crate::git::interface::{GitInterface, GitPath};
+    use std::path::PathBuf;
+    use tempfile::TempDir;
+
+    #[test]
+    fn sample_respects_conflicts() {
+        let path = TempDir::new().unwrap();
+        let repo_path = PathBuf::from(path.path());
+        prepare_empty_git_repo(repo_path.clone()).unwrap();
+        populate_with_features(repo_path.clone()).unwrap();
+        commit_file_to_branch(repo_path.clone(), "_main/_feature/_root/foo", "a", "foo").unwrap();
+        commit_file_to_branch(repo_path.clone(), "_main/_feature/_root/bar", "a", "bar").unwrap();
+        for branch in ["_main/_product/_sample/99", "_main/_product/_sample/mine"] {
+            let status = std::process::Command::new("git")
+                .args(["branch", branch])
+                .current_dir(&repo_path)
+                .status()
+                .unwrap();
+            assert!(status.success());
+        }
+        let repo = CommandRepository::new(
+            Box::new(SampleCommand),
+            GitPath::CustomDirectory(repo_path.clone()),
+        );
+        repo.execute(ArgSource::SUPPLIED(vec!["sample", "--derive"]))
+            .unwrap();
+
+        let interface = GitInterface::in_directory(repo_path);
+        let stale = QualifiedPath::from("main/product/sample/99");
+        assert!(!interface.get_model().has_branch(&stale));
+        assert!(interface.get_branch_backups().unwrap().contains(&stale));
+        assert!(
+            interface
+                .get_model()
+                .has_branch(&QualifiedPath::from("main/product/sample/mine"))
+        );
+        let foo = QualifiedPath::from("main/feature/root/foo");
+        let bar = QualifiedPath::from("main/feature/root/bar");
+        let baz = QualifiedPath::from("main/feature/root/baz");
+        let mut samples = Vec::new();
+        for i in 1.. {
+            let product = QualifiedPath::from(format!("main/product/sample/{}", i));
+            if !interface.get_model().has_branch(&product) {
+                break;
+            }
+            let history = interface.get_commit_history(&product).unwrap();
+            samples.push(
+                DerivationRecord::latest(&history)
+                    .unwrap()
+                    .features()
+                    .clone(),
+            );
+        }
+        assert!(!samples.is_empty());
+        assert!(
+            !samples
+                .iter()
+                .any(|sample| sample.contains(&foo) && sample.contains(&bar))
+        );
+        for (l, r) in [(&foo, &baz), (&bar, &baz)] {
+            for (selected_l, selected_r) in [(true, true), (true, false), (false, true)] {
+                assert!(samples.iter().any(|sample| {
+                    sample.contains(l) == selected_l && sample.contains(r) == selected_r
+                }));
+            }
+        }
+    }
+}
@@ -1,5 +1,5 @@
 use crate::cli::*;
-use crate::model::NodePathType;
+use crate::model::{NodePathType, QualifiedPath};
 use clap::Command;
 use std::error::Error;
 
@@ -19,19 +19,74 @@ impl CommandInterface for SpreadCommand {
         let current_path = context.git.get_current_node_path()?;
         let current_branch = current_path.get_qualified_path();
         let merge_argument = vec![current_branch.clone()];
+        let worktree = context.git.create_worktree()?;
+        let mut failed: Vec<QualifiedPath> = Vec::new();
         for path in current_path.iter_children_req() {
             let qualified_path = path.get_qualified_path();
             match path.concretize() {
                 NodePathType::Tag(_) => {}
                 _ => {
                     context.log_to_stdout(format!("Spreading to {}", qualified_path));
-                    context.git.checkout(&qualified_path)?;
-                    context.git.merge(&merge_argument)?;
+                    worktree.checkout(&qualified_path)?;
+                    let output = worktree.interface().merge(&merge_argument)?;
+                    if !output.status.success() {
+                        worktree.interface().abort_merge()?;
+                        failed.push(qualified_path);
+                    }
                 }
             }
         }
-        context.git.checkout(&current_branch)?;
-        context.log_to_stdout("Success");
+        if failed.is_empty() {
+            context.log_to_stdout("Success");
+        } else {
+            for path in failed {
+                context.log_to_stdout(format!("Unable to spread to {}: merge conflict", path));
+            }
+        }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::interface::GitInterface;
+    use crate::git::interface::GitPath;
+    use crate::git::interface::test_utils::{
+        commit_file_to_branch, get_current_branch, populate_with_features, prepare_empty_git_repo,
+    };
+    use std::path::PathBuf;
+    use tempfile::TempDir;
+
+    #[test]
+    fn spread_keeps_checkout() {
+        let path = TempDir::new().unwrap();
+        let repo_path = PathBuf::from(path.path());
+        prepare_empty_git_repo(repo_path.clone()).unwrap();
+        populate_with_features(repo_path.clone()).unwrap();
+        commit_file_to_branch(
+            repo_path.clone(),
+            "_main/_feature/root",
+            "root_file",
+            "root",
+        )
+        .unwrap();
+        GitInterface::in_directory(repo_path.clone())
+            .checkout(&QualifiedPath::from("main/feature/root"))
+            .unwrap();
+        let repo = CommandRepository::new(
+            Box::new(SpreadCommand),
+            GitPath::CustomDirectory(repo_path.clone()),
+        );
+        repo.execute(ArgSource::SUPPLIED(vec!["spread"])).unwrap();
+        assert_eq!(
+            get_current_branch(repo_path.clone()).unwrap(),
+            "_main/_feature/root"
+        );
+        let interface = GitInterface::in_directory(repo_path);
+        let files = interface
+            .get_files_managed_by_branch(&QualifiedPath::from("main/feature/root/foo"))
+            .unwrap();
+        assert!(files.contains(&"root_file".to_string()));
+    }
+}
@@ -16,12 +16,25 @@ impl CommandDefinition for TangleCommand {
             Box::new(TreeCommand),
             Box::new(DeriveCommand),
             Box::new(CheckCommand),
+            Box::new(HookCommand),
             Box::new(CheckoutCommand),
+            Box::new(DiffCommand),
+            Box::new(LogCommand),
+            Box::new(BlameCommand),
             Box::new(InitCommand),
+            Box::new(MigrateCommand),
+            Box::new(DoctorCommand),
             Box::new(FeatureCommand),
+            Box::new(FilesCommand),
             Box::new(ProductCommand),
+            Box::new(ModelCommand),
+            Box::new(SampleCommand),
+            Box::new(ExecCommand),
             Box::new(TagCommand),
             Box::new(SpreadCommand),
+            Box::new(FetchCommand),
+            Box::new(PullCommand),
+            Box::new(PushCommand),
             Box::new(UntieCommand),
             Box::new(HiddenCompletionCommand),
         ]
@@ -1,7 +1,78 @@
 use crate::cli::*;
-use clap::Command;
+use crate::git::interface::GitInterface;
+use crate::model::{AnyNodeType, NodePath, NodePathType, QualifiedPath};
+use clap::{Arg, ArgAction, Command};
+use colored::Colorize;
+use std::collections::HashMap;
 use std::error::Error;
 
+/// Annotates the branches of `node_path` and its descendants with the number of commits they
+/// are ahead of and behind their remote-tracking branches. Branches that are in sync are not
+/// annotated unless `show_status` is set, which also adds the current branch, the commits ahead
+/// of and behind the parent and whether the children need a spread.
+fn get_annotations(
+    node_path: &NodePath<AnyNodeType>,
+    show_status: bool,
+    git: &GitInterface,
+) -> Result<HashMap<QualifiedPath, String>, Box<dyn Error>> {
+    let model = git.get_model();
+    let current_branch = git.get_current_qualified_path().ok();
+    let nodes = std::iter::once(node_path.clone())
+        .chain(node_path.iter_children_req())
+        .filter(|node| {
+            node.get_metadata().has_branch()
+                && !matches!(node.clone().concretize(), NodePathType::Tag(_))
+        })
+        .collect::<Vec<_>>();
+    // commits ahead of and behind the parent of every branch
+    let mut parent_counts: HashMap<QualifiedPath, (usize, usize)> = HashMap::new();
+    if show_status {
+        for node in nodes.iter() {
+            if let Some(parent) = node.get_parent_with_branch() {
+                let path = node.get_qualified_path();
+                let counts = (
+                    git.count_commits_not_in(&path, &parent)?,
+                    git.count_commits_not_in(&parent, &path)?,
+                );
+                parent_counts.insert(path, counts);
+            }
+        }
+    }
+    let mut annotations = HashMap::new();
+    for node in nodes.iter() {
+        let path = node.get_qualified_path();
+        let mut parts = Vec::new();
+        if show_status && current_branch.as_ref() == Some(&path) {
+            parts.push("(current)".green().to_string());
+        }
+        if let Some((ahead, behind)) = parent_counts.get(&path) {
+            parts.push(format!("[parent: +{} -{}]", ahead, behind));
+        }
+        for remote in model.get_remotes() {
+            if !model.has_remote_branch(&remote, &path) {
+                continue;
+            }
+            let (ahead, behind) = git.count_ahead_behind_upstream(&remote, &path)?;
+            if show_status || ahead > 0 || behind > 0 {
+                parts.push(format!("[{}: +{} -{}]", remote, ahead, behind));
+            }
+        }
+        // a spread is needed somewhere below if a descendant is behind its parent
+        let needs_spread = node.iter_children_req().any(|child| {
+            parent_counts
+                .get(&child.get_qualified_path())
+                .is_some_and(|(_, behind)| *behind > 0)
+        });
+        if needs_spread {
+            parts.push(format!("[{}]", "needs spread".yellow()));
+        }
+        if !parts.is_empty() {
+            annotations.insert(path, parts.join(" "));
+        }
+    }
+    Ok(annotations)
+}
+
 #[derive(Clone, Debug)]
 pub struct TreeCommand;
 
@@ -11,6 +82,18 @@ impl CommandDefinition for TreeCommand {
             .about("Displays the tree structure")
             .disable_help_subcommand(true)
             .arg(make_show_tags())
+            .arg(
+                Arg::new("all")
+                    .long("all")
+                    .action(ArgAction::SetTrue)
+                    .help("Also show refs that do not fit into the tree"),
+            )
+            .arg(
+                Arg::new("status")
+                    .long("status")
+                    .action(ArgAction::SetTrue)
+                    .help("Show the whole area with the state of every branch relative to its parent and upstream"),
+            )
     }
 }
 
@@ -20,9 +103,86 @@ impl CommandInterface for TreeCommand {
             .arg_helper
             .get_argument_value::<bool>("show_tags")
             .unwrap();
-        let current_node_path = context.git.get_current_node_path()?;
-        let tree = current_node_path.display_tree(show_tags);
+        let show_all = context
+            .arg_helper
+            .get_argument_value::<bool>("all")
+            .unwrap();
+        let show_status = context
+            .arg_helper
+            .get_argument_value::<bool>("status")
+            .unwrap();
+        let node_path = if show_status {
+            let area = context.git.get_current_area()?.get_qualified_path();
+            context.git.get_model().get_node_path(&area).unwrap()
+        } else {
+            context.git.get_current_node_path()?
+        };
+        let annotations = get_annotations(&node_path, show_status, context.git)?;
+        let tree = node_path.display_annotated_tree(show_tags, &annotations);
         context.log_to_stdout(tree);
+        let foreign_refs = context.git.get_model().get_foreign_refs().clone();
+        if show_all && !foreign_refs.is_empty() {
+            context.log_to_stdout("Foreign refs:");
+            for foreign_ref in foreign_refs {
+                context.log_to_stdout(format!(
+                    "  {}{}: {}",
+                    foreign_ref.namespace, foreign_ref.name, foreign_ref.reason
+                ));
+            }
+        }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::interface::GitPath;
+    use crate::git::interface::test_utils::{
+        commit_file_to_branch, populate_with_features, prepare_empty_git_repo,
+    };
+    use std::path::PathBuf;
+    use tempfile::TempDir;
+
+    #[test]
+    fn tree_status_annotations() {
+        let path = TempDir::new().unwrap();
+        let repo_path = PathBuf::from(path.path());
+        prepare_empty_git_repo(repo_path.clone()).unwrap();
+        populate_with_features(repo_path.clone()).unwrap();
+        commit_file_to_branch(repo_path.clone(), "_main/_feature/root", "root", "root").unwrap();
+        commit_file_to_branch(repo_path.clone(), "_main/_feature/_root/foo", "foo", "foo").unwrap();
+        let interface = GitInterface::in_directory(repo_path.clone());
+        let foo = QualifiedPath::from("main/feature/root/foo");
+        interface.checkout(&foo).unwrap();
+
+        let area = interface
+            .get_model()
+            .get_node_path(&QualifiedPath::from("main"))
+            .unwrap();
+        assert!(
+            get_annotations(&area, false, &interface)
+                .unwrap()
+                .is_empty()
+        );
+        let annotations = get_annotations(&area, true, &interface).unwrap();
+        let root = &annotations[&QualifiedPath::from("main/feature/root")];
+        assert!(root.contains("[parent: +1 -0]"));
+        assert!(root.contains("needs spread"));
+        let foo = &annotations[&foo];
+        assert!(foo.contains("current"));
+        assert!(foo.contains("[parent: +1 -1]"));
+        assert!(!foo.contains("needs spread"));
+        // foo is behind root, which is below main
+        let main = &annotations[&QualifiedPath::from("main")];
+        assert!(main.contains("needs spread"));
+        assert!(!main.contains("parent"));
+
+        CommandRepository::new(
+            Box::new(TreeCommand),
+            GitPath::CustomDirectory(repo_path.clone()),
+        )
+        .execute(ArgSource::SUPPLIED(vec!["tree", "--status"]))
+        .unwrap();
+    }
+}
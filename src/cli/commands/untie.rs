@@ -1,23 +1,9 @@
 use crate::cli::*;
-use crate::model::{NodePathType, QualifiedPath};
+use crate::git::ownership::FileOwnership;
+use crate::model::{DerivationRecord, NodePathType, QualifiedPath};
 use clap::{Arg, Command};
 use std::error::Error;
 
-fn extract_feature_names(message: &str) -> Vec<QualifiedPath> {
-    let to_filter = vec!["# DO NOT EDIT OR REMOVE THIS COMMIT", "DERIVATION FINISHED"];
-    let trimmed = message.trim();
-    trimmed
-        .split("\n")
-        .filter_map(|e| {
-            if !to_filter.contains(&e) {
-                Some(QualifiedPath::from(e))
-            } else {
-                None
-            }
-        })
-        .collect()
-}
-
 #[derive(Clone, Debug)]
 pub struct UntieCommand;
 
@@ -64,15 +50,17 @@ impl CommandInterface for UntieCommand {
         };
         let mut has_valid = false;
         let mut derivation_found = false;
-        let mut features: Vec<QualifiedPath> = Vec::new();
+        let features: Vec<QualifiedPath> = match DerivationRecord::latest(&commit_history) {
+            Some(record) => record.features().clone(),
+            None => Vec::new(),
+        };
         commit_history.reverse();
         for commit in commit_history.iter() {
-            if commit.message().contains("DERIVATION FINISHED") {
+            if DerivationRecord::is_derivation_message(commit.message()) {
                 if commit.hash() == &hash {
                     return Err("Derivation commit cannot be untied".into());
                 }
                 derivation_found = true;
-                features.extend(extract_feature_names(&commit.message()));
             } else {
                 if derivation_found && commit.hash() == &hash {
                     has_valid = true;
@@ -83,20 +71,14 @@ impl CommandInterface for UntieCommand {
         if !has_valid {
             return Err("Commit not found after initial derivation".into());
         }
-        let files_of_commit = context.git.get_files_changed_by_commit(&hash)?;
-        let filtered = features
+        let files_of_commit = context
+            .git
+            .get_files_changed_by_commit(&hash)?
             .into_iter()
-            .filter(|feature| {
-                let managed_files = context.git.get_files_managed_by_branch(feature).unwrap();
-                let mut all_true = true;
-                for file_in_commit in files_of_commit.iter() {
-                    if !managed_files.contains(file_in_commit) {
-                        all_true = false;
-                    }
-                }
-                all_true
-            })
-            .collect::<Vec<QualifiedPath>>();
+            .filter(|file| !file.is_empty())
+            .collect::<Vec<_>>();
+        let ownership = FileOwnership::compute(context.git, &context.git.get_current_area()?)?;
+        let filtered = ownership.get_features_owning_all(&files_of_commit, &features);
         let feature: QualifiedPath = match maybe_feature {
             Some(feature) => QualifiedPath::from(feature),
             None => {
@@ -107,16 +89,15 @@ impl CommandInterface for UntieCommand {
                 }
             },
         };
-        let current_path = context.git.get_current_qualified_path()?;
-        context.git.checkout(&feature)?;
-        let output = context.git.cherry_pick(&hash)?;
+        let worktree = context.git.create_worktree()?;
+        worktree.checkout(&feature)?;
+        let output = worktree.interface().cherry_pick(&hash)?;
         if !output.status.success() {
-            context.git.abort_merge()?;
+            worktree.interface().abort_cherry_pick()?;
             context.log_to_stdout(format!("Unable to untie commit {}", &hash));
         } else {
             context.log_to_stdout(format!("Untied commit {} to {}", &hash, &feature));
         }
-        context.git.checkout(&current_path)?;
         Ok(())
     }
 }
@@ -11,3 +11,37 @@ pub fn make_delete(force: bool) -> Arg {
     let short = if force { 'D' } else { 'd' };
     Arg::new("delete").short(short)
 }
+
+pub fn make_recursive() -> Arg {
+    Arg::new("recursive")
+        .short('r')
+        .long("recursive")
+        .action(ArgAction::SetTrue)
+        .help("Also delete all children")
+}
+
+pub fn make_force() -> Arg {
+    Arg::new("force")
+        .long("force")
+        .action(ArgAction::SetTrue)
+        .help("Delete even if the branch is still referenced")
+}
+
+pub fn make_restore() -> Arg {
+    Arg::new("restore")
+        .long("restore")
+        .help("Restore a deleted branch and its children from the backup")
+}
+
+pub fn make_verify() -> Arg {
+    Arg::new("verify")
+        .long("verify")
+        .value_name("COMMAND")
+        .help("Runs a command on the merged tree of every pair of features that merges cleanly and treats failures as conflicts")
+}
+
+pub fn make_remote() -> Arg {
+    Arg::new("remote")
+        .default_value("origin")
+        .help("The remote to synchronize with")
+}
@@ -22,8 +22,9 @@ impl CommandMap {
                 .get_subcommands()
                 .into_iter()
                 .map(|c| {
-                    let sub_command = c.build_command();
-                    children.push(CommandMap::new(c));
+                    let child = CommandMap::new(c);
+                    let sub_command = child.clap_command.clone();
+                    children.push(child);
                     sub_command
                 })
                 .collect::<Vec<Command>>(),
@@ -95,8 +96,12 @@ impl CommandContext<'_> {
     }
     fn transform_branch_names<S: Into<String>>(&self, to_print: S) -> String {
         let mut result = to_print.into();
+        let naming = self.git.get_model().get_naming();
         for branch in self.git.get_model().get_qualified_paths_with_branches() {
-            result = result.replace(branch.to_git_branch().as_str(), branch.to_string().as_str());
+            result = result.replace(
+                naming.to_ref_name(branch).as_str(),
+                branch.to_string().as_str(),
+            );
         }
         result
     }
@@ -58,10 +58,11 @@ impl CommandRepository {
                 .clone()
                 .get_matches_from(supplied),
         };
+        let mut git = GitInterface::new(self.work_path.clone())?;
         self.execute_recursive(&mut CommandContext::new(
             &self.command_map,
             &self.command_map,
-            &mut GitInterface::new(self.work_path.clone()),
+            &mut git,
             ArgHelper::new(&args),
         ))?;
         Ok(())
@@ -1,35 +1,37 @@
 use crate::git::error::GitError;
 use crate::git::interface::GitInterface;
-use crate::model::QualifiedPath;
+use crate::git::worktree::Worktree;
+use crate::model::{Commit, ConflictRecord, ConflictStatus, QualifiedPath};
 use colored::Colorize;
 use std::fmt::Display;
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 #[derive(Debug)]
 pub enum ConflictStatistic {
     OK((QualifiedPath, QualifiedPath)),
     CONFLICT((QualifiedPath, QualifiedPath)),
+    /// The features merge cleanly, but the verification command fails on the merged tree.
+    #[allow(non_camel_case_types)]
+    SEMANTIC_CONFLICT((QualifiedPath, QualifiedPath)),
     ERROR((QualifiedPath, QualifiedPath), GitError),
 }
 
 impl From<ConflictStatistic> for String {
     fn from(value: ConflictStatistic) -> Self {
-        match value {
-            ConflictStatistic::OK((l, r)) => {
-                format!("Merge {} and {} ", l, r) + "OK".green().to_string().as_str()
-            }
-            ConflictStatistic::CONFLICT((l, r)) => {
-                format!("Merge {} and {} ", l, r) + "CONFLICT".red().to_string().as_str()
-            }
-            ConflictStatistic::ERROR((l, r), error) => {
-                format!("Merge {} and {} ", l, r) + "ERROR".green().to_string().as_str()
-            }
-        }
+        value.to_string()
     }
 }
 
 impl Display for ConflictStatistic {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_str(self.to_string().as_str())
+        let ((l, r), status) = match self {
+            ConflictStatistic::OK(pair) => (pair, "OK".green()),
+            ConflictStatistic::CONFLICT(pair) => (pair, "CONFLICT".red()),
+            ConflictStatistic::SEMANTIC_CONFLICT(pair) => (pair, "SEMANTIC CONFLICT".red()),
+            ConflictStatistic::ERROR(pair, _) => (pair, "ERROR".green()),
+        };
+        write!(f, "Merge {} and {} {}", l, r, status)
     }
 }
 
@@ -57,13 +59,18 @@ impl ConflictStatistics {
     pub fn push(&mut self, statistic: ConflictStatistic) {
         match statistic {
             ConflictStatistic::OK(_) => self.ok.push(statistic),
-            ConflictStatistic::CONFLICT(_) => self.conflict.push(statistic),
+            ConflictStatistic::CONFLICT(_) | ConflictStatistic::SEMANTIC_CONFLICT(_) => {
+                self.conflict.push(statistic)
+            }
             ConflictStatistic::ERROR(_, _) => self.error.push(statistic),
         }
     }
     pub fn iter_ok(&self) -> impl Iterator<Item = &ConflictStatistic> {
         self.ok.iter()
     }
+    pub fn iter_conflict(&self) -> impl Iterator<Item = &ConflictStatistic> {
+        self.conflict.iter()
+    }
     pub fn n_ok(&self) -> usize {
         self.ok.len()
     }
@@ -81,53 +88,299 @@ impl FromIterator<ConflictStatistic> for ConflictStatistics {
     }
 }
 
+/// A file that cannot be merged, with the line ranges of the conflict markers in the merged
+/// file and the commits of either side that touched it.
+#[derive(Debug)]
+pub struct ConflictingFile {
+    pub path: String,
+    pub hunks: Vec<(usize, usize)>,
+    pub left_commits: Vec<Commit>,
+    pub right_commits: Vec<Commit>,
+}
+
+#[derive(Debug)]
+pub struct ConflictExplanation {
+    pub left: QualifiedPath,
+    pub right: QualifiedPath,
+    pub files: Vec<ConflictingFile>,
+    /// The deepest feature with a branch that both features descend from.
+    pub common_ancestor: Option<QualifiedPath>,
+}
+
+fn find_conflict_hunks(content: &str) -> Vec<(usize, usize)> {
+    let mut hunks = Vec::new();
+    let mut start = None;
+    for (i, line) in content.lines().enumerate() {
+        if line.starts_with("<<<<<<<") {
+            start = Some(i + 1);
+        } else if line.starts_with(">>>>>>>")
+            && let Some(begin) = start.take()
+        {
+            hunks.push((begin, i + 1));
+        }
+    }
+    hunks
+}
+
 pub struct ConflictChecker<'a> {
     interface: &'a GitInterface,
+    verify_command: Option<String>,
 }
 
 impl<'a> ConflictChecker<'a> {
     pub fn new(interface: &'a GitInterface) -> Self {
-        Self { interface }
+        Self {
+            interface,
+            verify_command: None,
+        }
+    }
+    /// Runs `command` with `sh -c` on the merged tree of every pair that merges cleanly.
+    /// Pairs for which it fails are reported as [`ConflictStatistic::SEMANTIC_CONFLICT`].
+    pub fn with_verify_command(mut self, command: Option<String>) -> Self {
+        self.verify_command = command;
+        self
     }
 
     pub fn check(
         &self,
         paths: &Vec<QualifiedPath>,
     ) -> Result<impl Iterator<Item = ConflictStatistic>, GitError> {
-        let mut feature_combinations: Vec<(&QualifiedPath, &QualifiedPath)> = Vec::new();
+        let mut feature_combinations: Vec<(QualifiedPath, QualifiedPath)> = Vec::new();
         for (i, path) in paths.iter().enumerate() {
             for part in paths[i + 1..].iter() {
-                feature_combinations.push((path, part));
+                feature_combinations.push((path.clone(), part.clone()));
             }
         }
+        self.check_pairs(feature_combinations)
+    }
+
+    /// Checks `feature` against each of `others`.
+    pub fn check_against(
+        &self,
+        feature: &QualifiedPath,
+        others: &[QualifiedPath],
+    ) -> Result<impl Iterator<Item = ConflictStatistic>, GitError> {
+        let feature_combinations = others
+            .iter()
+            .filter(|other| *other != feature)
+            .map(|other| (feature.clone(), other.clone()))
+            .collect();
+        self.check_pairs(feature_combinations)
+    }
 
-        let iterator = feature_combinations.into_iter().map(|(l, r)| {
-            let statistic = self.check_two(l.clone(), r.clone());
-            match statistic {
-                Ok(stat) => match stat {
-                    true => ConflictStatistic::OK((l.clone(), r.clone())),
-                    false => ConflictStatistic::CONFLICT((l.clone(), r.clone())),
-                },
-                Err(e) => ConflictStatistic::ERROR((l.clone(), r.clone()), e),
+    fn check_pairs(
+        &self,
+        feature_combinations: Vec<(QualifiedPath, QualifiedPath)>,
+    ) -> Result<impl Iterator<Item = ConflictStatistic>, GitError> {
+        let area = self.interface.get_current_area()?.get_qualified_path();
+        let worktree = self.interface.create_worktree()?;
+        let iterator = feature_combinations.into_iter().map(move |(l, r)| {
+            let pair = (l.clone(), r.clone());
+            match self.check_two(&worktree, &area, l, r) {
+                Ok(statistic) => statistic,
+                Err(e) => ConflictStatistic::ERROR(pair, e),
             }
         });
         Ok(iterator)
     }
 
-    fn check_two(&self, l: QualifiedPath, r: QualifiedPath) -> Result<bool, GitError> {
-        let current_path = self.interface.get_current_qualified_path()?;
-        let current_area = self.interface.get_current_area()?;
-        self.interface
-            .checkout(&current_area.get_qualified_path())?;
-        let temporary = QualifiedPath::from("tmp");
-        self.interface.create_branch_no_mut(&temporary)?;
-        self.interface.checkout_raw(&temporary)?;
-        let success = self.interface.merge(&vec![l, r])?.status.success();
-        if !success {
-            self.interface.abort_merge()?;
+    /// Stores the results in the conflict cache. Returns the records of the pairs that merged
+    /// without conflicts when they were last checked, but conflict now.
+    pub fn record(
+        &self,
+        statistics: &[ConflictStatistic],
+    ) -> Result<Vec<ConflictRecord>, GitError> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let mut cache = self.interface.read_conflict_cache()?;
+        let mut regressions = Vec::new();
+        for statistic in statistics.iter() {
+            let ((l, r), status) = match statistic {
+                ConflictStatistic::OK(pair) => (pair, ConflictStatus::Ok),
+                ConflictStatistic::CONFLICT(pair) => (pair, ConflictStatus::Conflict),
+                ConflictStatistic::SEMANTIC_CONFLICT(pair) => {
+                    (pair, ConflictStatus::SemanticConflict)
+                }
+                ConflictStatistic::ERROR(_, _) => continue,
+            };
+            let record = ConflictRecord {
+                left: l.to_string(),
+                right: r.to_string(),
+                status,
+                left_tip: self.interface.get_tip(l)?,
+                right_tip: self.interface.get_tip(r)?,
+                timestamp,
+            };
+            let previous = cache.insert(record.clone());
+            if status != ConflictStatus::Ok
+                && previous.is_some_and(|p| p.status == ConflictStatus::Ok)
+            {
+                regressions.push(record);
+            }
+        }
+        self.interface.write_conflict_cache(&cache)?;
+        Ok(regressions)
+    }
+
+    /// Merges two conflicting features and reports where and why they conflict.
+    pub fn explain(
+        &self,
+        l: &QualifiedPath,
+        r: &QualifiedPath,
+    ) -> Result<ConflictExplanation, GitError> {
+        let feature_root = self
+            .interface
+            .get_current_area()?
+            .get_path_to_feature_root();
+        let worktree = self.interface.create_worktree()?;
+        let interface = worktree.interface();
+        // a two-way merge leaves conflict markers, unlike the octopus merge of check
+        worktree.checkout_detached(l)?;
+        let merged = interface.merge(&vec![r.clone()])?.status.success();
+        let mut files = Vec::new();
+        if !merged {
+            for path in interface.get_unmerged_files()? {
+                let content =
+                    std::fs::read_to_string(worktree.path().join(&path)).unwrap_or_default();
+                files.push(ConflictingFile {
+                    hunks: find_conflict_hunks(&content),
+                    left_commits: self.interface.get_commits_touching(l, r, &path)?,
+                    right_commits: self.interface.get_commits_touching(r, l, &path)?,
+                    path,
+                });
+            }
+            interface.abort_merge()?;
+        }
+        let common_ancestor = (feature_root.len() + 1..=l.len().min(r.len()))
+            .rev()
+            .map(|n| l.strip_n_right(n))
+            .filter(|ancestor| r.strip_n_right(ancestor.len()) == *ancestor)
+            .find(|ancestor| self.interface.get_model().has_branch(ancestor));
+        Ok(ConflictExplanation {
+            left: l.clone(),
+            right: r.clone(),
+            files,
+            common_ancestor,
+        })
+    }
+
+    fn check_two(
+        &self,
+        worktree: &Worktree,
+        area: &QualifiedPath,
+        l: QualifiedPath,
+        r: QualifiedPath,
+    ) -> Result<ConflictStatistic, GitError> {
+        let result = self.merge_and_verify(worktree, area, l, r);
+        // the worktree is reused for the next pair
+        worktree.clean()?;
+        result
+    }
+
+    fn merge_and_verify(
+        &self,
+        worktree: &Worktree,
+        area: &QualifiedPath,
+        l: QualifiedPath,
+        r: QualifiedPath,
+    ) -> Result<ConflictStatistic, GitError> {
+        let interface = worktree.interface();
+        worktree.checkout_detached(area)?;
+        if !interface
+            .merge(&vec![l.clone(), r.clone()])?
+            .status
+            .success()
+        {
+            interface.abort_merge()?;
+            return Ok(ConflictStatistic::CONFLICT((l, r)));
+        }
+        if let Some(command) = &self.verify_command {
+            let status = Command::new("sh")
+                .args(["-c", command.as_str()])
+                .current_dir(worktree.path())
+                .output()?
+                .status;
+            if !status.success() {
+                return Ok(ConflictStatistic::SEMANTIC_CONFLICT((l, r)));
+            }
         }
-        self.interface.checkout(&current_path)?;
-        self.interface.delete_branch(&temporary)?;
-        Ok(success)
+        Ok(ConflictStatistic::OK((l, r)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::interface::test_utils::{
+        commit_file_to_branch, populate_with_features, prepare_empty_git_repo,
+    };
+    use std::path::PathBuf;
+    use tempfile::TempDir;
+
+    #[test]
+    fn explain_conflict() {
+        let path = TempDir::new().unwrap();
+        let repo_path = PathBuf::from(path.path());
+        prepare_empty_git_repo(repo_path.clone()).unwrap();
+        populate_with_features(repo_path.clone()).unwrap();
+        commit_file_to_branch(
+            repo_path.clone(),
+            "_main/_feature/_root/foo",
+            "a",
+            "x\nfoo\n",
+        )
+        .unwrap();
+        commit_file_to_branch(
+            repo_path.clone(),
+            "_main/_feature/_root/bar",
+            "a",
+            "x\nbar\n",
+        )
+        .unwrap();
+        let interface = GitInterface::in_directory(repo_path);
+        let foo = QualifiedPath::from("main/feature/root/foo");
+        let bar = QualifiedPath::from("main/feature/root/bar");
+        let explanation = ConflictChecker::new(&interface)
+            .explain(&foo, &bar)
+            .unwrap();
+        assert_eq!(explanation.files.len(), 1);
+        let file = &explanation.files[0];
+        assert_eq!(file.path, "a");
+        // the common first line is merged
+        assert_eq!(file.hunks, vec![(2, 6)]);
+        assert_eq!(file.left_commits.len(), 1);
+        assert_eq!(file.right_commits[0].message(), "change a");
+        assert_eq!(
+            explanation.common_ancestor,
+            Some(QualifiedPath::from("main/feature/root"))
+        );
+    }
+
+    #[test]
+    fn verify_runs_on_clean_worktree() {
+        let path = TempDir::new().unwrap();
+        let repo_path = PathBuf::from(path.path());
+        prepare_empty_git_repo(repo_path.clone()).unwrap();
+        populate_with_features(repo_path.clone()).unwrap();
+        let interface = GitInterface::in_directory(repo_path);
+        let paths = vec![
+            QualifiedPath::from("main/feature/root/foo"),
+            QualifiedPath::from("main/feature/root/bar"),
+            QualifiedPath::from("main/feature/root/baz"),
+        ];
+        // fails if the output of a previous pair is still present
+        let statistics: Vec<ConflictStatistic> = ConflictChecker::new(&interface)
+            .with_verify_command(Some("test ! -e out && touch out".to_string()))
+            .check(&paths)
+            .unwrap()
+            .collect();
+        assert_eq!(statistics.len(), 3);
+        assert!(
+            statistics
+                .iter()
+                .all(|statistic| matches!(statistic, ConflictStatistic::OK(_)))
+        );
     }
 }
@@ -1,4 +1,4 @@
-use crate::model::WrongNodeTypeError;
+use crate::model::{InvalidPathError, WrongNodeTypeError};
 use std::error::Error;
 use std::fmt::{Display, Formatter};
 use std::io;
@@ -26,6 +26,7 @@ pub enum GitError {
     Io(io::Error),
     GitInterface(GitInterfaceError),
     WrongNodeType(WrongNodeTypeError),
+    InvalidPath(InvalidPathError),
 }
 impl Display for GitError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
@@ -33,6 +34,7 @@ impl Display for GitError {
             GitError::Io(err) => err.fmt(f),
             GitError::GitInterface(err) => err.fmt(f),
             GitError::WrongNodeType(err) => err.fmt(f),
+            GitError::InvalidPath(err) => err.fmt(f),
         }
     }
 }
@@ -52,3 +54,8 @@ impl From<WrongNodeTypeError> for GitError {
         GitError::WrongNodeType(value)
     }
 }
+impl From<InvalidPathError> for GitError {
+    fn from(value: InvalidPathError) -> Self {
+        GitError::InvalidPath(value)
+    }
+}
@@ -1,9 +1,36 @@
 use crate::git::error::{GitError, GitInterfaceError};
+use crate::git::persistency::{
+    GitDirPersistencyHandler, GitRefPersistencyHandler, PersistencyHandler,
+};
+use crate::git::worktree::Worktree;
 use crate::model::*;
 use crate::util::u8_to_string;
 use std::io;
-use std::path::PathBuf;
-use std::process::{Command, Output};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static WORKTREE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+const BACKUP_BRANCH_NAMESPACE: &str = "refs/tangl/backup/heads/";
+const BACKUP_TAG_NAMESPACE: &str = "refs/tangl/backup/tags/";
+const ARCHIVE_NAMESPACE: &str = "refs/tangl/archive/";
+const METADATA_NAMESPACE: &str = "refs/tangl/";
+/// Remote-tracking refs of the metadata refs of a remote are stored below
+/// `refs/tangl/remotes/<remote>/`.
+const REMOTE_METADATA_NAMESPACE: &str = "refs/tangl/remotes/";
+const MODEL_FILE: &str = "model.json";
+const PRODUCTS_FILE: &str = "products.json";
+const CONFLICTS_FILE: &str = "conflicts.json";
+const NAMING_FILE: &str = "naming.json";
+/// Namespaces of refs whose names encode qualified paths.
+const PATH_NAMESPACES: [&str; 5] = [
+    "refs/heads/",
+    "refs/tags/",
+    ARCHIVE_NAMESPACE,
+    BACKUP_BRANCH_NAMESPACE,
+    BACKUP_TAG_NAMESPACE,
+];
 
 #[derive(Clone, Debug)]
 pub enum GitPath {
@@ -16,9 +43,6 @@ pub(super) struct GitCLI {
     path: GitPath,
 }
 impl GitCLI {
-    pub fn in_current_directory() -> Self {
-        Self::new(GitPath::CurrentDirectory)
-    }
     pub fn in_custom_directory(path: PathBuf) -> Self {
         Self::new(GitPath::CustomDirectory(path))
     }
@@ -39,6 +63,21 @@ impl GitCLI {
         transformed.extend(args);
         base.args(transformed).output()
     }
+    pub fn run_with_input(&self, args: Vec<&str>, input: &str) -> io::Result<Output> {
+        let mut base = Command::new("git");
+        if let GitPath::CustomDirectory(ref path) = self.path {
+            base.arg(format!("--git-dir={}/.git", path.to_str().unwrap()));
+            base.arg(format!("--work-tree={}", path.to_str().unwrap()));
+        }
+        let mut child = base
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        child.stdin.take().unwrap().write_all(input.as_bytes())?;
+        child.wait_with_output()
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -47,48 +86,197 @@ pub struct GitInterface {
     raw_git_interface: GitCLI,
 }
 impl GitInterface {
+    /// Panics if the model cannot be read, see [`GitInterface::new`].
     pub fn default() -> Self {
-        Self::new(GitPath::CurrentDirectory)
+        Self::new(GitPath::CurrentDirectory).unwrap()
     }
+    /// Panics if the model cannot be read, see [`GitInterface::new`].
     pub fn in_directory(path: PathBuf) -> Self {
-        Self::new(GitPath::CustomDirectory(path))
+        Self::new(GitPath::CustomDirectory(path)).unwrap()
     }
-    pub fn new(path: GitPath) -> Self {
+    /// Reads the model of the repository, which fails e.g. if the naming scheme is invalid.
+    pub fn new(path: GitPath) -> Result<Self, GitError> {
         let raw_interface = GitCLI::new(path);
         let mut interface = Self {
             model: TreeDataModel::new(),
             raw_git_interface: raw_interface,
         };
-        match interface.update_complete_model() {
-            Ok(_) => interface,
-            Err(e) => panic!("{:?}", e),
-        }
+        interface.update_complete_model()?;
+        Ok(interface)
     }
     fn update_complete_model(&mut self) -> Result<(), GitError> {
-        let branch_output = self.raw_git_interface.run(vec!["branch"])?;
-        let all_branches: Vec<String> = u8_to_string(&branch_output.stdout)
-            .split("\n")
-            .map(|raw_string| raw_string.trim().to_string())
-            .collect();
-        for branch in all_branches {
-            if !branch.is_empty() {
-                self.model
-                    .insert_qualified_path(QualifiedPath::from(branch), false)?;
+        self.model = TreeDataModel::with_naming(self.read_naming_scheme()?);
+        for namespace in ["refs/heads/", "refs/tags/", ARCHIVE_NAMESPACE] {
+            for name in self.list_refs(namespace)? {
+                if let Err(reason) = self.insert_ref(namespace, &name) {
+                    self.model.insert_foreign_ref(ForeignRef {
+                        namespace: namespace.to_string(),
+                        name,
+                        reason,
+                    });
+                }
             }
         }
-        let tag_output = self.raw_git_interface.run(vec!["tag"])?;
-        let all_tags: Vec<String> = u8_to_string(&tag_output.stdout)
-            .split("\n")
-            .map(|raw_string| raw_string.trim().to_string())
-            .collect();
-        for tag in all_tags {
-            if !tag.is_empty() {
-                self.model
-                    .insert_qualified_path(QualifiedPath::from(tag), true)?;
+        for remote in self.list_remotes()? {
+            for name in self.list_refs(&format!("refs/remotes/{}/", remote))? {
+                if let Some(path) = self.model.get_naming().parse_ref_name(&name) {
+                    self.model.insert_remote_branch(&remote, path);
+                }
             }
         }
+        for (path, metadata) in self.read_metadata_store()?.iter() {
+            self.model.update_feature_metadata(&path, metadata);
+        }
+        Ok(())
+    }
+    /// Inserts a ref into the model. Refs outside of the namespace or the managed areas of the
+    /// naming scheme are skipped, refs that do not fit into the tree return the reason.
+    fn insert_ref(&mut self, namespace: &str, name: &str) -> Result<(), String> {
+        let naming = self.model.get_naming();
+        if !naming.is_in_namespace(name) {
+            return Ok(());
+        }
+        let path = naming.from_ref_name(name).map_err(|e| e.to_string())?;
+        if !naming.is_managed_area(&path) {
+            return Ok(());
+        }
+        naming.check_root(&path).map_err(|e| e.to_string())?;
+        match namespace {
+            ARCHIVE_NAMESPACE => self.model.insert_archived_path(path),
+            namespace => self
+                .model
+                .insert_qualified_path(path, namespace == "refs/tags/"),
+        }
+        .map_err(|e| e.to_string())
+    }
+    /// Reloads the model, e.g. after refs were changed without this interface.
+    pub fn reload_model(&mut self) -> Result<(), GitError> {
+        self.update_complete_model()
+    }
+    /// Returns a handler for a file that is local to this clone and never shared.
+    pub fn get_local_persistency(&self, file_name: &str) -> GitDirPersistencyHandler {
+        GitDirPersistencyHandler::new(file_name, self.raw_git_interface.clone())
+    }
+    pub fn read_naming_scheme(&self) -> Result<NamingScheme, GitError> {
+        let data = GitRefPersistencyHandler::new(NAMING_FILE, self.raw_git_interface.clone())
+            .read_file()?;
+        NamingScheme::from_json(&data).map_err(|e| {
+            GitError::GitInterface(GitInterfaceError::new(
+                format!("Cannot read naming scheme: {}", e).as_str(),
+            ))
+        })
+    }
+    /// Stores the naming scheme and reloads the model with it.
+    pub fn write_naming_scheme(&mut self, naming: &NamingScheme) -> Result<(), GitError> {
+        GitRefPersistencyHandler::new(NAMING_FILE, self.raw_git_interface.clone())
+            .write_file(&naming.to_json())?;
+        self.update_complete_model()
+    }
+    pub fn read_metadata_store(&self) -> Result<MetadataStore, GitError> {
+        let data = GitRefPersistencyHandler::new(MODEL_FILE, self.raw_git_interface.clone())
+            .read_file()?;
+        MetadataStore::from_json(&data).map_err(|e| {
+            GitError::GitInterface(GitInterfaceError::new(
+                format!("Cannot read {}: {}", MODEL_FILE, e).as_str(),
+            ))
+        })
+    }
+    pub fn write_metadata_store(&self, store: &MetadataStore) -> Result<(), GitError> {
+        GitRefPersistencyHandler::new(MODEL_FILE, self.raw_git_interface.clone())
+            .write_file(&store.to_json())
+    }
+    pub fn set_feature_metadata(
+        &mut self,
+        path: &QualifiedPath,
+        metadata: FeatureMetadata,
+    ) -> Result<(), GitError> {
+        let mut store = self.read_metadata_store()?;
+        store.set(path, metadata.clone());
+        self.write_metadata_store(&store)?;
+        self.model.update_feature_metadata(path, metadata);
+        Ok(())
+    }
+    /// Moves the metadata of `from` and all features below it to `to`.
+    pub fn move_feature_metadata(
+        &self,
+        from: &QualifiedPath,
+        to: &QualifiedPath,
+    ) -> Result<(), GitError> {
+        let mut store = self.read_metadata_store()?;
+        if store.iter().any(|(path, _)| path.has_prefix(from)) {
+            store.rename(from, to);
+            self.write_metadata_store(&store)?;
+        }
         Ok(())
     }
+    pub fn read_product_configs(&self) -> Result<ProductConfigStore, GitError> {
+        let data = GitRefPersistencyHandler::new(PRODUCTS_FILE, self.raw_git_interface.clone())
+            .read_file()?;
+        ProductConfigStore::from_json(&data).map_err(|e| {
+            GitError::GitInterface(GitInterfaceError::new(
+                format!("Cannot read {}: {}", PRODUCTS_FILE, e).as_str(),
+            ))
+        })
+    }
+    pub fn write_product_configs(&self, store: &ProductConfigStore) -> Result<(), GitError> {
+        GitRefPersistencyHandler::new(PRODUCTS_FILE, self.raw_git_interface.clone())
+            .write_file(&store.to_json())
+    }
+    pub fn read_conflict_cache(&self) -> Result<ConflictCache, GitError> {
+        let data = self.get_local_persistency(CONFLICTS_FILE).read_file()?;
+        ConflictCache::from_json(&data).map_err(|e| {
+            GitError::GitInterface(GitInterfaceError::new(
+                format!("Cannot read {}: {}", CONFLICTS_FILE, e).as_str(),
+            ))
+        })
+    }
+    pub fn write_conflict_cache(&self, cache: &ConflictCache) -> Result<(), GitError> {
+        self.get_local_persistency(CONFLICTS_FILE)
+            .write_file(&cache.to_json())
+    }
+    /// Returns the hash of the commit a path points to.
+    pub fn get_tip(&self, path: &QualifiedPath) -> Result<String, GitError> {
+        let revision = self.revision(path);
+        let output = self.run_checked(vec!["rev-parse", "--verify", revision.as_str()])?;
+        Ok(u8_to_string(&output.stdout).trim().to_string())
+    }
+    /// Parses a date in any format git understands, e.g. `2024-05-01` or `2 weeks ago`, into
+    /// seconds since the Unix epoch.
+    pub fn parse_date(&self, date: &str) -> Result<u64, GitError> {
+        let since = format!("--since={}", date);
+        let output = self.run_checked(vec!["rev-parse", since.as_str()])?;
+        u8_to_string(&output.stdout)
+            .trim()
+            .strip_prefix("--max-age=")
+            .and_then(|seconds| seconds.parse().ok())
+            .ok_or_else(|| {
+                GitError::GitInterface(GitInterfaceError::new(
+                    format!("Invalid date '{}'", date).as_str(),
+                ))
+            })
+    }
+    /// Returns the directory git runs hooks from, honouring `core.hooksPath`.
+    pub fn get_hooks_dir(&self) -> Result<PathBuf, GitError> {
+        let output = self.run_checked(vec![
+            "rev-parse",
+            "--path-format=absolute",
+            "--git-path",
+            "hooks",
+        ])?;
+        Ok(PathBuf::from(u8_to_string(&output.stdout).trim()))
+    }
+    /// Returns the revision to use for a path: its branch or, if archived, its archive ref.
+    fn revision(&self, path: &QualifiedPath) -> String {
+        if self.model.is_archived(path) {
+            ARCHIVE_NAMESPACE.to_string() + self.ref_name(path).as_str()
+        } else {
+            self.ref_name(path)
+        }
+    }
+    /// Returns the name of the branch or tag of `path` in the naming scheme of the repository.
+    fn ref_name(&self, path: &QualifiedPath) -> String {
+        self.model.get_naming().to_ref_name(path)
+    }
     pub fn get_model(&self) -> &TreeDataModel {
         &self.model
     }
@@ -100,17 +288,64 @@ impl GitInterface {
                 .stdout,
         ))
     }
+    /// Returns the paths of the branches checked out in any worktree of the repository,
+    /// including the main one.
+    pub fn get_checked_out_paths(&self) -> Result<Vec<QualifiedPath>, GitError> {
+        let output = self.run_checked(vec!["worktree", "list", "--porcelain"])?;
+        Ok(u8_to_string(&output.stdout)
+            .lines()
+            .filter_map(|line| line.strip_prefix("branch refs/heads/"))
+            .filter_map(|name| self.model.get_naming().parse_ref_name(name))
+            .collect())
+    }
     pub fn get_current_qualified_path(&self) -> Result<QualifiedPath, GitError> {
-        Ok(QualifiedPath::from(self.get_current_branch()?))
+        Ok(self
+            .model
+            .get_naming()
+            .from_ref_name(&self.get_current_branch()?)?)
+    }
+    fn foreign_current_branch_error(path: &QualifiedPath) -> GitError {
+        GitError::GitInterface(GitInterfaceError::new(
+            format!("The current branch {} does not belong to tangl", path).as_str(),
+        ))
     }
     pub fn get_current_node_path(&self) -> Result<NodePath<AnyNodeType>, GitError> {
         let current_qualified_path = self.get_current_qualified_path()?;
-        Ok(self.model.get_node_path(&current_qualified_path).unwrap())
+        self.model
+            .get_node_path(&current_qualified_path)
+            .ok_or_else(|| Self::foreign_current_branch_error(&current_qualified_path))
     }
     pub fn get_current_area(&self) -> Result<NodePath<Area>, GitError> {
         let current_qualified_path = self.get_current_qualified_path()?;
         let qualified_path = QualifiedPath::from(current_qualified_path.first().unwrap().clone());
-        Ok(self.model.get_area(&qualified_path).unwrap())
+        self.model
+            .get_area(&qualified_path)
+            .ok_or_else(|| Self::foreign_current_branch_error(&current_qualified_path))
+    }
+    /// Returns the latest derivation record of every product branch in the area.
+    pub fn get_derivation_records(
+        &self,
+        area: &NodePath<Area>,
+    ) -> Result<Vec<(QualifiedPath, DerivationRecord)>, GitError> {
+        let mut records = Vec::new();
+        let maybe_product_root = area.clone().to_product_root();
+        if maybe_product_root.is_none() {
+            return Ok(records);
+        }
+        let products = HasBranchFilteringNodePathTransformer::new(true)
+            .transform(maybe_product_root.unwrap().iter_children_req())
+            .filter_map(|path| match path.concretize() {
+                NodePathType::Product(product) => Some(product.get_qualified_path()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        for product in products {
+            let history = self.get_commit_history(&product)?;
+            if let Some(record) = DerivationRecord::latest(&history) {
+                records.push((product, record));
+            }
+        }
+        Ok(records)
     }
 
     // all git commands
@@ -125,7 +360,7 @@ impl GitInterface {
     pub(super) fn checkout_raw(&self, path: &QualifiedPath) -> Result<Output, GitError> {
         Ok(self
             .raw_git_interface
-            .run(vec!["checkout", path.to_git_branch().as_str()])?)
+            .run(vec!["checkout", self.ref_name(path).as_str()])?)
     }
     pub fn checkout(&self, path: &QualifiedPath) -> Result<Output, GitError> {
         if !self.model.has_branch(&path) {
@@ -135,8 +370,16 @@ impl GitInterface {
         }
         self.checkout_raw(&path)
     }
+    pub fn checkout_detached(&self, path: &QualifiedPath) -> Result<Output, GitError> {
+        Ok(self.raw_git_interface.run(vec![
+            "checkout",
+            "--detach",
+            self.ref_name(path).as_str(),
+        ])?)
+    }
     pub(super) fn create_branch_no_mut(&self, path: &QualifiedPath) -> Result<Output, GitError> {
-        let branch = path.to_git_branch();
+        path.validate()?;
+        let branch = self.ref_name(path);
         let commands = vec!["branch", branch.as_str()];
         Ok(self.raw_git_interface.run(commands)?)
     }
@@ -151,14 +394,514 @@ impl GitInterface {
             )))
         }
     }
+    pub fn create_branch_from(
+        &mut self,
+        path: &QualifiedPath,
+        start: &QualifiedPath,
+    ) -> Result<Output, GitError> {
+        path.validate()?;
+        let branch = self.ref_name(path);
+        let start_point = self.ref_name(start);
+        let output =
+            self.raw_git_interface
+                .run(vec!["branch", branch.as_str(), start_point.as_str()])?;
+        if output.status.success() {
+            self.model.insert_qualified_path(path.clone(), false)?;
+            Ok(output)
+        } else {
+            Err(GitError::GitInterface(GitInterfaceError::new(
+                u8_to_string(&output.stderr).as_str(),
+            )))
+        }
+    }
     pub fn delete_branch(&self, path: &QualifiedPath) -> Result<Output, GitError> {
-        let branch = path.to_git_branch();
+        let branch = self.ref_name(path);
         let commands = vec!["branch", "-D", branch.as_str()];
         Ok(self.raw_git_interface.run(commands)?)
     }
+    pub fn rename_branch(
+        &self,
+        from: &QualifiedPath,
+        to: &QualifiedPath,
+    ) -> Result<Output, GitError> {
+        to.validate()?;
+        let from_branch = self.ref_name(from);
+        let to_branch = self.ref_name(to);
+        Ok(self.raw_git_interface.run(vec![
+            "branch",
+            "-m",
+            from_branch.as_str(),
+            to_branch.as_str(),
+        ])?)
+    }
+    pub fn rename_tag(&self, from: &QualifiedPath, to: &QualifiedPath) -> Result<Output, GitError> {
+        let from_ref = format!("refs/tags/{}", self.ref_name(from));
+        let to_ref = format!("refs/tags/{}", self.ref_name(to));
+        let output = self.raw_git_interface.run(vec![
+            "update-ref",
+            to_ref.as_str(),
+            from_ref.as_str(),
+            "",
+        ])?;
+        if !output.status.success() {
+            return Ok(output);
+        }
+        Ok(self
+            .raw_git_interface
+            .run(vec!["update-ref", "-d", from_ref.as_str()])?)
+    }
+    fn run_checked(&self, args: Vec<&str>) -> Result<Output, GitError> {
+        let output = self.raw_git_interface.run(args)?;
+        if output.status.success() {
+            Ok(output)
+        } else {
+            Err(GitError::GitInterface(GitInterfaceError::new(
+                u8_to_string(&output.stderr).trim(),
+            )))
+        }
+    }
+    /// Deletes a branch after storing its tip in the backup namespace.
+    pub fn delete_branch_with_backup(&self, path: &QualifiedPath) -> Result<(), GitError> {
+        let branch = self.ref_name(path);
+        let backup = BACKUP_BRANCH_NAMESPACE.to_string() + branch.as_str();
+        let source = "refs/heads/".to_string() + branch.as_str();
+        self.run_checked(vec!["update-ref", backup.as_str(), source.as_str()])?;
+        self.run_checked(vec!["branch", "-D", branch.as_str()])?;
+        Ok(())
+    }
+    /// Deletes a tag given by its complete path after storing it in the backup namespace.
+    pub fn delete_tag_with_backup(&self, path: &QualifiedPath) -> Result<(), GitError> {
+        let tag = self.ref_name(path);
+        let backup = BACKUP_TAG_NAMESPACE.to_string() + tag.as_str();
+        let source = "refs/tags/".to_string() + tag.as_str();
+        self.run_checked(vec!["update-ref", backup.as_str(), source.as_str()])?;
+        self.run_checked(vec!["update-ref", "-d", source.as_str()])?;
+        Ok(())
+    }
+    fn list_refs(&self, namespace: &str) -> Result<Vec<String>, GitError> {
+        let output =
+            self.raw_git_interface
+                .run(vec!["for-each-ref", "--format=%(refname)", namespace])?;
+        Ok(u8_to_string(&output.stdout)
+            .split("\n")
+            .filter_map(|line| line.trim().strip_prefix(namespace))
+            .map(|name| name.to_string())
+            .collect())
+    }
+    pub fn list_remotes(&self) -> Result<Vec<String>, GitError> {
+        let output = self.run_checked(vec!["remote"])?;
+        Ok(u8_to_string(&output.stdout)
+            .lines()
+            .map(|line| line.trim().to_string())
+            .filter(|line| !line.is_empty())
+            .collect())
+    }
+    /// Returns the full names of the refs of a remote, without peeled tags.
+    fn list_remote_refs(&self, remote: &str) -> Result<Vec<String>, GitError> {
+        let output = self.run_checked(vec!["ls-remote", remote])?;
+        Ok(u8_to_string(&output.stdout)
+            .lines()
+            .filter_map(|line| line.split_once('\t'))
+            .map(|(_, name)| name.to_string())
+            .filter(|name| !name.ends_with("^{}"))
+            .collect())
+    }
+    /// Checks whether the metadata ref `refs/tangl/<name>` is exchanged with remotes of `area`.
+    /// These are the data ref and the archived branches of the area. Backups and the
+    /// remote-tracking refs of the metadata stay local.
+    fn is_shared_metadata(&self, name: &str, area: &QualifiedPath) -> bool {
+        let archive = ARCHIVE_NAMESPACE.strip_prefix(METADATA_NAMESPACE).unwrap();
+        match name.strip_prefix(archive) {
+            Some(branch) => self
+                .model
+                .get_naming()
+                .parse_ref_name(branch)
+                .is_some_and(|path| path.has_prefix(area)),
+            None => name == "data",
+        }
+    }
+    /// Returns the names of the metadata refs below `refs/tangl/` that are exchanged with
+    /// remotes of `area`, e.g. `data`.
+    fn list_metadata_refs(&self, area: &QualifiedPath) -> Result<Vec<String>, GitError> {
+        Ok(self
+            .list_refs(METADATA_NAMESPACE)?
+            .into_iter()
+            .filter(|name| self.is_shared_metadata(name, area))
+            .collect())
+    }
+    /// Returns the ref that keeps the tip of the deleted or archived branch of `path`.
+    fn get_removed_branch_ref(&self, path: &QualifiedPath) -> Result<Option<String>, GitError> {
+        if self.model.has_branch(path) {
+            return Ok(None);
+        }
+        for namespace in [ARCHIVE_NAMESPACE, BACKUP_BRANCH_NAMESPACE] {
+            let name = namespace.to_string() + self.ref_name(path).as_str();
+            let output = self.raw_git_interface.run(vec![
+                "rev-parse",
+                "--verify",
+                "--quiet",
+                name.as_str(),
+            ])?;
+            if output.status.success() {
+                return Ok(Some(name));
+            }
+        }
+        Ok(None)
+    }
+    /// Returns the remote-tracking branches of `area` whose local branch was deleted or
+    /// archived, together with whether all of their commits are kept by the backup or archive.
+    /// These branches are not recreated by a pull, and the contained ones are deleted on the
+    /// remote by a push.
+    pub fn get_removed_upstream_branches(
+        &self,
+        remote: &str,
+        area: &QualifiedPath,
+    ) -> Result<Vec<(QualifiedPath, bool)>, GitError> {
+        let mut removed = Vec::new();
+        for path in self.model.get_remote_branches(remote) {
+            if !path.has_prefix(area) {
+                continue;
+            }
+            if let Some(name) = self.get_removed_branch_ref(path)? {
+                let output = self.raw_git_interface.run(vec![
+                    "merge-base",
+                    "--is-ancestor",
+                    self.upstream_revision(remote, path).as_str(),
+                    name.as_str(),
+                ])?;
+                removed.push((path.clone(), output.status.success()));
+            }
+        }
+        Ok(removed)
+    }
+    /// Deletes the refs below `namespace` that satisfy `is_stale`.
+    fn prune_refs(&self, namespace: &str, is_stale: impl Fn(&str) -> bool) -> Result<(), GitError> {
+        for name in self.list_refs(namespace)? {
+            if is_stale(&name) {
+                let full_name = namespace.to_string() + name.as_str();
+                self.run_checked(vec!["update-ref", "-d", full_name.as_str()])?;
+            }
+        }
+        Ok(())
+    }
+    /// Fetches the branches, tags and archived branches of `area` and the data ref from `remote`.
+    ///
+    /// Branches are fetched into the remote-tracking branches `refs/remotes/<remote>/`, tags
+    /// directly and metadata refs into `refs/tangl/remotes/<remote>/`. Remote-tracking refs
+    /// whose ref was deleted on the remote are removed.
+    pub fn fetch(&mut self, remote: &str, area: &QualifiedPath) -> Result<Output, GitError> {
+        let naming = self.model.get_naming().clone();
+        let in_area = |name: &str| {
+            naming
+                .parse_ref_name(name)
+                .is_some_and(|path| path.has_prefix(area))
+        };
+        let mut refspecs = Vec::new();
+        let mut branches = Vec::new();
+        let mut metadata_refs = Vec::new();
+        for name in self.list_remote_refs(remote)? {
+            if let Some(branch) = name.strip_prefix("refs/heads/") {
+                if in_area(branch) {
+                    refspecs.push(format!("+{}:refs/remotes/{}/{}", name, remote, branch));
+                    branches.push(branch.to_string());
+                }
+            } else if let Some(tag) = name.strip_prefix("refs/tags/") {
+                if in_area(tag) {
+                    refspecs.push(format!("+{}:{}", name, name));
+                }
+            } else if let Some(metadata) = name.strip_prefix(METADATA_NAMESPACE)
+                && !name.starts_with(REMOTE_METADATA_NAMESPACE)
+                && self.is_shared_metadata(metadata, area)
+            {
+                refspecs.push(format!(
+                    "+{}:{}{}/{}",
+                    name, REMOTE_METADATA_NAMESPACE, remote, metadata
+                ));
+                metadata_refs.push(metadata.to_string());
+            }
+        }
+        let mut args = vec!["fetch", "--no-tags", remote];
+        args.extend(refspecs.iter().map(|refspec| refspec.as_str()));
+        let output = self.run_checked(args)?;
+        self.prune_refs(&format!("refs/remotes/{}/", remote), |name| {
+            in_area(name) && !branches.iter().any(|branch| branch == name)
+        })?;
+        self.prune_refs(
+            &format!("{}{}/", REMOTE_METADATA_NAMESPACE, remote),
+            |name| {
+                if self.is_shared_metadata(name, area) {
+                    !metadata_refs.iter().any(|metadata| metadata == name)
+                } else {
+                    // archived branches of other areas are pruned when fetching those
+                    !name.starts_with("archive/")
+                }
+            },
+        )?;
+        self.update_complete_model()?;
+        Ok(output)
+    }
+    /// Pushes the branches, tags and archived branches of `area` and the data ref to `remote`
+    /// and updates the remote-tracking refs afterwards.
+    ///
+    /// Branches of the remote that were deleted or archived locally are deleted on the remote,
+    /// as long as they contain no commits that only exist on the remote.
+    pub fn push(
+        &mut self,
+        remote: &str,
+        area: &QualifiedPath,
+        force: bool,
+    ) -> Result<Output, GitError> {
+        let force_marker = if force { "+" } else { "" };
+        let mut refspecs = Vec::new();
+        for path in self.model.get_qualified_paths_with_branches() {
+            if !path.has_prefix(area) {
+                continue;
+            }
+            let is_tag = self
+                .model
+                .get_node_path(path)
+                .is_some_and(|node| matches!(node.concretize(), NodePathType::Tag(_)));
+            let name = if is_tag {
+                format!("refs/tags/{}", self.ref_name(path))
+            } else {
+                format!("refs/heads/{}", self.ref_name(path))
+            };
+            refspecs.push(format!("{}{}:{}", force_marker, name, name));
+        }
+        for (path, contained) in self.get_removed_upstream_branches(remote, area)? {
+            if contained {
+                refspecs.push(format!(":refs/heads/{}", self.ref_name(&path)));
+            }
+        }
+        for metadata in self.list_metadata_refs(area)? {
+            let name = METADATA_NAMESPACE.to_string() + metadata.as_str();
+            refspecs.push(format!("{}{}:{}", force_marker, name, name));
+        }
+        let mut args = vec!["push", remote];
+        args.extend(refspecs.iter().map(|refspec| refspec.as_str()));
+        let output = self.run_checked(args)?;
+        self.fetch(remote, area)?;
+        Ok(output)
+    }
+    fn upstream_revision(&self, remote: &str, path: &QualifiedPath) -> String {
+        format!("refs/remotes/{}/{}", remote, self.ref_name(path))
+    }
+    /// Returns how many commits `path` is ahead of and behind its remote-tracking branch.
+    pub fn count_ahead_behind_upstream(
+        &self,
+        remote: &str,
+        path: &QualifiedPath,
+    ) -> Result<(usize, usize), GitError> {
+        let range = format!(
+            "{}...{}",
+            self.revision(path),
+            self.upstream_revision(remote, path)
+        );
+        let output =
+            self.run_checked(vec!["rev-list", "--left-right", "--count", range.as_str()])?;
+        let counts = u8_to_string(&output.stdout)
+            .split_whitespace()
+            .map(|count| count.parse().unwrap_or(0))
+            .collect::<Vec<usize>>();
+        Ok((
+            counts.first().copied().unwrap_or(0),
+            counts.get(1).copied().unwrap_or(0),
+        ))
+    }
+    /// Points the branch of `path` to its remote-tracking branch, creating it if necessary.
+    /// Must not be used for the current branch.
+    pub fn reset_to_upstream(
+        &self,
+        remote: &str,
+        path: &QualifiedPath,
+    ) -> Result<Output, GitError> {
+        let branch = format!("refs/heads/{}", self.ref_name(path));
+        self.run_checked(vec![
+            "update-ref",
+            branch.as_str(),
+            self.upstream_revision(remote, path).as_str(),
+        ])
+    }
+    /// Merges the remote-tracking branch of `path` into the checked out branch.
+    pub fn merge_upstream(&self, remote: &str, path: &QualifiedPath) -> Result<Output, GitError> {
+        Ok(self
+            .raw_git_interface
+            .run(vec!["merge", self.upstream_revision(remote, path).as_str()])?)
+    }
+    /// Fast-forwards the metadata refs to the ones fetched from `remote`. Returns the names of
+    /// the metadata refs that diverged and were kept as they are.
+    pub fn update_metadata_from_remote(&mut self, remote: &str) -> Result<Vec<String>, GitError> {
+        let namespace = format!("{}{}/", REMOTE_METADATA_NAMESPACE, remote);
+        let mut diverged = Vec::new();
+        for name in self.list_refs(&namespace)? {
+            let local = METADATA_NAMESPACE.to_string() + name.as_str();
+            let fetched = namespace.clone() + name.as_str();
+            let local_commit = self.raw_git_interface.run(vec![
+                "rev-parse",
+                "--verify",
+                "--quiet",
+                local.as_str(),
+            ])?;
+            if local_commit.status.success() {
+                let local_commit = u8_to_string(&local_commit.stdout).trim().to_string();
+                let is_ancestor = |l: &str, r: &str| -> Result<bool, GitError> {
+                    Ok(self
+                        .raw_git_interface
+                        .run(vec!["merge-base", "--is-ancestor", l, r])?
+                        .status
+                        .success())
+                };
+                if is_ancestor(&fetched, &local_commit)? {
+                    continue;
+                }
+                if !is_ancestor(&local_commit, &fetched)? {
+                    diverged.push(local);
+                    continue;
+                }
+            }
+            self.run_checked(vec!["update-ref", local.as_str(), fetched.as_str()])?;
+        }
+        self.update_complete_model()?;
+        Ok(diverged)
+    }
+    /// Returns the namespace and name of every ref whose name encodes a qualified path,
+    /// including refs that were not created by tangl.
+    pub fn list_path_refs(&self) -> Result<Vec<(&'static str, String)>, GitError> {
+        let mut refs = Vec::new();
+        for namespace in PATH_NAMESPACES {
+            for name in self.list_refs(namespace)? {
+                refs.push((namespace, name));
+            }
+        }
+        Ok(refs)
+    }
+    /// Renames a ref inside its namespace. Branches are renamed with `git branch -m`, so that
+    /// a checked out branch stays checked out.
+    pub fn rename_ref(&self, namespace: &str, from: &str, to: &str) -> Result<(), GitError> {
+        if namespace == "refs/heads/" {
+            self.run_checked(vec!["branch", "-m", from, to])?;
+            return Ok(());
+        }
+        let source = namespace.to_string() + from;
+        let target = namespace.to_string() + to;
+        self.run_checked(vec!["update-ref", target.as_str(), source.as_str(), ""])?;
+        self.run_checked(vec!["update-ref", "-d", source.as_str()])?;
+        Ok(())
+    }
+    /// Returns the paths of all backed up branches.
+    pub fn get_branch_backups(&self) -> Result<Vec<QualifiedPath>, GitError> {
+        Ok(self
+            .list_refs(BACKUP_BRANCH_NAMESPACE)?
+            .iter()
+            .filter_map(|name| self.model.get_naming().from_ref_name(name).ok())
+            .collect())
+    }
+    /// Restores all backed up branches and tags located at or below `prefix`.
+    ///
+    /// Returns the restored branches.
+    pub fn restore_backups(&self, prefix: &QualifiedPath) -> Result<Vec<QualifiedPath>, GitError> {
+        let mut restored = Vec::new();
+        for (namespace, target_namespace) in [
+            (BACKUP_BRANCH_NAMESPACE, "refs/heads/"),
+            (BACKUP_TAG_NAMESPACE, "refs/tags/"),
+        ] {
+            for name in self.list_refs(namespace)? {
+                let path = match self.model.get_naming().from_ref_name(&name) {
+                    Ok(path) if path.has_prefix(prefix) => path,
+                    _ => continue,
+                };
+                let backup = namespace.to_string() + name.as_str();
+                let target = target_namespace.to_string() + name.as_str();
+                self.run_checked(vec!["update-ref", target.as_str(), backup.as_str(), ""])?;
+                self.run_checked(vec!["update-ref", "-d", backup.as_str()])?;
+                if namespace == BACKUP_BRANCH_NAMESPACE {
+                    restored.push(path);
+                }
+            }
+        }
+        Ok(restored)
+    }
+    /// Moves a branch to the archive namespace. It stays resolvable, but is no branch anymore.
+    pub fn archive_branch(&self, path: &QualifiedPath) -> Result<(), GitError> {
+        let branch = self.ref_name(path);
+        let archive = ARCHIVE_NAMESPACE.to_string() + branch.as_str();
+        let source = "refs/heads/".to_string() + branch.as_str();
+        self.run_checked(vec!["update-ref", archive.as_str(), source.as_str(), ""])?;
+        self.run_checked(vec!["branch", "-D", branch.as_str()])?;
+        Ok(())
+    }
+    /// Moves an archived branch back to the branch namespace.
+    pub fn unarchive_branch(&self, path: &QualifiedPath) -> Result<(), GitError> {
+        let branch = self.ref_name(path);
+        let archive = ARCHIVE_NAMESPACE.to_string() + branch.as_str();
+        let target = "refs/heads/".to_string() + branch.as_str();
+        self.run_checked(vec!["update-ref", target.as_str(), archive.as_str(), ""])?;
+        self.run_checked(vec!["update-ref", "-d", archive.as_str()])?;
+        Ok(())
+    }
+    /// Counts the commits reachable from `branch` but not from `base`.
+    pub fn count_commits_not_in(
+        &self,
+        branch: &QualifiedPath,
+        base: &QualifiedPath,
+    ) -> Result<usize, GitError> {
+        let range = format!("{}..{}", self.revision(base), self.revision(branch));
+        let output = self.run_checked(vec!["rev-list", "--count", range.as_str()])?;
+        Ok(u8_to_string(&output.stdout).trim().parse().unwrap_or(0))
+    }
+    /// Returns the best common ancestor of two paths, or `None` if their histories are unrelated.
+    pub fn get_merge_base(
+        &self,
+        l: &QualifiedPath,
+        r: &QualifiedPath,
+    ) -> Result<Option<String>, GitError> {
+        let output = self.raw_git_interface.run(vec![
+            "merge-base",
+            self.revision(l).as_str(),
+            self.revision(r).as_str(),
+        ])?;
+        let commit = u8_to_string(&output.stdout).trim().to_string();
+        Ok(Some(commit).filter(|commit| output.status.success() && !commit.is_empty()))
+    }
+    /// Returns the ref that holds the commits of `path`: its branch or tag, or the archive or
+    /// backup of a removed branch. Returns `None` if nothing is left of the branch.
+    pub fn get_revision(&self, path: &QualifiedPath) -> Result<Option<String>, GitError> {
+        if self.model.has_branch(path) || self.model.is_archived(path) {
+            return Ok(Some(self.revision(path)));
+        }
+        self.get_removed_branch_ref(path)
+    }
+    /// Checks whether `commit` is part of the history of `path`, which may be a removed branch,
+    /// see [`GitInterface::get_revision`].
+    pub fn is_ancestor(&self, commit: &str, path: &QualifiedPath) -> Result<bool, GitError> {
+        let revision = self.get_revision(path)?.ok_or_else(|| {
+            GitError::GitInterface(GitInterfaceError::new(
+                format!("{} has no branch", path.to_string()).as_str(),
+            ))
+        })?;
+        let output = self.raw_git_interface.run(vec![
+            "merge-base",
+            "--is-ancestor",
+            commit,
+            revision.as_str(),
+        ])?;
+        match output.status.code() {
+            Some(0) => Ok(true),
+            Some(1) => Ok(false),
+            _ => Err(GitError::GitInterface(GitInterfaceError::new(
+                u8_to_string(&output.stderr).trim(),
+            ))),
+        }
+    }
+    /// Returns the commit a tag points to.
+    pub fn get_tag_commit(&self, tag: &QualifiedPath) -> Result<String, GitError> {
+        let revision = format!("refs/tags/{}^{{commit}}", self.ref_name(tag));
+        let output = self.run_checked(vec!["rev-parse", revision.as_str()])?;
+        Ok(u8_to_string(&output.stdout).trim().to_string())
+    }
     pub fn merge(&self, paths: &Vec<QualifiedPath>) -> Result<Output, GitError> {
         let mut base = vec!["merge"];
-        let new_paths: Vec<String> = paths.iter().map(|s| s.to_git_branch()).collect();
+        let new_paths: Vec<String> = paths.iter().map(|s| self.ref_name(s)).collect();
         let converted_paths: Vec<&str> = new_paths.iter().map(|p| p.as_str()).collect();
         base.extend(converted_paths);
         Ok(self.raw_git_interface.run(base)?)
@@ -166,25 +909,34 @@ impl GitInterface {
     pub fn abort_merge(&self) -> Result<Output, GitError> {
         Ok(self.raw_git_interface.run(vec!["merge", "--abort"])?)
     }
+    /// Rebases the checked out branch onto `onto`.
+    pub fn rebase(&self, onto: &QualifiedPath) -> Result<Output, GitError> {
+        Ok(self
+            .raw_git_interface
+            .run(vec!["rebase", self.ref_name(onto).as_str()])?)
+    }
+    pub fn abort_rebase(&self) -> Result<Output, GitError> {
+        Ok(self.raw_git_interface.run(vec!["rebase", "--abort"])?)
+    }
     pub fn create_tag(&self, tag: &QualifiedPath) -> Result<Output, GitError> {
         let current_branch = self.get_current_qualified_path()?;
         let tagged = current_branch + tag.clone();
         Ok(self
             .raw_git_interface
-            .run(vec!["tag", tagged.to_git_branch().as_str()])?)
+            .run(vec!["tag", self.ref_name(&tagged).as_str()])?)
     }
     pub fn delete_tag(&self, tag: &QualifiedPath) -> Result<Output, GitError> {
         let current_branch = self.get_current_qualified_path()?;
         let tagged = current_branch + tag.clone();
         Ok(self
             .raw_git_interface
-            .run(vec!["tag", "-d", tagged.to_git_branch().as_str()])?)
+            .run(vec!["tag", "-d", self.ref_name(&tagged).as_str()])?)
     }
     pub fn get_commit_history(&self, branch: &QualifiedPath) -> Result<Vec<Commit>, GitError> {
         let raw_hashes = u8_to_string(
             &self
                 .raw_git_interface
-                .run(vec!["log", "--format=%H", branch.to_git_branch().as_str()])?
+                .run(vec!["log", "--format=%H", self.revision(branch).as_str()])?
                 .stdout,
         )
         .trim()
@@ -216,13 +968,139 @@ impl GitInterface {
             "ls-tree",
             "-r",
             "--name-only",
-            branch.to_git_branch().as_str(),
+            self.revision(branch).as_str(),
         ])?;
         Ok(u8_to_string(&out.stdout)
             .split("\n")
             .map(|e| e.to_string())
             .collect())
     }
+    /// Returns the files changed on `branch` since it diverged from `base`.
+    pub fn get_files_changed_since(
+        &self,
+        branch: &QualifiedPath,
+        base: &QualifiedPath,
+    ) -> Result<Vec<String>, GitError> {
+        let range = format!("{}...{}", self.revision(base), self.revision(branch));
+        let output = self.run_checked(vec!["diff", "--name-only", range.as_str()])?;
+        Ok(u8_to_string(&output.stdout)
+            .split("\n")
+            .filter(|e| !e.is_empty())
+            .map(|e| e.to_string())
+            .collect())
+    }
+    /// Returns the files with unresolved conflicts in the current checkout.
+    pub fn get_unmerged_files(&self) -> Result<Vec<String>, GitError> {
+        let output = self.run_checked(vec!["diff", "--name-only", "--diff-filter=U"])?;
+        Ok(u8_to_string(&output.stdout)
+            .split("\n")
+            .filter(|e| !e.is_empty())
+            .map(|e| e.to_string())
+            .collect())
+    }
+    /// Returns the commits of `branch` that are not in `base` and touch `file`, newest first.
+    pub fn get_commits_touching(
+        &self,
+        branch: &QualifiedPath,
+        base: &QualifiedPath,
+        file: &str,
+    ) -> Result<Vec<Commit>, GitError> {
+        let range = format!("{}..{}", self.revision(base), self.revision(branch));
+        let output = self.run_checked(vec!["log", "--format=%H %s", range.as_str(), "--", file])?;
+        Ok(u8_to_string(&output.stdout)
+            .split("\n")
+            .filter_map(|line| line.split_once(' '))
+            .map(|(hash, message)| Commit::new(hash, message))
+            .collect())
+    }
+    /// Returns the commits of `branch` that are not in `base`, newest first, together with the
+    /// paths whose branches or tags point to them.
+    pub fn get_commits_not_in(
+        &self,
+        branch: &QualifiedPath,
+        base: &QualifiedPath,
+    ) -> Result<Vec<(Commit, Vec<QualifiedPath>)>, GitError> {
+        let range = format!("{}..{}", self.revision(base), self.revision(branch));
+        let output = self.run_checked(vec![
+            "log",
+            "--decorate=full",
+            "--format=%H%x09%D%x09%s",
+            range.as_str(),
+            "--",
+        ])?;
+        Ok(u8_to_string(&output.stdout)
+            .lines()
+            .filter_map(|line| {
+                let mut parts = line.splitn(3, '\t');
+                let hash = parts.next()?;
+                let decorations = parts.next()?;
+                let paths = decorations
+                    .split(", ")
+                    .filter_map(|decoration| self.parse_decoration(decoration))
+                    .collect();
+                Some((Commit::new(hash, parts.next().unwrap_or("")), paths))
+            })
+            .collect())
+    }
+    /// Decodes a ref name as printed by `git log --decorate=full`, e.g. `tag: refs/tags/...`.
+    fn parse_decoration(&self, decoration: &str) -> Option<QualifiedPath> {
+        let name = decoration.trim_start_matches("HEAD -> ");
+        let name = name.strip_prefix("tag: ").unwrap_or(name);
+        let name = name
+            .strip_prefix("refs/heads/")
+            .or_else(|| name.strip_prefix("refs/tags/"))?;
+        self.model.get_naming().parse_ref_name(name)
+    }
+    /// Returns the diff between `from` and `to`. With `merge_base`, the diff starts at the best
+    /// common ancestor instead, so it only contains the changes made on `to`.
+    pub fn diff(
+        &self,
+        from: &QualifiedPath,
+        to: &QualifiedPath,
+        merge_base: bool,
+        stat: bool,
+    ) -> Result<String, GitError> {
+        let from = self.revision(from);
+        let to = self.revision(to);
+        let range = if merge_base {
+            format!("{}...{}", from, to)
+        } else {
+            format!("{}..{}", from, to)
+        };
+        let mut args = vec!["diff"];
+        if stat {
+            args.push("--stat");
+        }
+        args.extend([range.as_str(), "--"]);
+        let output = self.run_checked(args)?;
+        Ok(u8_to_string(&output.stdout))
+    }
+    /// Returns the lines of `file` on `branch`, each with the commit that last changed it.
+    pub fn blame(&self, branch: &QualifiedPath, file: &str) -> Result<Vec<BlameLine>, GitError> {
+        let revision = self.revision(branch);
+        let output =
+            self.run_checked(vec!["blame", "--porcelain", revision.as_str(), "--", file])?;
+        let mut lines = Vec::new();
+        let mut commit = "";
+        let mut number = 0;
+        let stdout = u8_to_string(&output.stdout);
+        for line in stdout.lines() {
+            if let Some(content) = line.strip_prefix('\t') {
+                lines.push(BlameLine::new(commit, number, content));
+                continue;
+            }
+            // every line is preceded by a header "<commit> <original line> <final line> ..."
+            let parts = line.split(' ').collect::<Vec<_>>();
+            if parts.len() >= 3
+                && parts[0].len() >= 40
+                && parts[0].chars().all(|c| c.is_ascii_hexdigit())
+            {
+                commit = parts[0];
+                number = parts[2].parse().unwrap_or(0);
+            }
+        }
+        Ok(lines)
+    }
     pub fn get_files_changed_by_commit(&self, commit: &str) -> Result<Vec<String>, GitError> {
         let out = self.raw_git_interface.run(vec![
             "diff-tree",
@@ -247,6 +1125,77 @@ impl GitInterface {
     pub fn cherry_pick(&self, commit: &str) -> Result<Output, GitError> {
         Ok(self.raw_git_interface.run(vec!["cherry-pick", commit])?)
     }
+    pub fn abort_cherry_pick(&self) -> Result<Output, GitError> {
+        Ok(self.raw_git_interface.run(vec!["cherry-pick", "--abort"])?)
+    }
+    fn get_common_dir(&self) -> Result<PathBuf, GitError> {
+        let output = self.raw_git_interface.run(vec![
+            "rev-parse",
+            "--path-format=absolute",
+            "--git-common-dir",
+        ])?;
+        if !output.status.success() {
+            return Err(GitError::GitInterface(GitInterfaceError::new(
+                u8_to_string(&output.stderr).as_str(),
+            )));
+        }
+        Ok(PathBuf::from(u8_to_string(&output.stdout).trim()))
+    }
+    fn get_worktrees_dir(&self) -> Result<PathBuf, GitError> {
+        let mut path = self.get_common_dir()?;
+        path.push("tangl");
+        path.push("worktrees");
+        Ok(path)
+    }
+    /// Returns the temporary worktrees of tangl that still exist, e.g. because tangl was killed
+    /// before it could remove them.
+    pub fn list_tangl_worktrees(&self) -> Result<Vec<PathBuf>, GitError> {
+        let directory = self.get_worktrees_dir()?;
+        let output = self.run_checked(vec!["worktree", "list", "--porcelain"])?;
+        Ok(u8_to_string(&output.stdout)
+            .lines()
+            .filter_map(|line| line.strip_prefix("worktree "))
+            .map(PathBuf::from)
+            .filter(|path| path.starts_with(&directory))
+            .collect())
+    }
+    pub fn remove_worktree(&self, path: &Path) -> Result<(), GitError> {
+        let path = path.to_str().unwrap();
+        self.run_checked(vec!["worktree", "remove", "--force", path])?;
+        self.run_checked(vec!["worktree", "prune"])?;
+        Ok(())
+    }
+    /// Creates a temporary worktree with a detached HEAD.
+    ///
+    /// The worktree lives inside the git directory and shares all branches with this
+    /// repository, so branches can be checked out and modified there without moving
+    /// the HEAD of the current checkout.
+    pub fn create_worktree(&self) -> Result<Worktree, GitError> {
+        let mut path = self.get_worktrees_dir()?;
+        path.push(format!(
+            "{}-{}",
+            std::process::id(),
+            WORKTREE_COUNTER.fetch_add(1, Ordering::SeqCst)
+        ));
+        let path_str = path.to_str().unwrap().to_string();
+        let output =
+            self.raw_git_interface
+                .run(vec!["worktree", "add", "--detach", path_str.as_str()])?;
+        if !output.status.success() {
+            return Err(GitError::GitInterface(GitInterfaceError::new(
+                u8_to_string(&output.stderr).as_str(),
+            )));
+        }
+        let interface = Self {
+            model: self.model.clone(),
+            raw_git_interface: GitCLI::in_custom_directory(path.clone()),
+        };
+        Ok(Worktree::new(
+            interface,
+            path,
+            self.raw_git_interface.clone(),
+        ))
+    }
 }
 
 #[cfg(test)]
@@ -280,4 +1229,33 @@ pub mod test_utils {
         }
         Ok(())
     }
+
+    pub fn commit_file_to_branch(
+        path: PathBuf,
+        branch: &str,
+        file_name: &str,
+        content: &str,
+    ) -> Result<(), GitError> {
+        let git = GitCLI::in_custom_directory(path.clone());
+        let current = git.run(vec!["branch", "--show-current"])?;
+        git.run(vec!["checkout", branch])?;
+        let mut file = path.clone();
+        file.push(file_name);
+        fs::write(file.clone(), content)?;
+        git.run(vec!["add", file.to_str().unwrap()])?;
+        git.run(vec![
+            "commit",
+            "-m",
+            format!("change {}", file_name).as_str(),
+        ])?;
+        let current_branch = String::from_utf8(current.stdout).unwrap();
+        git.run(vec!["checkout", current_branch.trim()])?;
+        Ok(())
+    }
+
+    pub fn get_current_branch(path: PathBuf) -> Result<String, GitError> {
+        let git = GitCLI::in_custom_directory(path);
+        let output = git.run(vec!["branch", "--show-current"])?;
+        Ok(String::from_utf8(output.stdout).unwrap().trim().to_string())
+    }
 }
@@ -1,4 +1,6 @@
 pub mod conflict;
 mod error;
 pub mod interface;
+pub mod ownership;
 pub mod persistency;
+pub mod worktree;
@@ -0,0 +1,89 @@
+use crate::git::error::GitError;
+use crate::git::interface::GitInterface;
+use crate::model::{
+    Area, HasBranchFilteringNodePathTransformer, NodePath, NodePathTransformer, NodePathType,
+    QualifiedPath,
+};
+use std::collections::BTreeMap;
+
+/// Files that are owned by at least this many features are likely to cause conflicts.
+pub const HOTSPOT_THRESHOLD: usize = 2;
+
+/// Maps the tracked files of an area to the features that own them.
+///
+/// A feature owns a file if it adds or modifies the file relative to its parent feature.
+/// Files that no feature touches are owned by the area itself.
+#[derive(Clone, Debug, Default)]
+pub struct FileOwnership {
+    owners: BTreeMap<String, Vec<QualifiedPath>>,
+}
+
+impl FileOwnership {
+    pub fn compute(git: &GitInterface, area: &NodePath<Area>) -> Result<Self, GitError> {
+        let mut owners: BTreeMap<String, Vec<QualifiedPath>> = BTreeMap::new();
+        let area_path = area.get_qualified_path();
+        for file in git.get_files_managed_by_branch(&area_path)? {
+            if !file.is_empty() {
+                owners.insert(file, vec![]);
+            }
+        }
+        if let Some(feature_root) = area.clone().to_feature_root() {
+            let transformer = HasBranchFilteringNodePathTransformer::new(true);
+            let features = transformer
+                .transform(feature_root.iter_children_req())
+                .filter(|path| matches!(path.clone().concretize(), NodePathType::Feature(_)));
+            for feature in features {
+                let parent = match feature.get_parent_with_branch() {
+                    Some(parent) => parent,
+                    None => continue,
+                };
+                let path = feature.get_qualified_path();
+                for file in git.get_files_changed_since(&path, &parent)? {
+                    owners.entry(file).or_default().push(path.clone());
+                }
+            }
+        }
+        for features in owners.values_mut() {
+            if features.is_empty() {
+                features.push(area_path.clone());
+            }
+        }
+        Ok(Self { owners })
+    }
+    /// Returns the owners of `file`, or an empty slice if the file is unknown.
+    pub fn get_owners(&self, file: &str) -> &[QualifiedPath] {
+        self.owners.get(file).map(|o| o.as_slice()).unwrap_or(&[])
+    }
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Vec<QualifiedPath>)> {
+        self.owners.iter()
+    }
+    /// Returns the files owned by at least [`HOTSPOT_THRESHOLD`] features, most owners first.
+    pub fn get_hotspots(&self) -> Vec<(&String, &Vec<QualifiedPath>)> {
+        let mut hotspots = self
+            .owners
+            .iter()
+            .filter(|(_, owners)| owners.len() >= HOTSPOT_THRESHOLD)
+            .collect::<Vec<_>>();
+        hotspots.sort_by_key(|(_, owners)| std::cmp::Reverse(owners.len()));
+        hotspots
+    }
+    /// Returns the candidates that could have made a change to all `files`, i.e. the candidates
+    /// that own every file themselves or through one of their ancestors.
+    pub fn get_features_owning_all(
+        &self,
+        files: &[String],
+        candidates: &[QualifiedPath],
+    ) -> Vec<QualifiedPath> {
+        candidates
+            .iter()
+            .filter(|candidate| {
+                files.iter().all(|file| {
+                    self.get_owners(file)
+                        .iter()
+                        .any(|owner| candidate.has_prefix(owner))
+                })
+            })
+            .cloned()
+            .collect()
+    }
+}
@@ -1,45 +1,139 @@
+use crate::git::error::{GitError, GitInterfaceError};
 use crate::git::interface::GitCLI;
 use crate::util::u8_to_string;
-use std::error::Error;
-use std::fs::{read_to_string, write};
+use std::fs::{create_dir_all, read_to_string, write};
+use std::path::PathBuf;
 
-trait PersistencyHandler<E> {
+const DATA_REF: &str = "refs/tangl/data";
+
+pub trait PersistencyHandler<E> {
     fn read_file(&self) -> Result<String, E>;
     fn write_file(&self, data: &str) -> Result<(), E>;
 }
 
+/// Stores a file in the `tangl` directory inside the git directory of the repository.
+///
+/// Files stored this way are local to the clone and are never shared.
 pub struct GitDirPersistencyHandler {
-    file_path: String,
+    file_name: String,
     raw_git_interface: GitCLI,
 }
 
 impl GitDirPersistencyHandler {
-    pub fn new(file_name: &str) -> Self {
-        let path = String::from("hypergit/") + file_name;
+    pub(super) fn new(file_name: &str, raw_git_interface: GitCLI) -> Self {
         Self {
-            file_path: path,
-            raw_git_interface: GitCLI::in_current_directory(),
+            file_name: file_name.to_string(),
+            raw_git_interface,
         }
     }
-    fn get_file_path(&self) -> String {
-        let maybe_output = self
-            .raw_git_interface
-            .run(vec!["rev-parse", "--show-toplevel"]);
-        match maybe_output {
-            Ok(output) => u8_to_string(&output.stdout),
-            Err(error) => {
-                panic!("{}", error)
-            }
+    fn get_file_path(&self) -> Result<PathBuf, GitError> {
+        let output = self.raw_git_interface.run(vec![
+            "rev-parse",
+            "--path-format=absolute",
+            "--git-common-dir",
+        ])?;
+        if !output.status.success() {
+            return Err(GitError::GitInterface(GitInterfaceError::new(
+                u8_to_string(&output.stderr).as_str(),
+            )));
         }
+        let mut path = PathBuf::from(u8_to_string(&output.stdout).trim());
+        path.push("tangl");
+        path.push(&self.file_name);
+        Ok(path)
     }
 }
 
-impl PersistencyHandler<Box<dyn Error>> for GitDirPersistencyHandler {
-    fn read_file(&self) -> Result<String, Box<dyn Error>> {
-        Ok(read_to_string(&self.get_file_path())?)
+impl PersistencyHandler<GitError> for GitDirPersistencyHandler {
+    fn read_file(&self) -> Result<String, GitError> {
+        let path = self.get_file_path()?;
+        if !path.exists() {
+            return Ok(String::new());
+        }
+        Ok(read_to_string(path)?)
+    }
+
+    fn write_file(&self, data: &str) -> Result<(), GitError> {
+        let path = self.get_file_path()?;
+        create_dir_all(path.parent().unwrap())?;
+        Ok(write(path, data)?)
     }
+}
 
-    fn write_file(&self, data: &str) -> Result<(), Box<dyn Error>> {
-        Ok(write(self.get_file_path(), data)?)
+/// Stores a file in the tree of the tangl data ref `refs/tangl/data`.
+///
+/// Every write creates a new commit on the data ref, so the files are versioned and can be
+/// exchanged with remotes like any other ref.
+pub struct GitRefPersistencyHandler {
+    file_name: String,
+    raw_git_interface: GitCLI,
+}
+
+impl GitRefPersistencyHandler {
+    pub(super) fn new(file_name: &str, raw_git_interface: GitCLI) -> Self {
+        Self {
+            file_name: file_name.to_string(),
+            raw_git_interface,
+        }
+    }
+    fn run_checked(&self, args: Vec<&str>, input: Option<&str>) -> Result<String, GitError> {
+        let output = match input {
+            Some(input) => self.raw_git_interface.run_with_input(args, input)?,
+            None => self.raw_git_interface.run(args)?,
+        };
+        if !output.status.success() {
+            return Err(GitError::GitInterface(GitInterfaceError::new(
+                u8_to_string(&output.stderr).as_str(),
+            )));
+        }
+        Ok(u8_to_string(&output.stdout).trim().to_string())
+    }
+    fn get_current_commit(&self) -> Option<String> {
+        self.run_checked(vec!["rev-parse", "--verify", "--quiet", DATA_REF], None)
+            .ok()
+    }
+}
+
+impl PersistencyHandler<GitError> for GitRefPersistencyHandler {
+    fn read_file(&self) -> Result<String, GitError> {
+        let object = format!("{}:{}", DATA_REF, self.file_name);
+        let output = self
+            .raw_git_interface
+            .run(vec!["cat-file", "blob", object.as_str()])?;
+        if !output.status.success() {
+            return Ok(String::new());
+        }
+        Ok(u8_to_string(&output.stdout))
+    }
+
+    fn write_file(&self, data: &str) -> Result<(), GitError> {
+        let blob = self.run_checked(vec!["hash-object", "-w", "--stdin"], Some(data))?;
+        let parent = self.get_current_commit();
+        let mut entries: Vec<String> = match parent {
+            Some(ref commit) => self
+                .run_checked(vec!["ls-tree", commit.as_str()], None)?
+                .split("\n")
+                .filter(|line| {
+                    !line.is_empty() && !line.ends_with(&format!("\t{}", self.file_name))
+                })
+                .map(|line| line.to_string())
+                .collect(),
+            None => Vec::new(),
+        };
+        entries.push(format!("100644 blob {}\t{}", blob, self.file_name));
+        let tree = self.run_checked(vec!["mktree"], Some((entries.join("\n") + "\n").as_str()))?;
+        let message = format!("Update {}", self.file_name);
+        let mut commit_args = vec!["commit-tree", tree.as_str(), "-m", message.as_str()];
+        if let Some(ref commit) = parent {
+            commit_args.push("-p");
+            commit_args.push(commit.as_str());
+        }
+        let commit = self.run_checked(commit_args, None)?;
+        let mut update_args = vec!["update-ref", DATA_REF, commit.as_str()];
+        if let Some(ref old) = parent {
+            update_args.push(old.as_str());
+        }
+        self.run_checked(update_args, None)?;
+        Ok(())
     }
 }
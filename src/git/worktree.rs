@@ -0,0 +1,71 @@
+use crate::git::error::{GitError, GitInterfaceError};
+use crate::git::interface::{GitCLI, GitInterface};
+use crate::model::QualifiedPath;
+use crate::util::u8_to_string;
+use std::path::PathBuf;
+use std::process::Output;
+
+fn check_status(output: Output) -> Result<(), GitError> {
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(GitError::GitInterface(GitInterfaceError::new(
+            u8_to_string(&output.stderr).trim(),
+        )))
+    }
+}
+
+/// A temporary `git worktree` managed by tangl.
+///
+/// All operations that need a checkout (merging, cherry-picking, committing) can be run
+/// through [`Worktree::interface`] without touching the checkout of the user.
+/// The worktree is removed as soon as this struct is dropped.
+#[derive(Debug)]
+pub struct Worktree {
+    interface: GitInterface,
+    path: PathBuf,
+    owner: GitCLI,
+}
+
+impl Worktree {
+    pub(super) fn new(interface: GitInterface, path: PathBuf, owner: GitCLI) -> Self {
+        Self {
+            interface,
+            path,
+            owner,
+        }
+    }
+    pub fn interface(&self) -> &GitInterface {
+        &self.interface
+    }
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+    /// Checks out the branch of `path`. A checkout that git refuses, e.g. because the branch
+    /// is checked out in another worktree, is an error, so that nothing is merged or committed
+    /// onto the previous HEAD of the worktree.
+    pub fn checkout(&self, path: &QualifiedPath) -> Result<(), GitError> {
+        check_status(self.interface.checkout(path)?)
+    }
+    /// Checks out the commit of `path` without a branch, see [`Worktree::checkout`].
+    pub fn checkout_detached(&self, path: &QualifiedPath) -> Result<(), GitError> {
+        check_status(self.interface.checkout_detached(path)?)
+    }
+    /// Discards all changes and untracked files, e.g. build output of a verify command,
+    /// so that the worktree can be reused for the next merge.
+    pub fn clean(&self) -> Result<(), GitError> {
+        let cli = GitCLI::in_custom_directory(self.path.clone());
+        check_status(cli.run(vec!["reset", "--hard"])?)?;
+        check_status(cli.run(vec!["clean", "-fdx"])?)
+    }
+}
+
+impl Drop for Worktree {
+    fn drop(&mut self) {
+        let path = self.path.to_str().unwrap().to_string();
+        let _ = self
+            .owner
+            .run(vec!["worktree", "remove", "--force", path.as_str()]);
+        let _ = self.owner.run(vec!["worktree", "prune"]);
+    }
+}
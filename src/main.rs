@@ -4,5 +4,8 @@ use tangl::git::interface::GitPath;
 fn main() {
     let command_repository =
         CommandRepository::new(Box::new(TangleCommand {}), GitPath::CurrentDirectory);
-    command_repository.execute(ArgSource::CLI);
+    if let Err(error) = command_repository.execute(ArgSource::CLI) {
+        eprintln!("{}", error);
+        std::process::exit(1);
+    }
 }
@@ -20,3 +20,30 @@ impl Commit {
         &self.message
     }
 }
+
+/// A line of a file together with the commit that last changed it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlameLine {
+    commit: String,
+    number: usize,
+    content: String,
+}
+
+impl BlameLine {
+    pub fn new<S1: Into<String>, S2: Into<String>>(commit: S1, number: usize, content: S2) -> Self {
+        Self {
+            commit: commit.into(),
+            number,
+            content: content.into(),
+        }
+    }
+    pub fn commit(&self) -> &String {
+        &self.commit
+    }
+    pub fn number(&self) -> usize {
+        self.number
+    }
+    pub fn content(&self) -> &String {
+        &self.content
+    }
+}
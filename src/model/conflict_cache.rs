@@ -0,0 +1,185 @@
+use crate::model::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictStatus {
+    Ok,
+    Conflict,
+    SemanticConflict,
+}
+
+impl Display for ConflictStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Ok => "OK",
+            Self::Conflict => "CONFLICT",
+            Self::SemanticConflict => "SEMANTIC CONFLICT",
+        };
+        f.write_str(name)
+    }
+}
+
+/// The result of checking a pair of features at the given branch tips.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ConflictRecord {
+    pub left: String,
+    pub right: String,
+    pub status: ConflictStatus,
+    pub left_tip: String,
+    pub right_tip: String,
+    /// Seconds since the Unix epoch.
+    pub timestamp: u64,
+}
+
+impl ConflictRecord {
+    fn key(&self) -> String {
+        make_key(&self.left, &self.right)
+    }
+    pub fn get_left_path(&self) -> QualifiedPath {
+        QualifiedPath::from(self.left.clone())
+    }
+    pub fn get_right_path(&self) -> QualifiedPath {
+        QualifiedPath::from(self.right.clone())
+    }
+    /// Returns the tip of `feature` when the pair was checked.
+    pub fn get_tip(&self, feature: &QualifiedPath) -> Option<&String> {
+        let feature = feature.to_string();
+        if self.left == feature {
+            Some(&self.left_tip)
+        } else if self.right == feature {
+            Some(&self.right_tip)
+        } else {
+            None
+        }
+    }
+    fn has_same_result(&self, other: &ConflictRecord) -> bool {
+        self.status == other.status
+            && self.get_tip(&other.get_left_path()) == Some(&other.left_tip)
+            && self.get_tip(&other.get_right_path()) == Some(&other.right_tip)
+    }
+}
+
+fn make_key(l: &str, r: &str) -> String {
+    if l <= r {
+        format!("{} {}", l, r)
+    } else {
+        format!("{} {}", r, l)
+    }
+}
+
+/// The latest conflict check result of every checked pair of features, independent of the
+/// order of the pair, and the history of all results.
+///
+/// The history only grows when the status or the tips of a pair changed.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ConflictCache {
+    #[serde(default)]
+    pairs: BTreeMap<String, ConflictRecord>,
+    #[serde(default)]
+    history: Vec<ConflictRecord>,
+}
+
+impl ConflictCache {
+    pub fn from_json(data: &str) -> Result<Self, serde_json::Error> {
+        if data.trim().is_empty() {
+            return Ok(Self::default());
+        }
+        serde_json::from_str(data)
+    }
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+    pub fn get(&self, l: &QualifiedPath, r: &QualifiedPath) -> Option<&ConflictRecord> {
+        self.pairs.get(&make_key(&l.to_string(), &r.to_string()))
+    }
+    /// Stores the record and returns the previous record of the pair.
+    pub fn insert(&mut self, record: ConflictRecord) -> Option<ConflictRecord> {
+        let previous = self.pairs.insert(record.key(), record.clone());
+        if !previous
+            .as_ref()
+            .is_some_and(|p| p.has_same_result(&record))
+        {
+            self.history.push(record);
+        }
+        previous
+    }
+    /// Returns the latest result of the pair that was recorded at or before `timestamp`.
+    pub fn get_at(
+        &self,
+        l: &QualifiedPath,
+        r: &QualifiedPath,
+        timestamp: u64,
+    ) -> Option<&ConflictRecord> {
+        self.get_history(l, r)
+            .filter(|record| record.timestamp <= timestamp)
+            .last()
+    }
+    /// Returns all recorded results of the pair, oldest first.
+    pub fn get_history(
+        &self,
+        l: &QualifiedPath,
+        r: &QualifiedPath,
+    ) -> impl Iterator<Item = &ConflictRecord> {
+        let key = make_key(&l.to_string(), &r.to_string());
+        self.history
+            .iter()
+            .filter(move |record| record.key() == key)
+    }
+    pub fn iter(&self) -> impl Iterator<Item = &ConflictRecord> {
+        self.pairs.values()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conflict_cache_order_independent() {
+        let mut cache = ConflictCache::default();
+        let record = ConflictRecord {
+            left: "main/feature/b".to_string(),
+            right: "main/feature/a".to_string(),
+            status: ConflictStatus::Ok,
+            left_tip: "1".to_string(),
+            right_tip: "2".to_string(),
+            timestamp: 0,
+        };
+        assert!(cache.insert(record.clone()).is_none());
+        let a = QualifiedPath::from("main/feature/a");
+        let b = QualifiedPath::from("main/feature/b");
+        assert_eq!(cache.get(&a, &b), Some(&record));
+        let cache = ConflictCache::from_json(&cache.to_json()).unwrap();
+        assert_eq!(cache.get(&b, &a).unwrap().status, ConflictStatus::Ok);
+        assert!(cache.to_json().contains("\"ok\""));
+    }
+
+    #[test]
+    fn test_conflict_cache_history() {
+        let mut cache = ConflictCache::default();
+        let record = |status, tip: &str, timestamp| ConflictRecord {
+            left: "main/feature/a".to_string(),
+            right: "main/feature/b".to_string(),
+            status,
+            left_tip: tip.to_string(),
+            right_tip: "2".to_string(),
+            timestamp,
+        };
+        cache.insert(record(ConflictStatus::Ok, "1", 10));
+        cache.insert(record(ConflictStatus::Ok, "1", 20));
+        cache.insert(record(ConflictStatus::Conflict, "3", 30));
+        let a = QualifiedPath::from("main/feature/a");
+        let b = QualifiedPath::from("main/feature/b");
+        assert_eq!(cache.get_history(&b, &a).count(), 2);
+        assert_eq!(cache.get_at(&a, &b, 25).unwrap().status, ConflictStatus::Ok);
+        assert_eq!(
+            cache.get_at(&a, &b, 30).unwrap().status,
+            ConflictStatus::Conflict
+        );
+        assert!(cache.get_at(&a, &b, 5).is_none());
+        assert_eq!(cache.get(&a, &b).unwrap().timestamp, 30);
+    }
+}
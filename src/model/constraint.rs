@@ -0,0 +1,319 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+/// A propositional cross-tree constraint over features.
+///
+/// Features are referenced by name. Names that are not plain identifiers, e.g. qualified
+/// paths, are quoted when printed.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Constraint {
+    Feature(String),
+    Not(Box<Constraint>),
+    And(Box<Constraint>, Box<Constraint>),
+    Or(Box<Constraint>, Box<Constraint>),
+    Implies(Box<Constraint>, Box<Constraint>),
+    Equivalent(Box<Constraint>, Box<Constraint>),
+}
+
+impl Constraint {
+    /// Parses a constraint in UVL syntax: `!`, `&`, `|`, `=>`, `<=>` and parentheses.
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let tokens = tokenize(input)?;
+        let mut parser = Parser { tokens, index: 0 };
+        let constraint = parser.parse_equivalent()?;
+        match parser.peek() {
+            None => Ok(constraint),
+            Some(token) => Err(format!("Unexpected '{}' in constraint '{}'", token, input)),
+        }
+    }
+    /// Returns all referenced feature names in order of appearance.
+    pub fn features(&self) -> Vec<&String> {
+        match self {
+            Self::Feature(name) => vec![name],
+            Self::Not(inner) => inner.features(),
+            Self::And(l, r) | Self::Or(l, r) | Self::Implies(l, r) | Self::Equivalent(l, r) => {
+                let mut result = l.features();
+                result.extend(r.features());
+                result
+            }
+        }
+    }
+    /// Replaces every feature name by the result of `f`.
+    pub fn map_features<E, F>(&self, f: &F) -> Result<Self, E>
+    where
+        F: Fn(&str) -> Result<String, E>,
+    {
+        let map = |c: &Constraint| c.map_features(f).map(Box::new);
+        Ok(match self {
+            Self::Feature(name) => Self::Feature(f(name)?),
+            Self::Not(inner) => Self::Not(map(inner)?),
+            Self::And(l, r) => Self::And(map(l)?, map(r)?),
+            Self::Or(l, r) => Self::Or(map(l)?, map(r)?),
+            Self::Implies(l, r) => Self::Implies(map(l)?, map(r)?),
+            Self::Equivalent(l, r) => Self::Equivalent(map(l)?, map(r)?),
+        })
+    }
+    /// Evaluates the constraint for the features selected by `is_selected`.
+    pub fn evaluate<F: Fn(&str) -> bool>(&self, is_selected: &F) -> bool {
+        match self {
+            Self::Feature(name) => is_selected(name),
+            Self::Not(inner) => !inner.evaluate(is_selected),
+            Self::And(l, r) => l.evaluate(is_selected) && r.evaluate(is_selected),
+            Self::Or(l, r) => l.evaluate(is_selected) || r.evaluate(is_selected),
+            Self::Implies(l, r) => !l.evaluate(is_selected) || r.evaluate(is_selected),
+            Self::Equivalent(l, r) => l.evaluate(is_selected) == r.evaluate(is_selected),
+        }
+    }
+    fn precedence(&self) -> u8 {
+        match self {
+            Self::Equivalent(_, _) => 0,
+            Self::Implies(_, _) => 1,
+            Self::Or(_, _) => 2,
+            Self::And(_, _) => 3,
+            Self::Not(_) | Self::Feature(_) => 4,
+        }
+    }
+    fn fmt_operand(&self, operand: &Constraint, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if operand.precedence() <= self.precedence() && operand.precedence() < 4 {
+            write!(f, "({})", operand)
+        } else {
+            write!(f, "{}", operand)
+        }
+    }
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+impl Display for Constraint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let (l, operator, r) = match self {
+            Self::Feature(name) => {
+                return if !name.is_empty() && name.chars().all(is_identifier_char) {
+                    f.write_str(name)
+                } else {
+                    write!(f, "\"{}\"", name)
+                };
+            }
+            Self::Not(inner) => {
+                f.write_str("!")?;
+                return self.fmt_operand(inner, f);
+            }
+            Self::And(l, r) => (l, "&", r),
+            Self::Or(l, r) => (l, "|", r),
+            Self::Implies(l, r) => (l, "=>", r),
+            Self::Equivalent(l, r) => (l, "<=>", r),
+        };
+        self.fmt_operand(l, f)?;
+        write!(f, " {} ", operator)?;
+        // all binary operators are parsed left-associative
+        if r.precedence() == self.precedence() {
+            write!(f, "({})", r)
+        } else {
+            self.fmt_operand(r, f)
+        }
+    }
+}
+
+impl TryFrom<String> for Constraint {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::parse(&value)
+    }
+}
+
+impl From<Constraint> for String {
+    fn from(value: Constraint) -> Self {
+        value.to_string()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Name(String),
+    Not,
+    And,
+    Or,
+    Implies,
+    Equivalent,
+    Open,
+    Close,
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Name(name) => f.write_str(name),
+            Token::Not => f.write_str("!"),
+            Token::And => f.write_str("&"),
+            Token::Or => f.write_str("|"),
+            Token::Implies => f.write_str("=>"),
+            Token::Equivalent => f.write_str("<=>"),
+            Token::Open => f.write_str("("),
+            Token::Close => f.write_str(")"),
+        }
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+    while let Some(c) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '!' => Token::Not,
+            '&' => Token::And,
+            '|' => Token::Or,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            '=' if chars.next_if_eq(&'>').is_some() => Token::Implies,
+            '<' if chars.next_if_eq(&'=').is_some() && chars.next_if_eq(&'>').is_some() => {
+                Token::Equivalent
+            }
+            '"' => {
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some(c) => name.push(c),
+                        None => return Err(format!("Unterminated name in '{}'", input)),
+                    }
+                }
+                Token::Name(name)
+            }
+            c if is_identifier_char(c) => {
+                let mut name = c.to_string();
+                while let Some(c) = chars.next_if(|c| is_identifier_char(*c)) {
+                    name.push(c);
+                }
+                Token::Name(name)
+            }
+            c => {
+                return Err(format!(
+                    "Unsupported character '{}' in constraint '{}'",
+                    c, input
+                ));
+            }
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    index: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.index)
+    }
+    fn accept(&mut self, token: Token) -> bool {
+        if self.peek() == Some(&token) {
+            self.index += 1;
+            true
+        } else {
+            false
+        }
+    }
+    fn parse_binary(
+        &mut self,
+        operator: Token,
+        operand: fn(&mut Self) -> Result<Constraint, String>,
+        build: fn(Box<Constraint>, Box<Constraint>) -> Constraint,
+    ) -> Result<Constraint, String> {
+        let mut left = operand(self)?;
+        while self.accept(operator.clone()) {
+            let right = operand(self)?;
+            left = build(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+    fn parse_equivalent(&mut self) -> Result<Constraint, String> {
+        self.parse_binary(
+            Token::Equivalent,
+            Self::parse_implies,
+            Constraint::Equivalent,
+        )
+    }
+    fn parse_implies(&mut self) -> Result<Constraint, String> {
+        self.parse_binary(Token::Implies, Self::parse_or, Constraint::Implies)
+    }
+    fn parse_or(&mut self) -> Result<Constraint, String> {
+        self.parse_binary(Token::Or, Self::parse_and, Constraint::Or)
+    }
+    fn parse_and(&mut self) -> Result<Constraint, String> {
+        self.parse_binary(Token::And, Self::parse_unary, Constraint::And)
+    }
+    fn parse_unary(&mut self) -> Result<Constraint, String> {
+        if self.accept(Token::Not) {
+            return Ok(Constraint::Not(Box::new(self.parse_unary()?)));
+        }
+        if self.accept(Token::Open) {
+            let inner = self.parse_equivalent()?;
+            if !self.accept(Token::Close) {
+                return Err("Missing ')' in constraint".to_string());
+            }
+            return Ok(inner);
+        }
+        match self.tokens.get(self.index).cloned() {
+            Some(Token::Name(name)) => {
+                self.index += 1;
+                Ok(Constraint::Feature(name))
+            }
+            Some(token) => Err(format!("Expected a feature but found '{}'", token)),
+            None => Err("Unexpected end of constraint".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_constraint_parse_precedence() {
+        let constraint = Constraint::parse("A & !B | C => D <=> \"main/feature/e\"").unwrap();
+        assert_eq!(
+            constraint,
+            Constraint::Equivalent(
+                Box::new(Constraint::Implies(
+                    Box::new(Constraint::Or(
+                        Box::new(Constraint::And(
+                            Box::new(Constraint::Feature("A".to_string())),
+                            Box::new(Constraint::Not(Box::new(Constraint::Feature(
+                                "B".to_string()
+                            )))),
+                        )),
+                        Box::new(Constraint::Feature("C".to_string())),
+                    )),
+                    Box::new(Constraint::Feature("D".to_string())),
+                )),
+                Box::new(Constraint::Feature("main/feature/e".to_string())),
+            )
+        );
+        assert!(Constraint::parse("A & ").is_err());
+        assert!(Constraint::parse("A + B").is_err());
+    }
+
+    #[test]
+    fn test_constraint_display_round_trip() {
+        for input in [
+            "A => !(B | C)",
+            "(A => B) => C",
+            "A => (B => C)",
+            "!\"root/foo\" & (B <=> C)",
+        ] {
+            let constraint = Constraint::parse(input).unwrap();
+            assert_eq!(constraint.to_string(), input);
+            assert_eq!(
+                Constraint::parse(&constraint.to_string()).unwrap(),
+                constraint
+            );
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use crate::model::*;
+
+const DERIVATION_HEADER: &str = "# DO NOT EDIT OR REMOVE THIS COMMIT";
+const DERIVATION_FINISHED: &str = "DERIVATION FINISHED";
+const DERIVATION_UPDATED: &str = "DERIVATION UPDATED";
+
+/// The features a product was derived from, as stored in the derivation commit of the product.
+///
+/// A product can carry multiple records: the initial one created by `derive` and updated
+/// ones created whenever referenced features are renamed. The latest record always wins.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DerivationRecord {
+    features: Vec<QualifiedPath>,
+}
+
+impl DerivationRecord {
+    pub fn new(features: Vec<QualifiedPath>) -> Self {
+        Self { features }
+    }
+    pub fn is_derivation_message(message: &str) -> bool {
+        message.contains(DERIVATION_FINISHED) || message.contains(DERIVATION_UPDATED)
+    }
+    pub fn from_commit_message(message: &str) -> Option<Self> {
+        if !Self::is_derivation_message(message) {
+            return None;
+        }
+        let to_filter = [DERIVATION_HEADER, DERIVATION_FINISHED, DERIVATION_UPDATED];
+        let features = message
+            .trim()
+            .split("\n")
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty() && !to_filter.contains(line))
+            .map(QualifiedPath::from)
+            .collect();
+        Some(Self { features })
+    }
+    /// Returns the latest record of a commit history ordered from newest to oldest.
+    pub fn latest(history: &[Commit]) -> Option<Self> {
+        history
+            .iter()
+            .find_map(|commit| Self::from_commit_message(commit.message()))
+    }
+    pub fn features(&self) -> &Vec<QualifiedPath> {
+        &self.features
+    }
+    pub fn to_commit_message(&self) -> String {
+        self.make_message(DERIVATION_FINISHED)
+    }
+    pub fn to_update_message(&self) -> String {
+        self.make_message(DERIVATION_UPDATED)
+    }
+    fn make_message(&self, marker: &str) -> String {
+        let mut base = format!("{}\n{}\n", DERIVATION_HEADER, marker);
+        let strings = self
+            .features
+            .iter()
+            .map(|f| f.to_string())
+            .collect::<Vec<String>>();
+        base.push_str(strings.join("\n").as_str());
+        base
+    }
+    /// Returns true if the record contains the feature or any feature below it.
+    pub fn references(&self, feature: &QualifiedPath) -> bool {
+        self.features.iter().any(|f| f.has_prefix(feature))
+    }
+    /// Replaces the prefix `from` by `to` in all features of the record.
+    pub fn rename(&self, from: &QualifiedPath, to: &QualifiedPath) -> Self {
+        let features = self
+            .features
+            .iter()
+            .map(|f| {
+                if f.has_prefix(from) {
+                    let mut renamed = to.clone();
+                    for part in f.strip_n_left(from.len()).iter() {
+                        renamed.push(part.clone());
+                    }
+                    renamed
+                } else {
+                    f.clone()
+                }
+            })
+            .collect();
+        Self { features }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prepare_record() -> DerivationRecord {
+        DerivationRecord::new(vec![
+            QualifiedPath::from("main/feature/root/foo"),
+            QualifiedPath::from("main/feature/root/foo/sub"),
+            QualifiedPath::from("main/feature/root/foobar"),
+        ])
+    }
+
+    #[test]
+    fn test_derivation_record_round_trip() {
+        let record = prepare_record();
+        assert_eq!(
+            DerivationRecord::from_commit_message(&record.to_commit_message()),
+            Some(record.clone())
+        );
+        assert_eq!(
+            DerivationRecord::from_commit_message(&record.to_update_message()),
+            Some(record)
+        );
+        assert_eq!(
+            DerivationRecord::from_commit_message("initial commit"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_derivation_record_rename() {
+        let renamed = prepare_record().rename(
+            &QualifiedPath::from("main/feature/root/foo"),
+            &QualifiedPath::from("main/feature/other/baz"),
+        );
+        assert_eq!(
+            renamed.features(),
+            &vec![
+                QualifiedPath::from("main/feature/other/baz"),
+                QualifiedPath::from("main/feature/other/baz/sub"),
+                QualifiedPath::from("main/feature/root/foobar"),
+            ]
+        );
+        assert!(renamed.references(&QualifiedPath::from("main/feature/other")));
+        assert!(!renamed.references(&QualifiedPath::from("main/feature/root/foo")));
+    }
+}
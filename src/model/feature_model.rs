@@ -0,0 +1,657 @@
+use crate::model::*;
+use std::collections::HashMap;
+
+/// A feature of an exchangeable feature model. Abstract features have no implementation,
+/// which corresponds to features without a branch in tangl.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FeatureModelNode {
+    pub name: String,
+    pub is_abstract: bool,
+    pub children: Vec<FeatureModelNode>,
+}
+
+impl FeatureModelNode {
+    pub fn new<S: Into<String>>(name: S, is_abstract: bool) -> Self {
+        Self {
+            name: name.into(),
+            is_abstract,
+            children: Vec::new(),
+        }
+    }
+}
+
+/// The qualified path of a feature and whether it is abstract.
+pub type FeaturePath = (QualifiedPath, bool);
+
+/// A feature model as used by modelling tools like FeatureIDE or UVL.
+///
+/// The root of the model stands for the area and is not a feature itself, its children are
+/// the top-level features. Constraints reference features by their name in the model.
+/// All features are optional, groups of imported models are translated into constraints.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FeatureModel {
+    pub root: FeatureModelNode,
+    pub constraints: Vec<Constraint>,
+}
+
+impl FeatureModel {
+    /// Builds the model of an area. `constraints` reference features by their qualified path.
+    /// Archived features are left out, unless they have features that are not archived below
+    /// them. Then they are kept as abstract features.
+    pub fn from_area(area: &NodePath<Area>, constraints: &[Constraint]) -> Result<Self, String> {
+        // path, whether it is archived and whether it has a branch
+        let mut features: Vec<(QualifiedPath, bool, bool)> = Vec::new();
+        if let Some(feature_root) = area.clone().to_feature_root() {
+            for path in feature_root.iter_children_req() {
+                if let NodePathType::Feature(feature) = path.concretize() {
+                    let metadata = feature.get_metadata();
+                    features.push((
+                        feature.get_qualified_path(),
+                        metadata.get_feature_metadata().get_state() == Some(FeatureState::Archived),
+                        metadata.has_branch(),
+                    ));
+                }
+            }
+        }
+        let paths = features
+            .iter()
+            .filter(|(path, is_archived, _)| {
+                !is_archived
+                    || features.iter().any(|(other, is_archived, _)| {
+                        !is_archived && other != path && other.has_prefix(path)
+                    })
+            })
+            .map(|(path, is_archived, has_branch)| (path.clone(), *is_archived || !has_branch))
+            .collect::<Vec<_>>();
+        let feature_root = area.get_path_to_feature_root();
+        let root_name = area.get_qualified_path().to_string();
+        let mut segment_count: HashMap<String, usize> = HashMap::new();
+        segment_count.insert(root_name.clone(), 1);
+        for (path, _) in paths.iter() {
+            *segment_count
+                .entry(path.last().unwrap().clone())
+                .or_default() += 1;
+        }
+        let name_of = |path: &QualifiedPath| {
+            let segment = path.last().unwrap();
+            if segment_count[segment] == 1 {
+                segment.clone()
+            } else {
+                path.strip_n_left(feature_root.len()).to_string()
+            }
+        };
+        let names = paths
+            .iter()
+            .map(|(path, _)| (path.to_string(), name_of(path)))
+            .collect::<HashMap<_, _>>();
+
+        let mut root = FeatureModelNode::new(root_name, true);
+        for (path, is_abstract) in paths.iter() {
+            let mut parent = &mut root;
+            for depth in feature_root.len() + 1..path.len() {
+                let ancestor = path.strip_n_right(depth);
+                parent = names
+                    .get(&ancestor.to_string())
+                    .and_then(|name| parent.children.iter_mut().find(|child| &child.name == name))
+                    .ok_or(format!("The parent {} of {} is missing", ancestor, path))?;
+            }
+            parent.children.push(FeatureModelNode::new(
+                names[&path.to_string()].clone(),
+                *is_abstract,
+            ));
+        }
+        let constraints = constraints
+            .iter()
+            .map(|constraint| {
+                constraint.map_features(&|feature| match names.get(feature) {
+                    Some(name) => Ok(name.clone()),
+                    None => Err(format!(
+                        "Constraint '{}' references unknown feature {}",
+                        constraint, feature
+                    )),
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { root, constraints })
+    }
+    /// Returns the qualified path of every feature below `feature_root` in pre-order, whether
+    /// it is abstract, and the constraints referencing features by their qualified path.
+    pub fn to_paths(
+        &self,
+        feature_root: &QualifiedPath,
+    ) -> Result<(Vec<FeaturePath>, Vec<Constraint>), String> {
+        let mut paths: Vec<FeaturePath> = Vec::new();
+        let mut names: HashMap<String, QualifiedPath> = HashMap::new();
+        let mut to_visit = self
+            .root
+            .children
+            .iter()
+            .rev()
+            .map(|child| (feature_root.clone(), child))
+            .collect::<Vec<_>>();
+        while let Some((parent, node)) = to_visit.pop() {
+            let segment = node.name.rsplit('/').next().unwrap();
+            let segment = QualifiedPath::from(vec![segment.to_string()]);
+            segment.validate().map_err(|e| e.to_string())?;
+            let path = parent + segment;
+            if names.insert(node.name.clone(), path.clone()).is_some() {
+                return Err(format!("Feature {} is defined twice", node.name));
+            }
+            paths.push((path.clone(), node.is_abstract));
+            to_visit.extend(node.children.iter().rev().map(|c| (path.clone(), c)));
+        }
+        let constraints = self
+            .constraints
+            .iter()
+            .map(|constraint| {
+                constraint.map_features(&|feature| match names.get(feature) {
+                    Some(path) => Ok(path.to_string()),
+                    None if feature == self.root.name => Err(format!(
+                        "Constraint '{}' references the root {}, which is no feature in tangl",
+                        constraint, feature
+                    )),
+                    None => Err(format!(
+                        "Constraint '{}' references unknown feature {}",
+                        constraint, feature
+                    )),
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok((paths, constraints))
+    }
+    pub fn to_uvl(&self) -> String {
+        let mut lines = vec!["features".to_string()];
+        write_uvl_node(&self.root, 1, &mut lines);
+        if !self.constraints.is_empty() {
+            lines.push("constraints".to_string());
+            for constraint in self.constraints.iter() {
+                lines.push(format!("\t{}", constraint));
+            }
+        }
+        lines.join("\n") + "\n"
+    }
+    pub fn from_uvl(input: &str) -> Result<Self, String> {
+        let mut section = "";
+        let mut nodes: Vec<(FeatureModelNode, Option<usize>)> = Vec::new();
+        // indentation, feature and group of each open block
+        let mut stack: Vec<(usize, Option<usize>, Option<usize>)> = Vec::new();
+        let mut groups: Vec<(String, usize, Vec<usize>)> = Vec::new();
+        let mut constraints = Vec::new();
+        for line in input.lines() {
+            let line = match line.find("//") {
+                Some(index) => &line[..index],
+                None => line,
+            };
+            let content = line.trim();
+            if content.is_empty() {
+                continue;
+            }
+            let indent = line.len() - line.trim_start().len();
+            if indent == 0 {
+                section = content.split_whitespace().next().unwrap();
+                continue;
+            }
+            match section {
+                "features" => {
+                    stack.retain(|(i, _, _)| *i < indent);
+                    let parent = stack.iter().rev().find_map(|(_, node, _)| *node);
+                    if is_uvl_group(content) {
+                        let parent =
+                            parent.ok_or(format!("Group without parent feature: {}", content))?;
+                        groups.push((content.to_string(), parent, Vec::new()));
+                        stack.push((indent, None, Some(groups.len() - 1)));
+                        continue;
+                    }
+                    if parent.is_none() && !nodes.is_empty() {
+                        return Err(format!("Unexpected second root feature: {}", content));
+                    }
+                    nodes.push((parse_uvl_feature(content)?, parent));
+                    if let Some(group) = stack.last().and_then(|(_, _, group)| *group) {
+                        groups[group].2.push(nodes.len() - 1);
+                    }
+                    stack.push((indent, Some(nodes.len() - 1), None));
+                }
+                "constraints" => constraints.push(Constraint::parse(content)?),
+                _ => {}
+            }
+        }
+        if nodes.is_empty() {
+            return Err("The model does not contain any features".to_string());
+        }
+        for (kind, parent, children) in groups {
+            // the root is always selected
+            let parent = Some(&nodes[parent].0.name).filter(|_| parent != 0);
+            let children = children
+                .iter()
+                .map(|child| nodes[*child].0.name.clone())
+                .collect::<Vec<_>>();
+            constraints.extend(group_constraints(&kind, parent, &children)?);
+        }
+        Ok(Self {
+            root: assemble(&nodes, 0),
+            constraints,
+        })
+    }
+    pub fn to_featureide_xml(&self) -> String {
+        let mut lines = vec![
+            "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"no\"?>".to_string(),
+            "<featureModel>".to_string(),
+            "\t<struct>".to_string(),
+        ];
+        write_xml_node(&self.root, 2, &mut lines);
+        lines.push("\t</struct>".to_string());
+        if !self.constraints.is_empty() {
+            lines.push("\t<constraints>".to_string());
+            for constraint in self.constraints.iter() {
+                lines.push("\t\t<rule>".to_string());
+                write_xml_constraint(constraint, 3, &mut lines);
+                lines.push("\t\t</rule>".to_string());
+            }
+            lines.push("\t</constraints>".to_string());
+        }
+        lines.push("</featureModel>".to_string());
+        lines.join("\n") + "\n"
+    }
+    pub fn from_featureide_xml(input: &str) -> Result<Self, String> {
+        let document = roxmltree::Document::parse(input).map_err(|e| e.to_string())?;
+        let model = document.root_element();
+        let root = model
+            .children()
+            .find(|node| node.has_tag_name("struct"))
+            .and_then(|node| node.children().find(is_xml_feature))
+            .ok_or("The model does not contain any features")?;
+        let mut constraints = Vec::new();
+        if let Some(node) = model.children().find(|n| n.has_tag_name("constraints")) {
+            for rule in node.children().filter(|n| n.has_tag_name("rule")) {
+                match rule.children().find(|n| n.is_element()) {
+                    Some(expression) => constraints.push(read_xml_constraint(expression)?),
+                    None => return Err("Empty constraint rule".to_string()),
+                }
+            }
+        }
+        let root = read_xml_node(root, true, &mut constraints)?;
+        Ok(Self { root, constraints })
+    }
+}
+
+fn format_uvl_name(name: &str) -> String {
+    Constraint::Feature(name.to_string()).to_string()
+}
+
+fn write_uvl_node(node: &FeatureModelNode, depth: usize, lines: &mut Vec<String>) {
+    let indent = "\t".repeat(depth);
+    let attributes = if node.is_abstract { " {abstract}" } else { "" };
+    lines.push(format!(
+        "{}{}{}",
+        indent,
+        format_uvl_name(&node.name),
+        attributes
+    ));
+    if !node.children.is_empty() {
+        lines.push(format!("{}\toptional", indent));
+        for child in node.children.iter() {
+            write_uvl_node(child, depth + 2, lines);
+        }
+    }
+}
+
+/// Translates a group of the children of `parent` into constraints. `parent` is `None` for the
+/// root of the model, which is always selected. Groups are `optional`, `mandatory`, `or`,
+/// `alternative` or a cardinality like `[1..*]`.
+fn group_constraints(
+    kind: &str,
+    parent: Option<&String>,
+    children: &[String],
+) -> Result<Vec<Constraint>, String> {
+    let feature = |name: &String| Box::new(Constraint::Feature(name.clone()));
+    let required = |constraint: Constraint| match parent {
+        Some(parent) => Constraint::Implies(feature(parent), Box::new(constraint)),
+        None => constraint,
+    };
+    let any = || {
+        children
+            .iter()
+            .map(|child| Constraint::Feature(child.clone()))
+            .reduce(|l, r| Constraint::Or(Box::new(l), Box::new(r)))
+    };
+    let mut exclusions = Vec::new();
+    for (i, l) in children.iter().enumerate() {
+        for r in children[i + 1..].iter() {
+            exclusions.push(Constraint::Not(Box::new(Constraint::And(
+                feature(l),
+                feature(r),
+            ))));
+        }
+    }
+    let (min, max) = match kind {
+        "optional" => (0, None),
+        "mandatory" => {
+            return Ok(children
+                .iter()
+                .map(|child| required(Constraint::Feature(child.clone())))
+                .collect());
+        }
+        "or" => (1, None),
+        "alternative" => (1, Some(1)),
+        cardinality => parse_uvl_cardinality(cardinality)?,
+    };
+    let mut constraints = Vec::new();
+    match (min, any()) {
+        (0, _) | (_, None) => {}
+        (1, Some(any)) => constraints.push(required(any)),
+        _ => return Err(format!("Group cardinality {} is not supported", kind)),
+    }
+    match max {
+        Some(max) if max >= children.len() => {}
+        None => {}
+        Some(1) => constraints.extend(exclusions),
+        Some(_) => return Err(format!("Group cardinality {} is not supported", kind)),
+    }
+    Ok(constraints)
+}
+
+/// Parses a group cardinality `[n]`, `[n..m]` or `[n..*]` into its bounds.
+fn parse_uvl_cardinality(cardinality: &str) -> Result<(usize, Option<usize>), String> {
+    let invalid = || format!("Invalid group cardinality: {}", cardinality);
+    let bounds = cardinality
+        .strip_prefix('[')
+        .and_then(|c| c.strip_suffix(']'))
+        .ok_or_else(invalid)?;
+    let (min, max) = bounds.split_once("..").unwrap_or((bounds, bounds));
+    let min = min.trim().parse().map_err(|_| invalid())?;
+    let max = match max.trim() {
+        "*" => None,
+        max => Some(max.parse().map_err(|_| invalid())?),
+    };
+    Ok((min, max))
+}
+
+fn is_uvl_group(content: &str) -> bool {
+    ["optional", "mandatory", "or", "alternative"].contains(&content) || content.starts_with('[')
+}
+
+fn parse_uvl_feature(content: &str) -> Result<FeatureModelNode, String> {
+    let (name, rest) = if let Some(quoted) = content.strip_prefix('"') {
+        match quoted.split_once('"') {
+            Some((name, rest)) => (name.to_string(), rest),
+            None => return Err(format!("Unterminated feature name: {}", content)),
+        }
+    } else {
+        let end = content
+            .find(|c: char| c.is_whitespace() || c == '{')
+            .unwrap_or(content.len());
+        (content[..end].to_string(), &content[end..])
+    };
+    let is_abstract = match (rest.find('{'), rest.rfind('}')) {
+        (Some(start), Some(end)) if start < end => rest[start + 1..end]
+            .split(',')
+            .map(|attribute| attribute.split_whitespace().collect::<Vec<_>>())
+            .any(|attribute| attribute == ["abstract"] || attribute == ["abstract", "true"]),
+        _ => false,
+    };
+    Ok(FeatureModelNode::new(name, is_abstract))
+}
+
+fn assemble(nodes: &[(FeatureModelNode, Option<usize>)], index: usize) -> FeatureModelNode {
+    let mut node = nodes[index].0.clone();
+    node.children = nodes
+        .iter()
+        .enumerate()
+        .filter(|(_, (_, parent))| *parent == Some(index))
+        .map(|(child, _)| assemble(nodes, child))
+        .collect();
+    node
+}
+
+fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn write_xml_node(node: &FeatureModelNode, depth: usize, lines: &mut Vec<String>) {
+    let indent = "\t".repeat(depth);
+    let abstract_attribute = if node.is_abstract {
+        " abstract=\"true\""
+    } else {
+        ""
+    };
+    let name = escape_xml(&node.name);
+    if node.children.is_empty() {
+        lines.push(format!(
+            "{}<feature{} name=\"{}\"/>",
+            indent, abstract_attribute, name
+        ));
+        return;
+    }
+    lines.push(format!(
+        "{}<and{} name=\"{}\">",
+        indent, abstract_attribute, name
+    ));
+    for child in node.children.iter() {
+        write_xml_node(child, depth + 1, lines);
+    }
+    lines.push(format!("{}</and>", indent));
+}
+
+fn write_xml_constraint(constraint: &Constraint, depth: usize, lines: &mut Vec<String>) {
+    let indent = "\t".repeat(depth);
+    let (tag, operands) = match constraint {
+        Constraint::Feature(name) => {
+            lines.push(format!("{}<var>{}</var>", indent, escape_xml(name)));
+            return;
+        }
+        Constraint::Not(inner) => ("not", vec![inner]),
+        Constraint::And(l, r) => ("conj", vec![l, r]),
+        Constraint::Or(l, r) => ("disj", vec![l, r]),
+        Constraint::Implies(l, r) => ("imp", vec![l, r]),
+        Constraint::Equivalent(l, r) => ("eq", vec![l, r]),
+    };
+    lines.push(format!("{}<{}>", indent, tag));
+    for operand in operands {
+        write_xml_constraint(operand, depth + 1, lines);
+    }
+    lines.push(format!("{}</{}>", indent, tag));
+}
+
+fn is_xml_feature(node: &roxmltree::Node) -> bool {
+    ["feature", "and", "or", "alt"].contains(&node.tag_name().name())
+}
+
+/// Reads a feature and its children. The groups are added to `constraints`, see
+/// [`group_constraints`].
+fn read_xml_node(
+    node: roxmltree::Node,
+    is_root: bool,
+    constraints: &mut Vec<Constraint>,
+) -> Result<FeatureModelNode, String> {
+    let name = node
+        .attribute("name")
+        .ok_or(format!("Feature without name in line {}", node_line(&node)))?;
+    let mut result = FeatureModelNode::new(name, node.attribute("abstract") == Some("true"));
+    let children = node.children().filter(is_xml_feature).collect::<Vec<_>>();
+    for child in children.iter() {
+        result
+            .children
+            .push(read_xml_node(*child, false, constraints)?);
+    }
+    let parent = Some(&result.name).filter(|_| !is_root);
+    let names = result
+        .children
+        .iter()
+        .map(|child| child.name.clone())
+        .collect::<Vec<_>>();
+    match node.tag_name().name() {
+        "alt" => constraints.extend(group_constraints("alternative", parent, &names)?),
+        "or" => constraints.extend(group_constraints("or", parent, &names)?),
+        _ => {
+            for (child, name) in children.iter().zip(names) {
+                if child.attribute("mandatory") == Some("true") {
+                    constraints.extend(group_constraints("mandatory", parent, &[name])?);
+                }
+            }
+        }
+    }
+    Ok(result)
+}
+
+fn read_xml_constraint(node: roxmltree::Node) -> Result<Constraint, String> {
+    let operands = node
+        .children()
+        .filter(|n| n.is_element())
+        .map(read_xml_constraint)
+        .collect::<Result<Vec<_>, _>>()?;
+    let fold = |build: fn(Box<Constraint>, Box<Constraint>) -> Constraint| {
+        operands
+            .iter()
+            .cloned()
+            .reduce(|l, r| build(Box::new(l), Box::new(r)))
+            .ok_or(format!(
+                "Empty <{}> in line {}",
+                node.tag_name().name(),
+                node_line(&node)
+            ))
+    };
+    match (node.tag_name().name(), operands.len()) {
+        ("var", 0) => Ok(Constraint::Feature(
+            node.text().unwrap_or("").trim().to_string(),
+        )),
+        ("not", 1) => Ok(Constraint::Not(Box::new(operands[0].clone()))),
+        ("conj", _) => fold(Constraint::And),
+        ("disj", _) => fold(Constraint::Or),
+        ("imp", 2) => fold(Constraint::Implies),
+        ("eq", 2) => fold(Constraint::Equivalent),
+        (tag, _) => Err(format!(
+            "Unsupported constraint element <{}> in line {}",
+            tag,
+            node_line(&node)
+        )),
+    }
+}
+
+fn node_line(node: &roxmltree::Node) -> u32 {
+    node.document().text_pos_at(node.range().start).row
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prepare_model() -> FeatureModel {
+        let mut root = FeatureModelNode::new("main", true);
+        let mut base = FeatureModelNode::new("base", false);
+        base.children.push(FeatureModelNode::new("foo", false));
+        base.children.push(FeatureModelNode::new("bar", false));
+        let mut other = FeatureModelNode::new("other", true);
+        other
+            .children
+            .push(FeatureModelNode::new("other/foo", false));
+        root.children.push(base);
+        root.children.push(other);
+        FeatureModel {
+            root,
+            constraints: vec![Constraint::parse("foo => !\"other/foo\" | bar").unwrap()],
+        }
+    }
+
+    #[test]
+    fn test_uvl_round_trip() {
+        let model = prepare_model();
+        let uvl = model.to_uvl();
+        assert!(uvl.contains("\tmain {abstract}\n\t\toptional\n\t\t\tbase\n"));
+        assert_eq!(FeatureModel::from_uvl(&uvl).unwrap(), model);
+    }
+
+    #[test]
+    fn test_uvl_import() {
+        let uvl = "namespace Car\n\nfeatures\n    Car {abstract}\n        mandatory\n            Engine {abstract true}\n                alternative\n                    Electric\n                    Gas\n        optional\n            Radio // comment\nconstraints\n    Radio => Electric\n";
+        let model = FeatureModel::from_uvl(uvl).unwrap();
+        assert_eq!(model.root.name, "Car");
+        assert_eq!(model.root.children.len(), 2);
+        assert!(model.root.children[0].is_abstract);
+        assert_eq!(model.root.children[0].children[1].name, "Gas");
+        let (paths, constraints) = model
+            .to_paths(&QualifiedPath::from("main/feature"))
+            .unwrap();
+        assert_eq!(
+            paths,
+            vec![
+                (QualifiedPath::from("main/feature/Engine"), true),
+                (QualifiedPath::from("main/feature/Engine/Electric"), false),
+                (QualifiedPath::from("main/feature/Engine/Gas"), false),
+                (QualifiedPath::from("main/feature/Radio"), false),
+            ]
+        );
+        let constraints = constraints
+            .iter()
+            .map(|constraint| constraint.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            constraints,
+            vec![
+                "\"main/feature/Radio\" => \"main/feature/Engine/Electric\"",
+                "\"main/feature/Engine\"",
+                "\"main/feature/Engine\" => \"main/feature/Engine/Electric\" | \"main/feature/Engine/Gas\"",
+                "!(\"main/feature/Engine/Electric\" & \"main/feature/Engine/Gas\")",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_featureide_groups() {
+        let xml = "<featureModel><struct><and abstract=\"true\" mandatory=\"true\" name=\"Car\"><or name=\"Radio\"><feature name=\"FM\"/><feature name=\"DAB\"/></or><and mandatory=\"true\" name=\"Engine\"><feature name=\"Turbo\"/></and></and></struct></featureModel>";
+        let model = FeatureModel::from_featureide_xml(xml).unwrap();
+        let constraints = model
+            .constraints
+            .iter()
+            .map(|constraint| constraint.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(constraints, vec!["Radio => FM | DAB", "Engine"]);
+    }
+
+    #[test]
+    fn test_uvl_cardinality() {
+        let uvl = "features\n\tRoot\n\t\t[0..1]\n\t\t\ta\n\t\t\tb\n\t\t[1..*]\n\t\t\tc\n";
+        let model = FeatureModel::from_uvl(uvl).unwrap();
+        let constraints = model
+            .constraints
+            .iter()
+            .map(|constraint| constraint.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(constraints, vec!["!(a & b)", "c"]);
+        let unsupported = "features\n\tRoot\n\t\t[2..2]\n\t\t\ta\n\t\t\tb\n\t\t\tc\n";
+        assert!(FeatureModel::from_uvl(unsupported).is_err());
+    }
+
+    #[test]
+    fn test_featureide_round_trip() {
+        let model = prepare_model();
+        let xml = model.to_featureide_xml();
+        assert!(xml.contains("<and abstract=\"true\" name=\"main\">"));
+        assert!(xml.contains("<imp>"));
+        assert_eq!(FeatureModel::from_featureide_xml(&xml).unwrap(), model);
+    }
+
+    #[test]
+    fn test_import_rejects_invalid_names() {
+        let model =
+            FeatureModel::from_uvl("features\n\tRoot\n\t\toptional\n\t\t\t\"..\"\n").unwrap();
+        assert!(
+            model
+                .to_paths(&QualifiedPath::from("main/feature"))
+                .is_err()
+        );
+        // the naming scheme escapes characters like '_' in branch names
+        let model =
+            FeatureModel::from_uvl("features\n\tRoot\n\t\toptional\n\t\t\tmy_feature\n").unwrap();
+        let (paths, _) = model
+            .to_paths(&QualifiedPath::from("main/feature"))
+            .unwrap();
+        assert_eq!(
+            paths,
+            vec![(QualifiedPath::from("main/feature/my_feature"), false)]
+        );
+    }
+}
@@ -0,0 +1,473 @@
+use crate::model::*;
+use std::collections::HashMap;
+
+type Clause = Vec<i32>;
+type ClauseGroup = (Reason, Vec<Clause>);
+/// Builds the clauses defining `t <=> a op b`.
+type Definition = fn(i32, i32, i32) -> Vec<Clause>;
+
+/// Why a group of clauses is part of the formula.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Reason {
+    Selected(QualifiedPath),
+    Parent(QualifiedPath, QualifiedPath),
+    Constraint(Constraint),
+}
+
+impl Reason {
+    /// Formats the reason with feature names relative to the feature root.
+    pub fn to_relative_string(&self, feature_root: &QualifiedPath) -> String {
+        let relative = |path: &QualifiedPath| path.strip_n_left(feature_root.len()).to_string();
+        let relative_name = |name: &String| relative(&QualifiedPath::from(name.clone()));
+        match self {
+            Self::Selected(feature) => format!("{} is selected", relative(feature)),
+            Self::Parent(child, parent) => {
+                format!(
+                    "{} requires its parent {}",
+                    relative(child),
+                    relative(parent)
+                )
+            }
+            Self::Constraint(constraint) => match constraint {
+                Constraint::Implies(l, r) => match (l.as_ref(), r.as_ref()) {
+                    (Constraint::Feature(l), Constraint::Feature(r)) => {
+                        format!("{} requires {}", relative_name(l), relative_name(r))
+                    }
+                    (Constraint::Feature(l), Constraint::Not(r)) => match r.as_ref() {
+                        Constraint::Feature(r) => {
+                            format!("{} excludes {}", relative_name(l), relative_name(r))
+                        }
+                        _ => self.format_constraint(feature_root),
+                    },
+                    _ => self.format_constraint(feature_root),
+                },
+                _ => self.format_constraint(feature_root),
+            },
+        }
+    }
+    fn format_constraint(&self, feature_root: &QualifiedPath) -> String {
+        match self {
+            Self::Constraint(constraint) => {
+                let relative = constraint.map_features(&|f| {
+                    Ok::<String, ()>(
+                        QualifiedPath::from(f.to_string())
+                            .strip_n_left(feature_root.len())
+                            .to_string(),
+                    )
+                });
+                format!("constraint {}", relative.unwrap())
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// The propositional formula of the feature model of an area.
+///
+/// Every feature is a variable, every feature requires its parent and every cross-tree
+/// constraint is added in Tseitin encoding, which keeps the number of solutions intact.
+#[derive(Clone, Debug)]
+pub struct FeatureModelFormula {
+    features: Vec<QualifiedPath>,
+    index: HashMap<QualifiedPath, i32>,
+    n_variables: usize,
+    groups: Vec<ClauseGroup>,
+}
+
+impl FeatureModelFormula {
+    pub fn from_area(area: &NodePath<Area>, constraints: &[Constraint]) -> Self {
+        let mut features = Vec::new();
+        if let Some(feature_root) = area.clone().to_feature_root() {
+            for path in feature_root.iter_children_req() {
+                if let NodePathType::Feature(feature) = path.concretize() {
+                    features.push(feature.get_qualified_path());
+                }
+            }
+        }
+        Self::new(features, &area.get_path_to_feature_root(), constraints)
+    }
+    pub fn new(
+        features: Vec<QualifiedPath>,
+        feature_root: &QualifiedPath,
+        constraints: &[Constraint],
+    ) -> Self {
+        let mut formula = Self {
+            features: Vec::new(),
+            index: HashMap::new(),
+            n_variables: 0,
+            groups: Vec::new(),
+        };
+        for feature in features.iter() {
+            formula.variable(feature);
+        }
+        // features only referenced by constraints, e.g. deleted ones, are variables as well
+        for constraint in constraints.iter() {
+            for feature in constraint.features() {
+                formula.variable(&QualifiedPath::from(feature.clone()));
+            }
+        }
+        for feature in formula.features.clone() {
+            if feature.len() > feature_root.len() + 1 {
+                let parent = feature.strip_n_right(feature.len() - 1);
+                let clause = vec![-formula.variable(&feature), formula.variable(&parent)];
+                formula
+                    .groups
+                    .push((Reason::Parent(feature, parent), vec![clause]));
+            }
+        }
+        for constraint in constraints.iter() {
+            let mut clauses = Vec::new();
+            let literal = formula.encode(constraint, &mut clauses);
+            clauses.push(vec![literal]);
+            formula
+                .groups
+                .push((Reason::Constraint(constraint.clone()), clauses));
+        }
+        formula
+    }
+    fn variable(&mut self, feature: &QualifiedPath) -> i32 {
+        if let Some(variable) = self.index.get(feature) {
+            return *variable;
+        }
+        self.features.push(feature.clone());
+        let variable = self.new_variable();
+        self.index.insert(feature.clone(), variable);
+        variable
+    }
+    fn new_variable(&mut self) -> i32 {
+        self.n_variables += 1;
+        self.n_variables as i32
+    }
+    fn encode(&mut self, constraint: &Constraint, clauses: &mut Vec<Clause>) -> i32 {
+        let (l, r, build): (_, _, Definition) = match constraint {
+            Constraint::Feature(name) => return self.variable(&QualifiedPath::from(name.clone())),
+            Constraint::Not(inner) => return -self.encode(inner, clauses),
+            Constraint::And(l, r) => (l, r, |t, a, b| {
+                vec![vec![-t, a], vec![-t, b], vec![t, -a, -b]]
+            }),
+            Constraint::Or(l, r) => (l, r, |t, a, b| {
+                vec![vec![-t, a, b], vec![t, -a], vec![t, -b]]
+            }),
+            Constraint::Implies(l, r) => (l, r, |t, a, b| {
+                vec![vec![-t, -a, b], vec![t, a], vec![t, -b]]
+            }),
+            Constraint::Equivalent(l, r) => (l, r, |t, a, b| {
+                vec![
+                    vec![-t, -a, b],
+                    vec![-t, a, -b],
+                    vec![t, a, b],
+                    vec![t, -a, -b],
+                ]
+            }),
+        };
+        let a = self.encode(l, clauses);
+        let b = self.encode(r, clauses);
+        let t = self.new_variable();
+        clauses.extend(build(t, a, b));
+        t
+    }
+    pub fn get_features(&self) -> &Vec<QualifiedPath> {
+        &self.features
+    }
+    fn selection_groups(&self, selected: &[QualifiedPath]) -> Vec<ClauseGroup> {
+        selected
+            .iter()
+            .map(|feature| {
+                let clause = match self.index.get(feature) {
+                    Some(variable) => vec![*variable],
+                    // a feature unknown to the model cannot be selected
+                    None => vec![],
+                };
+                (Reason::Selected(feature.clone()), vec![clause])
+            })
+            .collect()
+    }
+    fn solve_groups(&self, groups: &[&ClauseGroup], extra: &[Clause]) -> bool {
+        let clauses = groups
+            .iter()
+            .flat_map(|(_, clauses)| clauses.iter().cloned())
+            .chain(extra.iter().cloned())
+            .collect::<Vec<_>>();
+        let mut assignment = vec![None; self.n_variables + 1];
+        solve(&clauses, &mut assignment)
+    }
+    /// Returns true if the selected features can be extended to a valid configuration.
+    pub fn is_satisfiable(&self, selected: &[QualifiedPath]) -> bool {
+        let selection = self.selection_groups(selected);
+        let groups = self
+            .groups
+            .iter()
+            .chain(selection.iter())
+            .collect::<Vec<_>>();
+        self.solve_groups(&groups, &[])
+    }
+    /// Returns the selected features of a valid configuration in which every feature of
+    /// `assumptions` has the given value, if there is any.
+    pub fn find_configuration(
+        &self,
+        assumptions: &[(QualifiedPath, bool)],
+    ) -> Option<Vec<QualifiedPath>> {
+        let mut clauses = self
+            .groups
+            .iter()
+            .flat_map(|(_, clauses)| clauses.iter().cloned())
+            .collect::<Vec<_>>();
+        for (feature, value) in assumptions.iter() {
+            match self.index.get(feature) {
+                Some(variable) if *value => clauses.push(vec![*variable]),
+                Some(variable) => clauses.push(vec![-variable]),
+                None if *value => return None,
+                None => {}
+            }
+        }
+        let mut assignment = vec![None; self.n_variables + 1];
+        if !solve(&clauses, &mut assignment) {
+            return None;
+        }
+        // unassigned variables do not occur in any unsatisfied clause and can be deselected
+        Some(
+            self.features
+                .iter()
+                .filter(|feature| assignment[self.index[*feature] as usize] == Some(true))
+                .cloned()
+                .collect(),
+        )
+    }
+    /// Returns a minimal set of reasons why the selection cannot be extended to a valid
+    /// configuration. Removing any of them makes the selection satisfiable.
+    pub fn explain(&self, selected: &[QualifiedPath]) -> Vec<Reason> {
+        let selection = self.selection_groups(selected);
+        let mut core = self
+            .groups
+            .iter()
+            .chain(selection.iter())
+            .collect::<Vec<_>>();
+        let mut i = 0;
+        while i < core.len() {
+            let mut candidate = core.clone();
+            candidate.remove(i);
+            if self.solve_groups(&candidate, &[]) {
+                i += 1;
+            } else {
+                core = candidate;
+            }
+        }
+        core.into_iter().map(|(reason, _)| reason.clone()).collect()
+    }
+    /// Returns all features that are not selected but contained in every valid configuration
+    /// including the selection.
+    pub fn implied(&self, selected: &[QualifiedPath]) -> Vec<QualifiedPath> {
+        let selection = self.selection_groups(selected);
+        let groups = self
+            .groups
+            .iter()
+            .chain(selection.iter())
+            .collect::<Vec<_>>();
+        self.features
+            .iter()
+            .filter(|feature| !selected.contains(feature))
+            .filter(|feature| !self.solve_groups(&groups, &[vec![-self.index[*feature]]]))
+            .cloned()
+            .collect()
+    }
+    /// Returns all constraints violated if exactly the selected features are part of the
+    /// configuration.
+    pub fn violated(&self, selected: &[QualifiedPath]) -> Vec<Reason> {
+        let is_selected = |feature: &QualifiedPath| selected.contains(feature);
+        self.groups
+            .iter()
+            .map(|(reason, _)| reason)
+            .filter(|reason| match reason {
+                Reason::Parent(child, parent) => is_selected(child) && !is_selected(parent),
+                Reason::Constraint(constraint) => !constraint
+                    .evaluate(&|name| is_selected(&QualifiedPath::from(name.to_string()))),
+                Reason::Selected(_) => false,
+            })
+            .cloned()
+            .collect()
+    }
+    /// Counts the valid configurations. Returns None if the number exceeds `u128`.
+    pub fn count(&self) -> Option<u128> {
+        let clauses = self
+            .groups
+            .iter()
+            .flat_map(|(_, clauses)| clauses.iter().cloned())
+            .collect::<Vec<_>>();
+        let mut assignment = vec![None; self.n_variables + 1];
+        count(&clauses, &mut assignment)
+    }
+}
+
+fn literal_value(literal: i32, assignment: &[Option<bool>]) -> Option<bool> {
+    assignment[literal.unsigned_abs() as usize].map(|value| value == (literal > 0))
+}
+
+/// Assigns all unit literals. Returns false on a conflict.
+fn propagate(clauses: &[Clause], assignment: &mut [Option<bool>]) -> bool {
+    loop {
+        let mut changed = false;
+        for clause in clauses.iter() {
+            let mut unassigned = None;
+            let mut n_unassigned = 0;
+            let mut satisfied = false;
+            for literal in clause.iter() {
+                match literal_value(*literal, assignment) {
+                    Some(true) => {
+                        satisfied = true;
+                        break;
+                    }
+                    Some(false) => {}
+                    None => {
+                        n_unassigned += 1;
+                        unassigned = Some(*literal);
+                    }
+                }
+            }
+            if satisfied {
+                continue;
+            }
+            match (n_unassigned, unassigned) {
+                (0, _) => return false,
+                (1, Some(literal)) => {
+                    assignment[literal.unsigned_abs() as usize] = Some(literal > 0);
+                    changed = true;
+                }
+                _ => {}
+            }
+        }
+        if !changed {
+            return true;
+        }
+    }
+}
+
+/// Returns an unassigned literal of the first clause that is not satisfied yet.
+fn choose_literal(clauses: &[Clause], assignment: &[Option<bool>]) -> Option<i32> {
+    clauses
+        .iter()
+        .filter(|clause| {
+            !clause
+                .iter()
+                .any(|literal| literal_value(*literal, assignment) == Some(true))
+        })
+        .find_map(|clause| {
+            clause
+                .iter()
+                .find(|literal| literal_value(**literal, assignment).is_none())
+                .copied()
+        })
+}
+
+fn solve(clauses: &[Clause], assignment: &mut Vec<Option<bool>>) -> bool {
+    if !propagate(clauses, assignment) {
+        return false;
+    }
+    let literal = match choose_literal(clauses, assignment) {
+        Some(literal) => literal,
+        None => return true,
+    };
+    for value in [literal > 0, literal < 0] {
+        let mut branch = assignment.clone();
+        branch[literal.unsigned_abs() as usize] = Some(value);
+        if solve(clauses, &mut branch) {
+            *assignment = branch;
+            return true;
+        }
+    }
+    false
+}
+
+fn count(clauses: &[Clause], assignment: &mut [Option<bool>]) -> Option<u128> {
+    if !propagate(clauses, assignment) {
+        return Some(0);
+    }
+    let literal = match choose_literal(clauses, assignment) {
+        Some(literal) => literal,
+        None => {
+            // every clause is satisfied, all remaining variables are free
+            let free = assignment.iter().skip(1).filter(|v| v.is_none()).count();
+            return 1u128.checked_shl(free as u32);
+        }
+    };
+    let mut result: u128 = 0;
+    for value in [true, false] {
+        let mut branch = assignment.to_vec();
+        branch[literal.unsigned_abs() as usize] = Some(value);
+        result = result.checked_add(count(clauses, &mut branch)?)?;
+    }
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prepare_formula(constraints: &[&str]) -> FeatureModelFormula {
+        let features = ["root", "root/foo", "root/bar", "root/baz"]
+            .iter()
+            .map(|f| QualifiedPath::from("main/feature") + QualifiedPath::from(*f))
+            .collect::<Vec<_>>();
+        let constraints = constraints
+            .iter()
+            .map(|c| Constraint::parse(c).unwrap())
+            .collect::<Vec<_>>();
+        FeatureModelFormula::new(features, &QualifiedPath::from("main/feature"), &constraints)
+    }
+
+    fn paths(features: &[&str]) -> Vec<QualifiedPath> {
+        features
+            .iter()
+            .map(|f| QualifiedPath::from("main/feature") + QualifiedPath::from(*f))
+            .collect()
+    }
+
+    #[test]
+    fn test_formula_count() {
+        // root is optional, its three children are optional below it
+        assert_eq!(prepare_formula(&[]).count(), Some(9));
+        let formula = prepare_formula(&["\"main/feature/root/bar\" => !\"main/feature/root/baz\""]);
+        assert_eq!(formula.count(), Some(7));
+        let formula = prepare_formula(&[
+            "\"main/feature/root/foo\" <=> \"main/feature/root/bar\"",
+            "\"main/feature/root\"",
+        ]);
+        assert_eq!(formula.count(), Some(4));
+    }
+
+    #[test]
+    fn test_formula_explain() {
+        let formula = prepare_formula(&[
+            "\"main/feature/root/foo\" => \"main/feature/root/baz\"",
+            "\"main/feature/root/bar\" => !\"main/feature/root/baz\"",
+        ]);
+        let selection = paths(&["root/bar", "root/baz"]);
+        assert!(!formula.is_satisfiable(&selection));
+        let core = formula
+            .explain(&selection)
+            .iter()
+            .map(|reason| reason.to_relative_string(&QualifiedPath::from("main/feature")))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            core,
+            vec![
+                "root/bar excludes root/baz",
+                "root/bar is selected",
+                "root/baz is selected"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_formula_implied_and_violated() {
+        let formula = prepare_formula(&["\"main/feature/root/foo\" => \"main/feature/root/baz\""]);
+        let selection = paths(&["root", "root/foo"]);
+        assert!(formula.is_satisfiable(&selection));
+        assert_eq!(formula.implied(&selection), paths(&["root/baz"]));
+        assert_eq!(formula.violated(&selection).len(), 1);
+        assert!(
+            formula
+                .violated(&paths(&["root", "root/foo", "root/baz"]))
+                .is_empty()
+        );
+    }
+}
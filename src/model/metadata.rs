@@ -0,0 +1,245 @@
+use crate::model::*;
+use colored::{ColoredString, Colorize};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FeatureState {
+    Experimental,
+    Stable,
+    Deprecated,
+    Archived,
+}
+
+impl FeatureState {
+    pub fn format_state_display(&self) -> ColoredString {
+        let label = format!("[{}]", self);
+        match self {
+            Self::Experimental => label.yellow(),
+            Self::Stable => label.green(),
+            Self::Deprecated => label.red(),
+            Self::Archived => label.dimmed(),
+        }
+    }
+}
+
+impl Display for FeatureState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Experimental => "experimental",
+            Self::Stable => "stable",
+            Self::Deprecated => "deprecated",
+            Self::Archived => "archived",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for FeatureState {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "experimental" => Ok(Self::Experimental),
+            "stable" => Ok(Self::Stable),
+            "deprecated" => Ok(Self::Deprecated),
+            "archived" => Ok(Self::Archived),
+            _ => Err(format!(
+                "'{}' is no valid feature state. Valid states include: experimental, stable, deprecated, archived",
+                s
+            )),
+        }
+    }
+}
+
+/// Information about a feature that cannot be derived from git itself.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct FeatureMetadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    state: Option<FeatureState>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    owner: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+}
+
+impl FeatureMetadata {
+    pub fn new(
+        state: Option<FeatureState>,
+        owner: Option<String>,
+        description: Option<String>,
+    ) -> Self {
+        Self {
+            state,
+            owner,
+            description,
+        }
+    }
+    pub fn get_state(&self) -> Option<FeatureState> {
+        self.state
+    }
+    pub fn get_owner(&self) -> Option<&String> {
+        self.owner.as_ref()
+    }
+    pub fn get_description(&self) -> Option<&String> {
+        self.description.as_ref()
+    }
+    pub fn set_state(&mut self, state: Option<FeatureState>) {
+        self.state = state;
+    }
+    pub fn set_owner(&mut self, owner: Option<String>) {
+        self.owner = owner;
+    }
+    pub fn set_description(&mut self, description: Option<String>) {
+        self.description = description;
+    }
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// The persisted part of the tangl model, stored as JSON.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct MetadataStore {
+    #[serde(default)]
+    features: BTreeMap<String, FeatureMetadata>,
+    /// Cross-tree constraints referencing features by their qualified path.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    constraints: Vec<Constraint>,
+}
+
+impl MetadataStore {
+    pub fn from_json(data: &str) -> Result<Self, serde_json::Error> {
+        if data.trim().is_empty() {
+            return Ok(Self::default());
+        }
+        serde_json::from_str(data)
+    }
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+    pub fn get(&self, path: &QualifiedPath) -> FeatureMetadata {
+        self.features
+            .get(&path.to_string())
+            .cloned()
+            .unwrap_or_default()
+    }
+    pub fn set(&mut self, path: &QualifiedPath, metadata: FeatureMetadata) {
+        if metadata.is_empty() {
+            self.features.remove(&path.to_string());
+        } else {
+            self.features.insert(path.to_string(), metadata);
+        }
+    }
+    /// Moves the metadata of `from` and all paths below it to `to`.
+    pub fn rename(&mut self, from: &QualifiedPath, to: &QualifiedPath) {
+        let to_move = self
+            .iter()
+            .filter(|(path, _)| path.has_prefix(from))
+            .collect::<Vec<_>>();
+        for (path, metadata) in to_move {
+            self.features.remove(&path.to_string());
+            let new_path = to.clone() + path.strip_n_left(from.len());
+            self.set(&new_path, metadata);
+        }
+        self.constraints = self
+            .constraints
+            .iter()
+            .map(|constraint| {
+                constraint
+                    .map_features(&|name| {
+                        let path = QualifiedPath::from(name.to_string());
+                        Ok::<String, ()>(if path.has_prefix(from) {
+                            (to.clone() + path.strip_n_left(from.len())).to_string()
+                        } else {
+                            name.to_string()
+                        })
+                    })
+                    .unwrap()
+            })
+            .collect();
+    }
+    pub fn get_constraints(&self) -> &Vec<Constraint> {
+        &self.constraints
+    }
+    /// Splits the constraints into those that only reference features below `feature_root`
+    /// and all others.
+    pub fn partition_constraints(
+        &self,
+        feature_root: &QualifiedPath,
+    ) -> (Vec<Constraint>, Vec<Constraint>) {
+        self.constraints.iter().cloned().partition(|c| {
+            c.features()
+                .iter()
+                .all(|f| QualifiedPath::from(f.to_string()).has_prefix(feature_root))
+        })
+    }
+    pub fn set_constraints(&mut self, constraints: Vec<Constraint>) {
+        self.constraints = constraints;
+    }
+    pub fn iter(&self) -> impl Iterator<Item = (QualifiedPath, FeatureMetadata)> {
+        self.features
+            .iter()
+            .map(|(path, metadata)| (QualifiedPath::from(path.clone()), metadata.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metadata_store_round_trip() {
+        let mut store = MetadataStore::default();
+        let path = QualifiedPath::from("main/feature/root/foo");
+        let metadata = FeatureMetadata::new(
+            Some(FeatureState::Deprecated),
+            Some("alice".to_string()),
+            None,
+        );
+        store.set(&path, metadata.clone());
+        let loaded = MetadataStore::from_json(&store.to_json()).unwrap();
+        assert_eq!(loaded.get(&path), metadata);
+        assert!(store.to_json().contains("\"deprecated\""));
+        assert!(!store.to_json().contains("description"));
+    }
+
+    #[test]
+    fn test_metadata_store_rename() {
+        let mut store = MetadataStore::default();
+        let metadata = FeatureMetadata::new(Some(FeatureState::Stable), None, None);
+        store.set(&QualifiedPath::from("main/feature/root"), metadata.clone());
+        store.set(
+            &QualifiedPath::from("main/feature/root/foo"),
+            metadata.clone(),
+        );
+        store.set(
+            &QualifiedPath::from("main/feature/rootbar"),
+            metadata.clone(),
+        );
+        store.set_constraints(vec![
+            Constraint::parse("\"main/feature/root/foo\" => \"main/feature/rootbar\"").unwrap(),
+        ]);
+        store.rename(
+            &QualifiedPath::from("main/feature/root"),
+            &QualifiedPath::from("main/feature/base"),
+        );
+        let mut paths = store.iter().map(|(p, _)| p.to_string()).collect::<Vec<_>>();
+        paths.sort();
+        assert_eq!(
+            paths,
+            vec![
+                "main/feature/base",
+                "main/feature/base/foo",
+                "main/feature/rootbar"
+            ]
+        );
+        assert_eq!(
+            store.get_constraints()[0].to_string(),
+            "\"main/feature/base/foo\" => \"main/feature/rootbar\""
+        );
+    }
+}
@@ -1,11 +1,29 @@
 mod commit;
+mod conflict_cache;
+mod constraint;
+mod derivation;
+mod feature_model;
+mod formula;
+mod metadata;
+mod naming;
 mod node;
 mod node_path;
+mod product_config;
 mod qualified_path;
+mod sampling;
 mod tree;
 
 pub use commit::*;
+pub use conflict_cache::*;
+pub use constraint::*;
+pub use derivation::*;
+pub use feature_model::*;
+pub use formula::*;
+pub use metadata::*;
+pub use naming::*;
 pub use node::*;
 pub use node_path::*;
+pub use product_config::*;
 pub use qualified_path::*;
+pub use sampling::*;
 pub use tree::*;
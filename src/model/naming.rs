@@ -0,0 +1,172 @@
+use crate::model::*;
+use crate::util::matches_pattern;
+use serde::{Deserialize, Serialize};
+
+fn default_feature_root() -> String {
+    FEATURES_PREFIX.to_string()
+}
+
+fn default_product_root() -> String {
+    PRODUCTS_PREFIX.to_string()
+}
+
+/// The per-repository scheme that maps qualified paths to git refs.
+///
+/// It also decides which refs belong to tangl at all, so that unrelated branches like
+/// `release-1.0` can live in the same repository.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct NamingScheme {
+    /// Prepended to the names of all branches and tags of tangl, e.g. `tangl/`. The branches
+    /// stay in `refs/heads/`, so that they can be checked out and committed to.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub prefix: String,
+    /// The name of the node below an area that contains the features.
+    #[serde(default = "default_feature_root")]
+    pub feature_root: String,
+    /// The name of the node below an area that contains the products.
+    #[serde(default = "default_product_root")]
+    pub product_root: String,
+    /// The areas managed by tangl. All areas are managed if empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub areas: Vec<String>,
+    /// Patterns of branch and tag names that never belong to tangl, e.g. `release-*`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ignore: Vec<String>,
+}
+
+impl Default for NamingScheme {
+    fn default() -> Self {
+        Self {
+            prefix: String::new(),
+            feature_root: default_feature_root(),
+            product_root: default_product_root(),
+            areas: vec![],
+            ignore: vec![],
+        }
+    }
+}
+
+impl NamingScheme {
+    pub fn from_json(data: &str) -> Result<Self, serde_json::Error> {
+        if data.trim().is_empty() {
+            return Ok(Self::default());
+        }
+        serde_json::from_str(data)
+    }
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+    /// Returns the type of the child of an area called `name`.
+    pub fn get_root_type(&self, name: &str) -> Option<NodeType> {
+        if name == self.feature_root {
+            Some(NodeType::FeatureRoot)
+        } else if name == self.product_root {
+            Some(NodeType::ProductRoot)
+        } else {
+            None
+        }
+    }
+    /// Returns the name of the branch or tag of `path`, relative to its ref namespace.
+    pub fn to_ref_name(&self, path: &QualifiedPath) -> String {
+        self.prefix.clone() + path.to_git_branch().as_str()
+    }
+    /// Decodes a name created by [`NamingScheme::to_ref_name`].
+    pub fn from_ref_name(&self, name: &str) -> Result<QualifiedPath, InvalidPathError> {
+        let stripped = name.trim().strip_prefix(&self.prefix).ok_or_else(|| {
+            InvalidPathError::new(format!(
+                "'{}' is not a tangl branch: it lacks the prefix '{}'",
+                name, self.prefix
+            ))
+        })?;
+        QualifiedPath::from_git_branch(stripped)
+    }
+    pub fn is_ignored(&self, name: &str) -> bool {
+        self.ignore
+            .iter()
+            .any(|pattern| matches_pattern(pattern, name))
+    }
+    /// Checks that a branch or tag name is located in the namespace of tangl, i.e. that it
+    /// starts with the prefix and is not ignored.
+    pub fn is_in_namespace(&self, name: &str) -> bool {
+        name.starts_with(&self.prefix) && !self.is_ignored(name)
+    }
+    pub fn is_managed_area(&self, path: &QualifiedPath) -> bool {
+        match path.first() {
+            Some(area) => self.areas.is_empty() || self.areas.contains(&area.to_string()),
+            None => false,
+        }
+    }
+    /// Checks that `path` is located in one of the roots of its area.
+    pub fn check_root(&self, path: &QualifiedPath) -> Result<(), WrongNodeTypeError> {
+        match path.get(1).map(|root| root.to_string()) {
+            Some(root) if self.get_root_type(&root).is_none() => {
+                Err(WrongNodeTypeError::new(format!(
+                    "'{}' is neither the feature root '{}' nor the product root '{}'",
+                    root, self.feature_root, self.product_root
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+    /// Returns the path of a branch or tag name, or `None` if the ref does not belong to tangl.
+    pub fn parse_ref_name(&self, name: &str) -> Option<QualifiedPath> {
+        if !self.is_in_namespace(name) {
+            return None;
+        }
+        self.from_ref_name(name)
+            .ok()
+            .filter(|path| self.is_managed_area(path) && self.check_root(path).is_ok())
+    }
+    /// Guesses the path a malformed branch or tag name was meant to have, e.g. the path
+    /// `main/feature/a` for the branch `main/feature/a`, which lacks the `_` of inner segments.
+    ///
+    /// Returns `None` if there is no such path or the name is already well-formed.
+    pub fn repair_ref_name(&self, name: &str) -> Option<QualifiedPath> {
+        let stripped = name.trim().strip_prefix(&self.prefix)?;
+        let path = QualifiedPath::from_malformed_git_branch(stripped);
+        let is_valid = path.validate().is_ok()
+            && self.is_managed_area(&path)
+            && self.check_root(&path).is_ok();
+        (is_valid && self.to_ref_name(&path) != name).then_some(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_naming_scheme_parse_ref_name() {
+        let naming = NamingScheme::from_json(
+            r#"{"prefix": "tangl/", "feature_root": "features", "areas": ["main"], "ignore": ["tangl/*wip*"]}"#,
+        )
+        .unwrap();
+        assert_eq!(naming.product_root, "product");
+        let path = QualifiedPath::from("main/features/root");
+        assert_eq!(naming.to_ref_name(&path), "tangl/_main/_features/root");
+        assert_eq!(
+            naming.parse_ref_name("tangl/_main/_features/root"),
+            Some(path)
+        );
+        assert_eq!(
+            naming.parse_ref_name("tangl/main"),
+            Some(QualifiedPath::from("main"))
+        );
+        for foreign in [
+            "_main/_features/root",
+            "tangl/_main/_feature/root",
+            "tangl/_main/docs",
+            "tangl/_other/_features/root",
+            "tangl/_main/_features/wip-root",
+            "release-1.0",
+        ] {
+            assert_eq!(naming.parse_ref_name(foreign), None, "{}", foreign);
+        }
+        assert_eq!(
+            naming.repair_ref_name("tangl/main/features/root"),
+            Some(QualifiedPath::from("main/features/root"))
+        );
+        assert_eq!(naming.repair_ref_name("tangl/_main/_features/root"), None);
+        assert_eq!(naming.repair_ref_name("tangl/main/docs"), None);
+    }
+}
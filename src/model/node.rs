@@ -51,25 +51,23 @@ pub enum NodeType {
 }
 
 impl NodeType {
-    pub fn build_child_from_name(&mut self, name: &str) -> Result<NodeType, WrongNodeTypeError> {
+    pub fn build_child_from_name(
+        &mut self,
+        name: &str,
+        naming: &NamingScheme,
+    ) -> Result<NodeType, WrongNodeTypeError> {
         match self {
             Self::Feature => Ok(Self::Feature),
             Self::Product => Ok(Self::Product),
             Self::FeatureRoot => Ok(Self::Feature),
             Self::ProductRoot => Ok(Self::Product),
             Self::VirtualRoot => Ok(Self::Area),
-            Self::Area => {
-                if name.starts_with(FEATURES_PREFIX) {
-                    Ok(Self::FeatureRoot)
-                } else if name.starts_with(PRODUCTS_PREFIX) {
-                    Ok(Self::ProductRoot)
-                } else {
-                    Err(WrongNodeTypeError::new(format!(
-                        "'{}' is no valid child of an area node. Valid childs include: feature, product",
-                        name
-                    )))
-                }
-            }
+            Self::Area => naming.get_root_type(name).ok_or_else(|| {
+                WrongNodeTypeError::new(format!(
+                    "'{}' is no valid child of an area node. Valid childs include: {}, {}",
+                    name, naming.feature_root, naming.product_root
+                ))
+            }),
             Self::Tag => Err(WrongNodeTypeError::new("Tags cannot have children")),
         }
     }
@@ -86,19 +84,32 @@ impl NodeType {
 #[derive(Clone, Debug)]
 pub struct NodeMetadata {
     has_branch: bool,
+    feature_metadata: FeatureMetadata,
 }
 impl NodeMetadata {
     pub fn new(has_branch: bool) -> Self {
-        Self { has_branch }
+        Self {
+            has_branch,
+            feature_metadata: FeatureMetadata::default(),
+        }
     }
     pub fn default() -> Self {
         let i = "".to_string();
         drop(i);
-        Self { has_branch: false }
+        Self {
+            has_branch: false,
+            feature_metadata: FeatureMetadata::default(),
+        }
     }
     pub fn has_branch(&self) -> bool {
         self.has_branch
     }
+    pub fn get_feature_metadata(&self) -> &FeatureMetadata {
+        &self.feature_metadata
+    }
+    pub fn set_feature_metadata(&mut self, feature_metadata: FeatureMetadata) {
+        self.feature_metadata = feature_metadata;
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -107,31 +118,54 @@ pub struct Node {
     node_type: NodeType,
     metadata: NodeMetadata,
     children: HashMap<String, Rc<Node>>,
+    naming: Rc<NamingScheme>,
 }
 
 impl Node {
     pub fn new<S: Into<String>>(name: S, node_type: NodeType, metadata: NodeMetadata) -> Self {
+        Self::with_naming(name, node_type, metadata, Rc::new(NamingScheme::default()))
+    }
+    /// Creates a node whose subtree decides the types of its nodes with `naming`.
+    pub fn with_naming<S: Into<String>>(
+        name: S,
+        node_type: NodeType,
+        metadata: NodeMetadata,
+        naming: Rc<NamingScheme>,
+    ) -> Self {
         Self {
             name: name.into(),
             node_type,
             metadata,
             children: HashMap::new(),
+            naming,
         }
     }
     pub fn update_metadata(&mut self, metadata: NodeMetadata) {
         self.metadata = metadata;
     }
-    fn build_display_tree(&self, show_tags: bool) -> Tree<String> {
+    fn build_display_tree(
+        &self,
+        show_tags: bool,
+        path: &QualifiedPath,
+        annotations: &HashMap<QualifiedPath, String>,
+    ) -> Tree<String> {
         let mut formatted = ColoredString::from(self.name.clone());
         if self.metadata.has_branch {
             formatted = formatted.blue()
         }
         formatted = self.node_type.format_node_display(formatted);
-        let mut tree = Tree::<String>::new(formatted.to_string());
+        let mut label = formatted.to_string();
+        if let Some(state) = self.metadata.feature_metadata.get_state() {
+            label = format!("{} {}", label, state.format_state_display());
+        }
+        if let Some(annotation) = annotations.get(path) {
+            label = format!("{} {}", label, annotation);
+        }
+        let mut tree = Tree::<String>::new(label);
         let mut sorted_children = self.children.iter().collect::<Vec<_>>();
         sorted_children.sort_by(|a, b| b.0.chars().cmp(a.0.chars()));
         sorted_children.reverse();
-        for (_, child) in sorted_children {
+        for (name, child) in sorted_children {
             match child.node_type {
                 NodeType::Tag => {
                     if !show_tags {
@@ -140,7 +174,10 @@ impl Node {
                 }
                 _ => {}
             }
-            tree.leaves.push(child.build_display_tree(show_tags));
+            let mut child_path = path.clone();
+            child_path.push(name.clone());
+            tree.leaves
+                .push(child.build_display_tree(show_tags, &child_path, annotations));
         }
         tree
     }
@@ -154,11 +191,17 @@ impl Node {
         let new_type = if is_tag {
             NodeType::Tag
         } else {
-            self.node_type.build_child_from_name(real_name.as_str())?
+            self.node_type
+                .build_child_from_name(real_name.as_str(), &self.naming)?
         };
         self.children.insert(
             real_name.clone(),
-            Rc::new(Node::new(real_name, new_type, metadata)),
+            Rc::new(Node::with_naming(
+                real_name,
+                new_type,
+                metadata,
+                self.naming.clone(),
+            )),
         );
         Ok(())
     }
@@ -183,6 +226,9 @@ impl Node {
     pub fn get_metadata(&self) -> &NodeMetadata {
         &self.metadata
     }
+    pub fn get_naming(&self) -> &NamingScheme {
+        &self.naming
+    }
     pub fn get_child<S: Into<String>>(&self, name: S) -> Option<&Rc<Node>> {
         Some(self.children.get(&name.into())?)
     }
@@ -219,6 +265,21 @@ impl Node {
             }
         }
     }
+    /// Sets the feature metadata of the node at `path`. Returns false if there is no such node.
+    pub fn update_feature_metadata(
+        &mut self,
+        path: &QualifiedPath,
+        feature_metadata: FeatureMetadata,
+    ) -> bool {
+        if path.is_empty() {
+            self.metadata.set_feature_metadata(feature_metadata);
+            return true;
+        }
+        match self.get_child_mut(path.get(0).unwrap().to_string()) {
+            Some(child) => child.update_feature_metadata(&path.strip_n_left(1), feature_metadata),
+            None => false,
+        }
+    }
     pub fn as_qualified_path(&self) -> QualifiedPath {
         QualifiedPath::from(self.name.clone())
     }
@@ -255,7 +316,19 @@ impl Node {
         result
     }
     pub fn display_tree(&self, show_tags: bool) -> String {
-        self.build_display_tree(show_tags).to_string()
+        self.build_display_tree(show_tags, &QualifiedPath::new(), &HashMap::new())
+            .to_string()
+    }
+    /// Displays the tree with the annotations of the nodes appended to their labels. `path` is
+    /// the qualified path of this node, which the keys of `annotations` are matched against.
+    pub fn display_annotated_tree(
+        &self,
+        show_tags: bool,
+        path: &QualifiedPath,
+        annotations: &HashMap<QualifiedPath, String>,
+    ) -> String {
+        self.build_display_tree(show_tags, path, annotations)
+            .to_string()
     }
 }
 
@@ -1,4 +1,5 @@
 use crate::model::*;
+use std::collections::HashMap;
 use std::fmt::Debug;
 use std::marker::PhantomData;
 use std::rc::Rc;
@@ -41,19 +42,21 @@ impl NodePath<AnyNodeType> {
 
 impl NodePath<Area> {
     pub fn get_path_to_feature_root(&self) -> QualifiedPath {
-        self.get_qualified_path() + QualifiedPath::from(FEATURES_PREFIX)
+        self.get_qualified_path() + QualifiedPath::from(self.get_naming().feature_root.clone())
     }
     pub fn get_path_to_product_root(&self) -> QualifiedPath {
-        self.get_qualified_path() + QualifiedPath::from(PRODUCTS_PREFIX)
+        self.get_qualified_path() + QualifiedPath::from(self.get_naming().product_root.clone())
     }
     pub fn to_feature_root(self) -> Option<NodePath<FeatureRoot>> {
-        match self.to(&QualifiedPath::from(FEATURES_PREFIX))?.concretize() {
+        let name = QualifiedPath::from(self.get_naming().feature_root.clone());
+        match self.to(&name)?.concretize() {
             NodePathType::FeatureRoot(path) => Some(path),
             _ => unreachable!(),
         }
     }
     pub fn to_product_root(self) -> Option<NodePath<ProductRoot>> {
-        match self.to(&QualifiedPath::from(PRODUCTS_PREFIX))?.concretize() {
+        let name = QualifiedPath::from(self.get_naming().product_root.clone());
+        match self.to(&name)?.concretize() {
             NodePathType::ProductRoot(path) => Some(path),
             _ => unreachable!(),
         }
@@ -107,6 +110,9 @@ impl<T: Clone + Debug> NodePath<T> {
             })
             .collect()
     }
+    pub fn get_naming(&self) -> &NamingScheme {
+        self.get_node().get_naming()
+    }
     pub fn get_metadata(&self) -> &NodeMetadata {
         self.get_node().get_metadata()
     }
@@ -129,9 +135,25 @@ impl<T: Clone + Debug> NodePath<T> {
         }
         path
     }
+    /// Returns the path of the closest ancestor that has a branch, e.g. the parent feature
+    /// of a feature or the area of a top-level feature.
+    pub fn get_parent_with_branch(&self) -> Option<QualifiedPath> {
+        let index = self.path[..self.path.len() - 1]
+            .iter()
+            .rposition(|node| node.get_metadata().has_branch())?;
+        Some(self.get_qualified_path().strip_n_right(index + 1))
+    }
     pub fn display_tree(&self, show_tags: bool) -> String {
         self.get_node().display_tree(show_tags)
     }
+    pub fn display_annotated_tree(
+        &self,
+        show_tags: bool,
+        annotations: &HashMap<QualifiedPath, String>,
+    ) -> String {
+        self.get_node()
+            .display_annotated_tree(show_tags, &self.get_qualified_path(), annotations)
+    }
 }
 
 pub trait NodePathTransformer<A, B>
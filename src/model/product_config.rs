@@ -0,0 +1,161 @@
+use crate::model::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProductOptions {
+    /// Replaces an existing product branch instead of refusing to derive it.
+    #[serde(default)]
+    pub replace: bool,
+    /// Adds features implied by the constraints of the feature model to the selection.
+    #[serde(default)]
+    pub complete: bool,
+    /// A command that must succeed on the merged tree of every pair of selected features.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verify: Option<String>,
+}
+
+/// A declarative feature selection that can be derived reproducibly.
+///
+/// Product and features are relative to the product and feature root of the area.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProductConfig {
+    pub product: String,
+    pub area: String,
+    pub features: Vec<String>,
+    #[serde(default)]
+    pub options: ProductOptions,
+}
+
+impl ProductConfig {
+    pub fn new(product: QualifiedPath, area: QualifiedPath, features: Vec<QualifiedPath>) -> Self {
+        Self {
+            product: product.to_string(),
+            area: area.to_string(),
+            features: features.iter().map(|f| f.to_string()).collect(),
+            options: ProductOptions::default(),
+        }
+    }
+    pub fn from_json(data: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(data)
+    }
+    pub fn get_area_path(&self) -> QualifiedPath {
+        QualifiedPath::from(self.area.clone())
+    }
+    pub fn get_product_path(&self, naming: &NamingScheme) -> QualifiedPath {
+        self.get_area_path()
+            + QualifiedPath::from(naming.product_root.clone())
+            + QualifiedPath::from(self.product.clone())
+    }
+    pub fn get_feature_paths(&self, naming: &NamingScheme) -> Vec<QualifiedPath> {
+        let feature_root = self.get_area_path() + QualifiedPath::from(naming.feature_root.clone());
+        self.features
+            .iter()
+            .map(|f| feature_root.clone() + QualifiedPath::from(f.clone()))
+            .collect()
+    }
+    /// Replaces the prefix `from` by `to` in all features of the configuration.
+    pub fn rename_feature(
+        &mut self,
+        from: &QualifiedPath,
+        to: &QualifiedPath,
+        naming: &NamingScheme,
+    ) -> bool {
+        let record = DerivationRecord::new(self.get_feature_paths(naming));
+        if !record.references(from) {
+            return false;
+        }
+        let strip = self.get_area_path().len() + 1;
+        self.features = record
+            .rename(from, to)
+            .features()
+            .iter()
+            .map(|f| f.strip_n_left(strip).to_string())
+            .collect();
+        true
+    }
+}
+
+/// All product configurations of a repository, keyed by the qualified path of the product.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ProductConfigStore {
+    #[serde(default)]
+    products: BTreeMap<String, ProductConfig>,
+}
+
+impl ProductConfigStore {
+    pub fn from_json(data: &str) -> Result<Self, serde_json::Error> {
+        if data.trim().is_empty() {
+            return Ok(Self::default());
+        }
+        serde_json::from_str(data)
+    }
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+    pub fn get(&self, product: &QualifiedPath) -> Option<&ProductConfig> {
+        self.products.get(&product.to_string())
+    }
+    pub fn set(&mut self, config: ProductConfig, naming: &NamingScheme) {
+        self.products
+            .insert(config.get_product_path(naming).to_string(), config);
+    }
+    pub fn remove(&mut self, product: &QualifiedPath) -> Option<ProductConfig> {
+        self.products.remove(&product.to_string())
+    }
+    pub fn iter(&self) -> impl Iterator<Item = &ProductConfig> {
+        self.products.values()
+    }
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut ProductConfig> {
+        self.products.values_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_product_config_paths() {
+        let config = ProductConfig::from_json(
+            r#"{"product": "pro", "area": "main", "features": ["root/foo", "root/bar"]}"#,
+        )
+        .unwrap();
+        assert!(!config.options.replace);
+        let naming = NamingScheme::default();
+        assert_eq!(
+            config.get_product_path(&naming),
+            QualifiedPath::from("main/product/pro")
+        );
+        assert_eq!(
+            config.get_feature_paths(&naming),
+            vec![
+                QualifiedPath::from("main/feature/root/foo"),
+                QualifiedPath::from("main/feature/root/bar"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_product_config_rename_feature() {
+        let mut config = ProductConfig::new(
+            QualifiedPath::from("pro"),
+            QualifiedPath::from("main"),
+            vec![
+                QualifiedPath::from("root/foo"),
+                QualifiedPath::from("root/foobar"),
+            ],
+        );
+        assert!(config.rename_feature(
+            &QualifiedPath::from("main/feature/root/foo"),
+            &QualifiedPath::from("main/feature/base/foo"),
+            &NamingScheme::default(),
+        ));
+        assert_eq!(config.features, vec!["base/foo", "root/foobar"]);
+        assert!(!config.rename_feature(
+            &QualifiedPath::from("main/feature/other"),
+            &QualifiedPath::from("main/feature/base"),
+            &NamingScheme::default(),
+        ));
+    }
+}
@@ -1,8 +1,81 @@
 use colored::Colorize;
+use std::error::Error;
 use std::fmt::{Display, Formatter};
 use std::ops::Add;
 
 const SEPARATOR: char = '/';
+/// Marks segments of a git branch name that have children.
+const INNER_MARKER: char = '_';
+const ESCAPE: char = '%';
+
+#[derive(Debug, Clone)]
+pub struct InvalidPathError {
+    msg: String,
+}
+impl InvalidPathError {
+    pub fn new<S: Into<String>>(msg: S) -> InvalidPathError {
+        InvalidPathError { msg: msg.into() }
+    }
+}
+impl Display for InvalidPathError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.msg)
+    }
+}
+impl Error for InvalidPathError {}
+
+/// Escapes every character of a segment that git does not allow in ref names at its position,
+/// a leading [`INNER_MARKER`] and the escape character itself as `%XX`.
+fn encode_segment(segment: &str) -> String {
+    let chars = segment.chars().collect::<Vec<_>>();
+    let mut encoded = String::new();
+    for (i, c) in chars.iter().enumerate() {
+        let next = chars.get(i + 1);
+        let escape = match c {
+            '%' | '~' | '^' | ':' | '?' | '*' | '[' | '\\' | ' ' => true,
+            c if c.is_ascii_control() => true,
+            '_' => i == 0,
+            '.' => i == 0 || i == chars.len() - 1 || next == Some(&'.'),
+            '@' => next == Some(&'{') || chars.len() == 1,
+            _ => false,
+        };
+        if escape {
+            let mut buffer = [0; 4];
+            for byte in c.encode_utf8(&mut buffer).bytes() {
+                encoded.push_str(format!("{}{:02X}", ESCAPE, byte).as_str());
+            }
+        } else {
+            encoded.push(*c);
+        }
+    }
+    match encoded.strip_suffix(".lock") {
+        Some(stem) => format!("{}{}2Elock", stem, ESCAPE),
+        None => encoded,
+    }
+}
+
+fn decode_segment(segment: &str) -> Result<String, InvalidPathError> {
+    let bytes = segment.as_bytes();
+    let mut decoded = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == ESCAPE as u8 {
+            let byte = segment
+                .get(i + 1..i + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                .ok_or_else(|| {
+                    InvalidPathError::new(format!("Malformed escape sequence in '{}'", segment))
+                })?;
+            decoded.push(byte);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded)
+        .map_err(|_| InvalidPathError::new(format!("Invalid unicode in '{}'", segment)))
+}
 
 #[derive(Clone, Debug, Hash, Eq, Ord, PartialOrd)]
 pub struct QualifiedPath {
@@ -10,9 +83,8 @@ pub struct QualifiedPath {
 }
 impl From<String> for QualifiedPath {
     fn from(value: String) -> Self {
-        let qualified_str = value.replace("*", "").replace("_", "");
         let mut qualified_path = Self::new();
-        qualified_path.push(qualified_str.trim().to_string());
+        qualified_path.push(value.trim().to_string());
         qualified_path
     }
 }
@@ -103,20 +175,85 @@ impl QualifiedPath {
     pub fn new() -> Self {
         Self { path: Vec::new() }
     }
+    /// Encodes the path as git branch name. Every segment but the last is prefixed with `_`, so
+    /// that a branch can have children, and characters git does not allow are escaped.
+    /// [`QualifiedPath::from_git_branch`] reverses the encoding.
     pub fn to_git_branch(&self) -> String {
         let trimmed_path = self.trim_whitespaces();
         let path = trimmed_path.path;
-        match path.len() {
-            1 => path[0].to_string(),
-            _ => {
-                let mut prefix = path[..path.len() - 1]
-                    .iter()
-                    .map(|x| "_".to_string() + x)
-                    .collect::<Vec<_>>();
-                prefix.push(path[path.len() - 1].to_string());
-                prefix.join("/")
+        path.iter()
+            .enumerate()
+            .map(|(i, segment)| {
+                let encoded = encode_segment(segment);
+                if i + 1 < path.len() {
+                    INNER_MARKER.to_string() + encoded.as_str()
+                } else {
+                    encoded
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+    /// Decodes a git branch name created by [`QualifiedPath::to_git_branch`].
+    pub fn from_git_branch(branch: &str) -> Result<QualifiedPath, InvalidPathError> {
+        let segments = branch.trim().split(SEPARATOR).collect::<Vec<_>>();
+        let mut path = Vec::new();
+        for (i, segment) in segments.iter().enumerate() {
+            let segment = if i + 1 < segments.len() {
+                segment.strip_prefix(INNER_MARKER).ok_or_else(|| {
+                    InvalidPathError::new(format!(
+                        "'{}' is not a tangl branch: segment '{}' lacks the '{}' prefix",
+                        branch, segment, INNER_MARKER
+                    ))
+                })?
+            } else {
+                segment
+            };
+            if segment.is_empty() {
+                return Err(InvalidPathError::new(format!(
+                    "'{}' is not a tangl branch: empty segment",
+                    branch
+                )));
+            }
+            path.push(decode_segment(segment)?);
+        }
+        Ok(QualifiedPath { path })
+    }
+    /// Decodes a branch name that was not created by [`QualifiedPath::to_git_branch`] as well as
+    /// possible: the `_` of inner segments is optional and malformed escapes are kept as is.
+    pub fn from_malformed_git_branch(branch: &str) -> QualifiedPath {
+        let segments = branch.trim().split(SEPARATOR).collect::<Vec<_>>();
+        let path = segments
+            .iter()
+            .enumerate()
+            .map(|(i, segment)| {
+                let segment = match segment.strip_prefix(INNER_MARKER) {
+                    Some(stripped) if i + 1 < segments.len() => stripped,
+                    _ => segment,
+                };
+                decode_segment(segment).unwrap_or(segment.to_string())
+            })
+            .collect();
+        QualifiedPath { path }
+    }
+    /// Checks that every segment can be used as a name of a node.
+    pub fn validate(&self) -> Result<(), InvalidPathError> {
+        for segment in self.path.iter() {
+            let reason = match segment.as_str() {
+                "" => Some("names must not be empty"),
+                "." | ".." => Some("'.' and '..' are reserved for relative paths"),
+                s if s.trim() != s => Some("names must not start or end with whitespace"),
+                _ => None,
+            };
+            if let Some(reason) = reason {
+                return Err(InvalidPathError::new(format!(
+                    "Invalid name '{}': {}",
+                    self.to_string(),
+                    reason
+                )));
             }
         }
+        Ok(())
     }
     pub fn to_string(&self) -> String {
         self.path.join("/")
@@ -181,6 +318,10 @@ impl QualifiedPath {
     pub fn starts_with(&self, prefix: &QualifiedPath) -> bool {
         self.to_string().starts_with(&prefix.to_string())
     }
+    /// Returns true if the first segments of this path equal all segments of `prefix`.
+    pub fn has_prefix(&self, prefix: &QualifiedPath) -> bool {
+        self.len() >= prefix.len() && self.path[..prefix.len()] == prefix.path[..]
+    }
     pub fn last_is(&self, suffix: &QualifiedPath) -> bool {
         self.last() == suffix.last()
     }
@@ -207,14 +348,72 @@ mod tests {
 
     #[test]
     fn test_qualified_path_from_git_branch() {
-        assert_eq!(QualifiedPath::from("_foo/bar").path, vec!["foo", "bar"]);
-        assert_eq!(
-            QualifiedPath::from("_foo/bar".to_string()).path,
-            vec!["foo", "bar"]
+        let from_branch = |branch: &str| QualifiedPath::from_git_branch(branch).unwrap().path;
+        assert_eq!(from_branch("_foo/bar"), vec!["foo", "bar"]);
+        assert_eq!(from_branch("_foo/_bar/baz"), vec!["foo", "bar", "baz"]);
+        assert_eq!(from_branch("_dark_mode/a_b"), vec!["dark_mode", "a_b"]);
+        assert!(QualifiedPath::from_git_branch("foo/bar").is_err());
+        assert!(QualifiedPath::from_git_branch("_foo/b%zz").is_err());
+        assert!(QualifiedPath::from_git_branch("_foo//bar").is_err());
+        let from_malformed = |branch: &str| QualifiedPath::from_malformed_git_branch(branch).path;
+        assert_eq!(from_malformed("foo/_bar/baz"), vec!["foo", "bar", "baz"]);
+        assert_eq!(from_malformed("_foo/b%zz"), vec!["foo", "b%zz"]);
+    }
+
+    #[test]
+    fn test_qualified_path_git_branch_round_trip() {
+        for name in [
+            "dark_mode",
+            "_hidden",
+            "v1.0",
+            ".config",
+            "a..b",
+            "end.",
+            "file.lock",
+            "50%",
+            "what?",
+            "a b",
+            "@",
+            "x@{y",
+            "grüße",
+            "日本",
+        ] {
+            let path = QualifiedPath::from(vec!["main".to_string(), name.to_string()]);
+            let branch = path.to_git_branch();
+            assert!(!branch.contains(".."), "{}", branch);
+            assert!(
+                !branch.ends_with(".lock") && !branch.ends_with('.'),
+                "{}",
+                branch
+            );
+            assert!(
+                !branch.contains("/.") && !branch.contains(' '),
+                "{}",
+                branch
+            );
+            assert_eq!(QualifiedPath::from_git_branch(&branch).unwrap(), path);
+            let inner = path.clone() + QualifiedPath::from("child");
+            assert_eq!(
+                QualifiedPath::from_git_branch(&inner.to_git_branch()).unwrap(),
+                inner
+            );
+        }
+        // a leaf starting with the marker must not collide with an inner segment
+        assert_eq!(QualifiedPath::from("main/_x").to_git_branch(), "_main/%5Fx");
+    }
+
+    #[test]
+    fn test_qualified_path_validate() {
+        assert!(
+            QualifiedPath::from("main/feature/dark_mode")
+                .validate()
+                .is_ok()
         );
-        assert_eq!(
-            QualifiedPath::from("_foo/_bar/baz").path,
-            vec!["foo", "bar", "baz"]
+        assert!(QualifiedPath::from("main//foo").validate().is_err());
+        assert!(
+            QualifiedPath::from(vec!["main".to_string(), "..".to_string()])
+                .validate()
+                .is_err()
         );
     }
 
@@ -300,6 +499,15 @@ mod tests {
         assert_eq!(r + l, QualifiedPath::from("foo/"));
     }
 
+    #[test]
+    fn test_qualified_path_has_prefix() {
+        let path = QualifiedPath::from("foo/bar/baz");
+        assert!(path.has_prefix(&QualifiedPath::from("foo/bar")));
+        assert!(path.has_prefix(&QualifiedPath::new()));
+        assert!(!path.has_prefix(&QualifiedPath::from("foo/ba")));
+        assert!(!QualifiedPath::from("foo").has_prefix(&path));
+    }
+
     #[test]
     fn test_qualified_path_trim() {
         let path = QualifiedPath::from("foo/bar");
@@ -0,0 +1,129 @@
+use crate::model::*;
+
+/// A combination of features, each either selected or deselected.
+pub type Interaction = Vec<(QualifiedPath, bool)>;
+
+fn combinations(n: usize, t: usize) -> Vec<Vec<usize>> {
+    if t == 0 {
+        return vec![vec![]];
+    }
+    if n < t {
+        return vec![];
+    }
+    let mut result = combinations(n - 1, t);
+    for mut combination in combinations(n - 1, t - 1) {
+        combination.push(n - 1);
+        result.push(combination);
+    }
+    result
+}
+
+/// Returns every interaction of `t` features that is part of at least one valid configuration.
+pub fn get_feasible_interactions(
+    formula: &FeatureModelFormula,
+    features: &[QualifiedPath],
+    t: usize,
+) -> Vec<Interaction> {
+    let mut interactions = Vec::new();
+    for combination in combinations(features.len(), t) {
+        for polarity in 0..1usize << t {
+            let interaction = combination
+                .iter()
+                .enumerate()
+                .map(|(i, feature)| (features[*feature].clone(), polarity & (1 << i) == 0))
+                .collect::<Interaction>();
+            if formula.find_configuration(&interaction).is_some() {
+                interactions.push(interaction);
+            }
+        }
+    }
+    interactions
+}
+
+fn covers(configuration: &[QualifiedPath], interaction: &Interaction) -> bool {
+    interaction
+        .iter()
+        .all(|(feature, selected)| configuration.contains(feature) == *selected)
+}
+
+/// Greedily computes valid configurations that together cover every feasible interaction of
+/// `t` of the given features. Each configuration is restricted to the given features.
+pub fn sample_t_wise(
+    formula: &FeatureModelFormula,
+    features: &[QualifiedPath],
+    t: usize,
+) -> Vec<Vec<QualifiedPath>> {
+    let mut uncovered = get_feasible_interactions(formula, features, t);
+    let mut samples = Vec::new();
+    while !uncovered.is_empty() {
+        let mut assumptions: Interaction = Vec::new();
+        let mut configuration = None;
+        for interaction in uncovered.iter() {
+            let contradicts = interaction.iter().any(|(feature, selected)| {
+                assumptions
+                    .iter()
+                    .any(|(other, value)| feature == other && selected != value)
+            });
+            if contradicts {
+                continue;
+            }
+            let mut candidate = assumptions.clone();
+            candidate.extend(
+                interaction
+                    .iter()
+                    .filter(|assumption| !assumptions.contains(assumption))
+                    .cloned(),
+            );
+            if let Some(found) = formula.find_configuration(&candidate) {
+                assumptions = candidate;
+                configuration = Some(found);
+            }
+        }
+        // the first uncovered interaction is feasible, so there is always a configuration
+        let configuration = configuration.unwrap();
+        uncovered.retain(|interaction| !covers(&configuration, interaction));
+        samples.push(
+            configuration
+                .into_iter()
+                .filter(|feature| features.contains(feature))
+                .collect(),
+        );
+    }
+    samples
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths(features: &[&str]) -> Vec<QualifiedPath> {
+        features
+            .iter()
+            .map(|f| QualifiedPath::from("main/feature") + QualifiedPath::from(*f))
+            .collect()
+    }
+
+    #[test]
+    fn test_sample_pairwise() {
+        let features = paths(&["root", "root/foo", "root/bar", "root/baz"]);
+        let constraints = vec![
+            Constraint::parse("\"main/feature/root/bar\" => !\"main/feature/root/baz\"").unwrap(),
+        ];
+        let formula = FeatureModelFormula::new(
+            features.clone(),
+            &QualifiedPath::from("main/feature"),
+            &constraints,
+        );
+        let samples = sample_t_wise(&formula, &features, 2);
+        for interaction in get_feasible_interactions(&formula, &features, 2) {
+            assert!(samples.iter().any(|sample| covers(sample, &interaction)));
+        }
+        for sample in samples.iter() {
+            assert!(formula.violated(sample).is_empty());
+        }
+        // 4 features have 24 pairwise interactions, of which 3 violate the hierarchy and one
+        // violates the constraint
+        assert_eq!(get_feasible_interactions(&formula, &features, 2).len(), 20);
+        assert!(samples.len() < 20);
+    }
+}
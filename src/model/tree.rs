@@ -1,20 +1,53 @@
 use crate::model::*;
+use std::collections::BTreeMap;
+use std::rc::Rc;
 
+/// The default name of the feature root of an area.
 pub const FEATURES_PREFIX: &str = "feature";
+/// The default name of the product root of an area.
 pub const PRODUCTS_PREFIX: &str = "product";
 
+/// A ref in one of the namespaces of tangl that does not fit into the tree.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ForeignRef {
+    /// The namespace of the ref, e.g. `refs/heads/`.
+    pub namespace: String,
+    /// The name of the ref relative to its namespace.
+    pub name: String,
+    /// Why the ref was not inserted into the tree.
+    pub reason: String,
+}
+
 #[derive(Clone, Debug)]
 pub struct TreeDataModel {
     virtual_root: Node,
     qualified_paths_with_branch: Vec<QualifiedPath>,
+    archived_paths: Vec<QualifiedPath>,
+    foreign_refs: Vec<ForeignRef>,
+    /// The paths that have a remote-tracking branch, by remote.
+    remote_branches: BTreeMap<String, Vec<QualifiedPath>>,
 }
 impl TreeDataModel {
     pub fn new() -> Self {
+        Self::with_naming(NamingScheme::default())
+    }
+    pub fn with_naming(naming: NamingScheme) -> Self {
         Self {
-            virtual_root: Node::new("", NodeType::VirtualRoot, NodeMetadata::default()),
+            virtual_root: Node::with_naming(
+                "",
+                NodeType::VirtualRoot,
+                NodeMetadata::default(),
+                Rc::new(naming),
+            ),
             qualified_paths_with_branch: vec![],
+            archived_paths: vec![],
+            foreign_refs: vec![],
+            remote_branches: BTreeMap::new(),
         }
     }
+    pub fn get_naming(&self) -> &NamingScheme {
+        self.virtual_root.get_naming()
+    }
     pub fn insert_qualified_path(
         &mut self,
         path: QualifiedPath,
@@ -25,11 +58,57 @@ impl TreeDataModel {
         self.qualified_paths_with_branch.push(path);
         Ok(())
     }
+    /// Inserts a feature whose branch was moved to the archive namespace.
+    pub fn insert_archived_path(&mut self, path: QualifiedPath) -> Result<(), WrongNodeTypeError> {
+        self.virtual_root
+            .insert_node_path(&path, NodeMetadata::default(), false)?;
+        self.archived_paths.push(path);
+        Ok(())
+    }
+    pub fn insert_foreign_ref(&mut self, foreign_ref: ForeignRef) {
+        self.foreign_refs.push(foreign_ref);
+    }
+    pub fn get_foreign_refs(&self) -> &Vec<ForeignRef> {
+        &self.foreign_refs
+    }
+    pub fn insert_remote_branch(&mut self, remote: &str, path: QualifiedPath) {
+        self.remote_branches
+            .entry(remote.to_string())
+            .or_default()
+            .push(path);
+    }
+    pub fn get_remotes(&self) -> Vec<String> {
+        self.remote_branches.keys().cloned().collect()
+    }
+    pub fn get_remote_branches(&self, remote: &str) -> &[QualifiedPath] {
+        self.remote_branches
+            .get(remote)
+            .map(|paths| paths.as_slice())
+            .unwrap_or(&[])
+    }
+    pub fn has_remote_branch(&self, remote: &str, path: &QualifiedPath) -> bool {
+        self.get_remote_branches(remote).contains(path)
+    }
+    pub fn update_feature_metadata(
+        &mut self,
+        path: &QualifiedPath,
+        feature_metadata: FeatureMetadata,
+    ) -> bool {
+        self.virtual_root
+            .update_feature_metadata(path, feature_metadata)
+    }
     pub fn get_area(&self, path: &QualifiedPath) -> Option<NodePath<Area>> {
         Some(NodePath::<Area>::new(
             self.virtual_root.get_child(path.first()?)?.clone(),
         ))
     }
+    pub fn get_areas(&self) -> Vec<NodePath<Area>> {
+        self.virtual_root
+            .iter_children()
+            .filter(|(_, child)| matches!(child.get_type(), NodeType::Area))
+            .map(|(_, child)| NodePath::<Area>::new(child.clone()))
+            .collect()
+    }
     pub fn get_node_path(&self, path: &QualifiedPath) -> Option<NodePath<AnyNodeType>> {
         let initial_path = self.get_area(&path.first()?)?;
         let new_path = path.strip_n_left(1);
@@ -41,7 +120,18 @@ impl TreeDataModel {
             .find(|e| *e == qualified_path)
             .is_some()
     }
+    /// Returns the closest proper ancestor of `path` that has a branch. Unlike
+    /// [`NodePath::get_parent_with_branch`], `path` does not need to exist yet.
+    pub fn get_parent_with_branch(&self, path: &QualifiedPath) -> Option<QualifiedPath> {
+        (1..path.len())
+            .rev()
+            .map(|n| path.strip_n_right(n))
+            .find(|ancestor| self.has_branch(ancestor))
+    }
     pub fn get_qualified_paths_with_branches(&self) -> &Vec<QualifiedPath> {
         &self.qualified_paths_with_branch
     }
+    pub fn is_archived(&self, qualified_path: &QualifiedPath) -> bool {
+        self.archived_paths.contains(qualified_path)
+    }
 }
@@ -1,3 +1,41 @@
 pub fn u8_to_string(source: &Vec<u8>) -> String {
     String::from(std::str::from_utf8(source).unwrap())
 }
+
+/// Matches `name` against `pattern`, where `*` matches any sequence and `?` any character.
+pub fn matches_pattern(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let name = name.chars().collect::<Vec<_>>();
+    let (mut p, mut n) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, n));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            p = star + 1;
+            n = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pattern_matching() {
+        assert!(matches_pattern("pro/*", "pro/a"));
+        assert!(matches_pattern("*", "pro/a/b"));
+        assert!(matches_pattern("p?o/*b", "pro/a/b"));
+        assert!(!matches_pattern("pro/*", "other/a"));
+        assert!(!matches_pattern("pro", "pro/a"));
+    }
+}